@@ -1,4 +1,5 @@
 #![allow(non_snake_case)]
+#![allow(clippy::needless_return)]
 
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 use std::f32;
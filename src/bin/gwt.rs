@@ -0,0 +1,252 @@
+#![allow(non_snake_case)]
+
+use std::env;
+use std::io::{BufRead, IsTerminal, Write, stdin, stdout};
+use std::path::Path;
+use std::process::exit;
+use std::sync::{Arc, Mutex};
+
+use engine::{Engine, GameState};
+use engine::actions::Action;
+use engine::agent::{Agent, AgentSpec};
+use engine::board::Board;
+use engine::cli::Usage;
+use engine::data::GameData;
+use engine::deck::Card;
+use engine::describe::Describe;
+use engine::events::GameEvent;
+use engine::narration::Narrator;
+use engine::notation::Notation;
+use engine::player::Employee;
+use engine::rules::{KansasCityStep, Phase};
+use engine::savegame::SaveContext;
+use engine::view::PlayerView;
+
+const USAGE: Usage = Usage("Usage: gwt [--players N] [--seed N] [--bot SEAT:AGENT]... [--load FILE]
+Plays a game in the terminal, seats are numbered from 1 and are played at the keyboard unless given to a bot,
+which is random, mcts:<iterations> or a heuristic strategy name. A loaded game keeps the data and seed it was
+saved with. On your turn enter an action's number or notation, board, undo, redo, save FILE or quit");
+
+fn bot(value: Option<String>) -> (usize, AgentSpec) {
+    let value = value.unwrap_or_else(|| { USAGE.fail("--bot needs SEAT:AGENT") });
+    let (seat, agent) = value.split_once(':').unwrap_or_else(|| { USAGE.fail("--bot needs SEAT:AGENT") });
+    let seat = match seat.parse::<usize>() {
+        Ok(seat) if seat > 0 => seat - 1,
+        _ => USAGE.fail(&format!("{} isn't a seat", seat)),
+    };
+    (seat, AgentSpec::parse(agent).unwrap_or_else(|e| { USAGE.fail(&e.to_string()) }))
+}
+
+fn phase(view: &PlayerView) -> String {
+    match view.phase() {
+        Phase::Move => "moving the cattleman".to_string(),
+        Phase::Location { .. } => format!("at space {}", view.me().cattleman_pos),
+        Phase::KansasCity(KansasCityStep::Foresight(column)) => format!("in Kansas City, choosing from column {}", column + 1),
+        Phase::KansasCity(KansasCityStep::Income) => "in Kansas City, taking income".to_string(),
+        Phase::KansasCity(KansasCityStep::Delivery { value }) => format!("in Kansas City, delivering for ${}", value),
+        Phase::KansasCity(KansasCityStep::Done) => "in Kansas City, refilling the hand".to_string(),
+    }
+}
+
+// What the seat about to choose can see of the game
+fn render(view: &PlayerView, notation: &Notation) -> String {
+    let me = view.me();
+    let mut lines = vec![format!("== Player {}, {} ==", view.seat() + 1, phase(view))];
+    if let GameState::FinalRound { .. } = view.state() {
+        lines.push("This is the final round".to_string());
+    }
+    lines.push(format!("${}, {} certificates, {} Cowboys, {} Craftsmen, {} Engineers", me.dollars, me.certificates,
+                       me.hired(Employee::Cowboy), me.hired(Employee::Craftsman), me.hired(Employee::Engineer)));
+    let hand: Vec<String> = me.deck.hand.iter().flatten().map(|c| { card(notation, c) }).collect();
+    lines.push(format!("Hand: {} (worth ${})", hand.join(", "), me.deck.handValue()));
+    for (i, objective) in me.deck.objectiveCards().iter().enumerate() {
+        lines.push(format!("Objective in hand {}: {}", i + 1, objective.describe()));
+    }
+    let cows: Vec<String> = view.cowMarket().iter().map(|c| { Notation::cow(c) }).collect();
+    lines.push(format!("Cow market: {}", cows.join(", ")));
+    for seat in (0..view.playerCount() as u32).filter(|s| { *s != view.seat() }) {
+        let other = view.seatView(seat);
+        lines.push(format!("Player {}: ${}, at space {}, {} cards in hand, {} certificates", seat + 1, other.dollars,
+                           other.cattleman_pos, other.deck.hand_len, other.certificates));
+    }
+    lines.join("\n")
+}
+
+// Cards and actions which aren't in the game data are printed as they're stored
+fn card(notation: &Notation, card: &Card) -> String {
+    notation.card(card).unwrap_or_else(|_| { format!("{:?}", card) })
+}
+
+fn printed(notation: &Notation, action: &Action) -> String {
+    notation.print(action).unwrap_or_else(|_| { format!("{:?}", action) })
+}
+
+// The action's notation, with what it does when that isn't obvious from the notation
+fn label(engine: &Engine, notation: &Notation, action: &Action) -> String {
+    let player = &engine.players()[engine.currentPlayer()];
+    let detail = match action {
+        Action::UseBuilding(or, xor) => engine.track()[player.cattleManPos()].building()
+            .and_then(|b| { b.actions().items[*or as usize] })
+            .and_then(|options| { options.items[*xor as usize] })
+            .map(|and| { and.describe() }),
+        Action::Auxiliary1(i) => player.board().auxAction(*i as usize).availableActions().0.map(|t| { t.describe() }),
+        Action::Auxiliary2(i) => player.board().auxAction(*i as usize).availableActions().1.map(|t| { t.describe() }),
+        _ => None,
+    };
+    match detail {
+        Some(detail) => format!("{}  ({})", printed(notation, action), detail),
+        None => printed(notation, action),
+    }
+}
+
+fn main() {
+    let mut players = 2;
+    let mut seed = 0;
+    let mut load = None;
+    let mut bots = Vec::<(usize, AgentSpec)>::new();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--players" => players = USAGE.number(&arg, args.next()),
+            "--seed" => seed = USAGE.number(&arg, args.next()),
+            "--bot" => bots.push(bot(args.next())),
+            "--load" => load = Some(args.next().unwrap_or_else(|| { USAGE.fail("--load needs a file") })),
+            "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            other => USAGE.fail(&format!("Unknown argument {}", other)),
+        }
+    }
+    let embedded = || { GameData::embedded().unwrap_or_else(|e| { USAGE.fail(&e.to_string()) }) };
+    // Saves from before the data and seed were kept are played with the embedded data and --seed
+    let (mut engine, context) = match load {
+        Some(path) => Engine::loadWith(Path::new(&path)),
+        None => Engine::newGame(&embedded(), players, seed).map(|engine| { (engine, SaveContext::default()) }),
+    }.unwrap_or_else(|e| { USAGE.fail(&e.to_string()) });
+    let data = context.data.unwrap_or_else(embedded);
+    let seed = context.seed.unwrap_or(seed);
+    let context = SaveContext { data: Some(data.clone()), seed: Some(seed) };
+    let players = engine.players().len();
+    let mut agents: Vec<Option<Box<dyn Agent>>> = (0..players).map(|_| { None }).collect();
+    for (seat, spec) in bots.iter() {
+        if *seat >= players {
+            USAGE.fail(&format!("There's no seat {} in a {} player game", seat + 1, players));
+        }
+        agents[*seat] = Some(spec.build(seed + *seat as u64));
+    }
+    engine.keepHistory(true);
+    let events = Arc::new(Mutex::new(Vec::<GameEvent>::new()));
+    let sink = events.clone();
+    engine.observe(move |event| { sink.lock().unwrap().push(event.clone()) });
+
+    let notation = Notation::new(&data);
+    let mut narrator = Narrator::new(&data);
+    // The seat which took each action that can be undone, so undo can go back to the last human choice
+    let mut movers = Vec::<usize>::new();
+    let mut last_human = None;
+    let ansi = stdout().is_terminal();
+    let mut input = stdin().lock().lines();
+    while !engine.isGameOver() {
+        let seat = engine.currentPlayer();
+        let legal = engine.legalActions();
+        let action = if let Some(agent) = agents[seat].as_mut() {
+            agent.choose(&PlayerView::new(&engine, seat as u32), &legal)
+        } else {
+            // Hot-seat: the hand stays hidden until the next player has the keyboard
+            if last_human.is_some_and(|s| { s != seat }) {
+                print!("Pass to Player {} and press enter ", seat + 1);
+                stdout().flush().ok();
+                if input.next().is_none() {
+                    return;
+                }
+            }
+            last_human = Some(seat);
+            if engine.phase() == Phase::Move {
+                print!("\n{}", Board::new(&engine).withData(&data).withAnsi(ansi));
+            }
+            println!("\n{}", render(&PlayerView::new(&engine, seat as u32), &notation));
+            for (i, action) in legal.iter().enumerate() {
+                println!("{:>3}) {}", i + 1, label(&engine, &notation, action));
+            }
+            print!("> ");
+            stdout().flush().ok();
+            let line = match input.next() {
+                Some(Ok(line)) => line,
+                _ => return,
+            };
+            let line = line.trim();
+            let chosen = match line.split_once(' ').unwrap_or((line, "")) {
+                ("quit", _) => return,
+                ("board", _) => {
+                    print!("{}", Board::new(&engine).withData(&data).withAnsi(ansi));
+                    None
+                }
+                ("undo", _) => {
+                    // Back past any bot moves to the last choice made at the keyboard
+                    while let Some(mover) = movers.pop() {
+                        if engine.undo().is_err() || agents[mover].is_none() {
+                            break;
+                        }
+                    }
+                    None
+                }
+                ("redo", _) => {
+                    while engine.canRedo() {
+                        let mover = engine.currentPlayer();
+                        if engine.redo().is_err() {
+                            break;
+                        }
+                        movers.push(mover);
+                        if agents[engine.currentPlayer()].is_none() {
+                            break;
+                        }
+                    }
+                    None
+                }
+                ("save", path) if !path.is_empty() => {
+                    match engine.saveWith(Path::new(path), &context) {
+                        Ok(()) => println!("Saved to {}", path),
+                        Err(e) => println!("{}", e),
+                    }
+                    None
+                }
+                _ => {
+                    let action = match line.parse::<usize>() {
+                        Ok(n) => legal.get(n.wrapping_sub(1)).copied(),
+                        Err(_) => notation.parse(line, seat as u32).ok().filter(|a| { legal.contains(a) }),
+                    };
+                    if action.is_none() {
+                        println!("{} isn't one of the actions", line);
+                    }
+                    action
+                }
+            };
+            match chosen {
+                Some(action) => action,
+                None => {
+                    for line in events.lock().unwrap().drain(..).flat_map(|e| { narrator.push(&e) }) {
+                        println!("{}", line);
+                    }
+                    continue;
+                }
+            }
+        };
+        if let Err(e) = engine.applyAction(action) {
+            eprintln!("{}", Board::new(&engine).withData(&data));
+            eprintln!("Player {} chose {}: {}", seat + 1, printed(&notation, &action), e);
+            exit(1);
+        }
+        movers.push(seat);
+        let mut lines: Vec<String> = events.lock().unwrap().drain(..).flat_map(|e| { narrator.push(&e) }).collect();
+        lines.extend(narrator.finish());
+        for line in lines {
+            println!("{}", line);
+        }
+    }
+    if let GameState::GameOver { scores, .. } = engine.state() {
+        for sheet in scores.iter() {
+            println!("Player {}: {} points", sheet.player + 1, sheet.total());
+        }
+    }
+}
@@ -0,0 +1,62 @@
+#![allow(non_snake_case)]
+
+use std::env;
+use std::path::Path;
+use std::process::exit;
+use std::sync::Arc;
+
+use engine::cli::Usage;
+use engine::data::GameData;
+use engine::dataset::ExampleWriter;
+use engine::mcts::MctsConfig;
+use engine::network::{Network, selfPlay};
+
+const USAGE: Usage = Usage("Usage: gwt_record [--players N] [--games N] [--iterations N] [--first-seed N] [--weights FILE] --out FILE
+Plays MCTS self-play games, writing every decision's features, legal actions, visit shares and outcome to FILE");
+
+fn main() {
+    let mut players = 2;
+    let mut games = 10;
+    let mut first_seed = 0;
+    let mut search = MctsConfig::new(100);
+    let mut weights = None;
+    let mut out = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--players" => players = USAGE.number(&arg, args.next()),
+            "--games" => games = USAGE.number(&arg, args.next()),
+            "--iterations" => search.iterations = USAGE.number(&arg, args.next()),
+            "--first-seed" => first_seed = USAGE.number(&arg, args.next()),
+            "--weights" => weights = args.next(),
+            "--out" => out = args.next(),
+            "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            other => USAGE.fail(&format!("Unknown argument {}", other)),
+        }
+    }
+    let out = out.unwrap_or_else(|| { USAGE.fail("--out is required") });
+    let data = GameData::embedded().unwrap_or_else(|e| { USAGE.fail(&e.to_string()) });
+    let network = weights.map(|path| { Arc::new(Network::load(Path::new(&path)).unwrap_or_else(|e| { USAGE.fail(&e.to_string()) })) });
+    let result = ExampleWriter::create(Path::new(&out)).and_then(|mut writer| {
+        for seed in first_seed..first_seed + games {
+            // Outcomes are only known once a game ends, so each game is written as it finishes
+            match selfPlay(&data, players, search, network.clone(), seed) {
+                Ok(examples) => {
+                    for example in examples.iter() {
+                        writer.write(example)?;
+                    }
+                }
+                Err(e) => eprintln!("Seed {}: {}", seed, e),
+            }
+        }
+        println!("{} examples written to {}", writer.written(), out);
+        writer.finish().map(drop)
+    });
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        exit(1);
+    }
+}
@@ -0,0 +1,58 @@
+#![allow(non_snake_case)]
+
+use std::env;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::process::exit;
+
+use engine::agent::AgentSpec;
+use engine::batch::{BatchConfig, simulate};
+use engine::cli::Usage;
+use engine::data::GameData;
+
+const USAGE: Usage = Usage("Usage: gwt_simulate [--games N] [--first-seed N] [--threads N] [--out FILE] AGENT AGENT...
+One agent per seat, each is random, mcts:<iterations> or a heuristic strategy name");
+
+fn main() {
+    let mut config = BatchConfig { seats: vec![], games: 1000, first_seed: 0, threads: 0 };
+    let mut out_path = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--games" => config.games = USAGE.number(&arg, args.next()),
+            "--first-seed" => config.first_seed = USAGE.number(&arg, args.next()),
+            "--threads" => config.threads = USAGE.number(&arg, args.next()),
+            "--out" => out_path = Some(args.next().unwrap_or_else(|| { USAGE.fail("--out needs a file") })),
+            "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            name => match AgentSpec::parse(name) {
+                Ok(spec) => config.seats.push(spec),
+                Err(e) => USAGE.fail(&e.to_string()),
+            },
+        }
+    }
+    let data = match GameData::embedded() {
+        Ok(data) => data,
+        Err(e) => USAGE.fail(&e.to_string()),
+    };
+    let mut out = match out_path.as_ref().map(File::create) {
+        Some(Ok(file)) => Some(BufWriter::new(file)),
+        Some(Err(e)) => USAGE.fail(&e.to_string()),
+        None => None,
+    };
+    let report = match simulate(&data, &config, out.as_mut().map(|o| { o as &mut dyn Write })) {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("{}", e);
+            exit(1);
+        }
+    };
+    println!("{} games, {} didn't finish, {:.1} turns on average", report.games, report.failed.len(), report.mean_turns);
+    let finished = report.games - report.failed.len() as u64;
+    for (seat, spec) in config.seats.iter().enumerate() {
+        let rate = if finished == 0 { 0.0 } else { report.seat_wins[seat] / finished as f64 };
+        println!("seat {} {:<16} win % {:>5.1}  mean score {:>6.1}", seat, spec.name(), rate * 100.0, report.mean_scores[seat]);
+    }
+}
@@ -0,0 +1,46 @@
+#![allow(non_snake_case)]
+
+use std::env;
+use std::process::exit;
+
+use engine::agent::AgentSpec;
+use engine::cli::Usage;
+use engine::data::GameData;
+use engine::tournament::{Format, Tournament, TournamentConfig};
+
+const USAGE: Usage = Usage("Usage: gwt_tournament [--players N] [--seeds N] [--first-seed N] [--swiss ROUNDS] AGENT AGENT...
+Agents are random, mcts:<iterations> or a heuristic strategy name");
+
+fn main() {
+    let mut config = TournamentConfig { format: Format::RoundRobin, players: 2, seeds: 10, first_seed: 0 };
+    let mut entrants = Vec::<AgentSpec>::new();
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--players" => config.players = USAGE.number(&arg, args.next()),
+            "--seeds" => config.seeds = USAGE.number(&arg, args.next()),
+            "--first-seed" => config.first_seed = USAGE.number(&arg, args.next()),
+            "--swiss" => config.format = Format::Swiss { rounds: USAGE.number(&arg, args.next()) },
+            "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            name => match AgentSpec::parse(name) {
+                Ok(spec) => entrants.push(spec),
+                Err(e) => USAGE.fail(&e.to_string()),
+            },
+        }
+    }
+    let data = match GameData::embedded() {
+        Ok(data) => data,
+        Err(e) => USAGE.fail(&e.to_string()),
+    };
+    let report = Tournament::new(&data, entrants, config).and_then(|t| { t.run() });
+    match report {
+        Ok(report) => print!("{}", report),
+        Err(e) => {
+            eprintln!("{}", e);
+            exit(1);
+        }
+    }
+}
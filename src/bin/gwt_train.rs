@@ -0,0 +1,97 @@
+#![allow(non_snake_case)]
+
+use std::env;
+use std::path::Path;
+use std::process::exit;
+use std::sync::Arc;
+
+use rand::SeedableRng;
+use rand_pcg::Pcg64;
+
+use engine::cli::Usage;
+use engine::data::GameData;
+use engine::dataset::ExampleReader;
+use engine::mcts::MctsConfig;
+use engine::network::{Example, Network, TrainConfig, selfPlay};
+
+const USAGE: Usage = Usage("Usage: gwt_train [--players N] [--games N] [--generations N] [--iterations N] [--epochs N]
+                 [--learning-rate X] [--hidden N,N...] [--first-seed N] [--weights FILE] [--examples FILE] --out FILE
+Each generation plays games of MCTS self-play guided by the current network, then trains it on them.
+With --examples the network is trained on examples recorded by gwt_record instead");
+
+fn main() {
+    let mut players = 2;
+    let mut games = 10;
+    let mut generations = 1;
+    let mut first_seed = 0;
+    let mut search = MctsConfig::new(100);
+    let mut train = TrainConfig::new();
+    let mut hidden = vec![64];
+    let mut weights = None;
+    let mut recorded = None;
+    let mut out = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--players" => players = USAGE.number(&arg, args.next()),
+            "--games" => games = USAGE.number(&arg, args.next()),
+            "--generations" => generations = USAGE.number(&arg, args.next()),
+            "--iterations" => search.iterations = USAGE.number(&arg, args.next()),
+            "--epochs" => train.epochs = USAGE.number(&arg, args.next()),
+            "--learning-rate" => train.learning_rate = USAGE.number(&arg, args.next()),
+            "--first-seed" => first_seed = USAGE.number(&arg, args.next()),
+            "--hidden" => {
+                let sizes = args.next().unwrap_or_default();
+                hidden = sizes.split(',').map(|s| { USAGE.number(&arg, Some(s.to_string())) }).collect();
+            }
+            "--weights" => weights = args.next(),
+            "--examples" => recorded = args.next(),
+            "--out" => out = args.next(),
+            "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            other => USAGE.fail(&format!("Unknown argument {}", other)),
+        }
+    }
+    let out = out.unwrap_or_else(|| { USAGE.fail("--out is required") });
+    let data = match GameData::embedded() {
+        Ok(data) => data,
+        Err(e) => USAGE.fail(&e.to_string()),
+    };
+    let mut network = match weights {
+        Some(path) => Network::load(Path::new(&path)).unwrap_or_else(|e| { USAGE.fail(&e.to_string()) }),
+        None => Network::new(&hidden, first_seed),
+    };
+    let mut rng = Pcg64::seed_from_u64(first_seed);
+    if let Some(path) = recorded {
+        let examples = ExampleReader::open(Path::new(&path))
+            .and_then(|reader| { reader.readAll() })
+            .unwrap_or_else(|e| { USAGE.fail(&e.to_string()) });
+        let losses = network.train(&examples, &train, &mut rng);
+        println!("{} examples, loss {:.4}", examples.len(), losses.last().unwrap_or(&0.0));
+        if let Err(e) = network.save(Path::new(&out)) {
+            eprintln!("{}", e);
+            exit(1);
+        }
+        return;
+    }
+    let mut seed = first_seed;
+    for generation in 0..generations {
+        let shared = Arc::new(network.clone());
+        let mut examples = Vec::<Example>::new();
+        for _ in 0..games {
+            match selfPlay(&data, players, search, Some(shared.clone()), seed) {
+                Ok(game) => examples.extend(game),
+                Err(e) => eprintln!("Seed {}: {}", seed, e),
+            }
+            seed += 1;
+        }
+        let losses = network.train(&examples, &train, &mut rng);
+        println!("generation {}: {} examples, loss {:.4}", generation, examples.len(), losses.last().unwrap_or(&0.0));
+        if let Err(e) = network.save(Path::new(&out)) {
+            eprintln!("{}", e);
+            exit(1);
+        }
+    }
+}
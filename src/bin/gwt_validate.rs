@@ -0,0 +1,29 @@
+#![allow(non_snake_case)]
+
+use std::env;
+use std::path::Path;
+use std::process::exit;
+
+use engine::data::GameData;
+use engine::validate::{ExpectedCounts, Severity, validate};
+
+// Usage: gwt_validate [data directory]
+fn main() {
+    let dir = env::args().nth(1).unwrap_or_else(|| { "data".to_string() });
+    let data = match GameData::read(Path::new(&dir)) {
+        Ok(data) => data,
+        Err(e) => {
+            eprintln!("{}", e);
+            exit(2);
+        }
+    };
+    let issues = validate(&data, &ExpectedCounts::standard());
+    for issue in issues.iter() {
+        println!("{}", issue);
+    }
+    let errors = issues.iter().filter(|i| { i.severity == Severity::Error }).count();
+    println!("{} errors, {} warnings", errors, issues.len() - errors);
+    if errors > 0 {
+        exit(1);
+    }
+}
@@ -4,7 +4,7 @@ use crate::buildings::{Building, Hazard, Tepee};
 use crate::deck::{Card, Cow, CowColor, Objective};
 use crate::player::Employee;
 
-#[derive(Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize, Debug)]
 pub enum Action {
     PayCoins(i32),
     BuyCow(Cow),
@@ -28,11 +28,21 @@ pub enum Action {
     PlaceDisc(Option<u32>),
     HireEmployee(Option<Employee>),
     PlaceBuilding(Option<u32>, Option<Building>),
-    Auxiliary1,
-    Auxiliary2,
+    // The single or double version of the auxiliary action at an index on the player board
+    Auxiliary1(u32),
+    Auxiliary2(u32),
+    // The Or and XOr indices of a building's option at the cattleman's location
+    UseBuilding(u32, u32),
+    DiscardCard(Card),
+    // A foresight column and row, 0 for the current tile and 1 for the next
+    ChooseTile(u32, u32),
+    UpgradeStation(u32),
+    PlayObjective(Objective),
+    // Skips the rest of an optional action
+    Pass,
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize, Debug)]
 pub enum DiscardCardOpts {
     AnyCow,
     Color(CowColor),
@@ -40,7 +50,7 @@ pub enum DiscardCardOpts {
     Objective,
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize, Debug)]
 pub enum ActionValues {
     Exact(i32),
     // Multiplier based on the number of a type of hired employee
@@ -62,7 +72,7 @@ pub enum ActionValues {
     Max,
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize, Debug)]
 pub enum ActionTag {
     TakeCoins(ActionValues),
     BuyCows,
@@ -0,0 +1,96 @@
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg64;
+
+use serde::{Deserialize, Serialize};
+
+use crate::actions::Action;
+use crate::error::GwtError;
+use crate::heuristic::{HeuristicAgent, Strategy};
+use crate::mcts::{MctsAgent, MctsConfig};
+use crate::view::PlayerView;
+
+// Anything that can take a seat in a game, legal is never empty
+pub trait Agent {
+    fn choose(&mut self, view: &PlayerView, legal: &[Action]) -> Action;
+}
+
+// Picks uniformly from the legal actions
+pub struct RandomAgent {
+    rng: Pcg64,
+}
+
+impl RandomAgent {
+    pub fn new(seed: u64) -> RandomAgent {
+        RandomAgent { rng: Pcg64::seed_from_u64(seed) }
+    }
+}
+
+impl Agent for RandomAgent {
+    fn choose(&mut self, _view: &PlayerView, legal: &[Action]) -> Action {
+        legal[self.rng.gen_range(0..legal.len())]
+    }
+}
+
+// A description of an agent which can build fresh copies of it, written as
+// random, mcts:<iterations> or the name of a heuristic strategy
+#[derive(Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+pub enum AgentSpec {
+    Random,
+    Heuristic(Strategy),
+    Mcts(u32),
+}
+
+impl AgentSpec {
+    pub fn parse(name: &str) -> Result<AgentSpec, GwtError> {
+        if name == "random" {
+            return Ok(AgentSpec::Random);
+        }
+        if let Some(strategy) = Strategy::fromName(name) {
+            return Ok(AgentSpec::Heuristic(strategy));
+        }
+        match name.strip_prefix("mcts:").map(|n| { n.parse::<u32>() }) {
+            Some(Ok(iterations)) if iterations > 0 => Ok(AgentSpec::Mcts(iterations)),
+            _ => Err(GwtError::UnknownAgent(name.to_string())),
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            AgentSpec::Random => "random".to_string(),
+            AgentSpec::Heuristic(strategy) => strategy.name().to_string(),
+            AgentSpec::Mcts(iterations) => format!("mcts:{}", iterations),
+        }
+    }
+
+    pub fn build(&self, seed: u64) -> Box<dyn Agent> {
+        match self {
+            AgentSpec::Random => Box::new(RandomAgent::new(seed)),
+            AgentSpec::Heuristic(strategy) => Box::new(HeuristicAgent::new(*strategy, seed)),
+            AgentSpec::Mcts(iterations) => Box::new(MctsAgent::new(MctsConfig::new(*iterations), seed)),
+        }
+    }
+}
+
+// Games played by random agents, for the tests of every module
+#[cfg(test)]
+pub(crate) mod testing {
+    use crate::Engine;
+    use crate::view::PlayerView;
+
+    use super::*;
+
+    // Applies random actions until the game is over or limit were applied, calling after with the engine after each
+    // one. Returns how many were applied
+    pub(crate) fn playRandom(engine: &mut Engine, seed: u64, limit: usize, mut after: impl FnMut(&Engine)) -> usize {
+        let mut agent = RandomAgent::new(seed);
+        let mut applied = 0;
+        while applied < limit && !engine.isGameOver() {
+            let legal = engine.legalActions();
+            let action = agent.choose(&PlayerView::new(engine, engine.currentPlayer() as u32), &legal);
+            engine.applyAction(action).unwrap();
+            applied += 1;
+            after(engine);
+        }
+        applied
+    }
+}
@@ -0,0 +1,251 @@
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::channel;
+use std::thread;
+
+use serde::{Deserialize, Serialize};
+
+use crate::agent::{Agent, AgentSpec};
+use crate::data::GameData;
+use crate::error::GwtError;
+use crate::runner::playGame;
+
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+pub struct BatchConfig {
+    // One agent per seat, seated the same way in every game
+    pub seats: Vec<AgentSpec>,
+    pub games: u64,
+    pub first_seed: u64,
+    // 0 uses every available core
+    pub threads: usize,
+}
+
+// One line of the streamed output, error is set for games which didn't finish
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+pub struct GameSummary {
+    pub seed: u64,
+    pub scores: Vec<i32>,
+    pub winners: Vec<u32>,
+    pub turns: u32,
+    pub actions: u32,
+    pub error: Option<GwtError>,
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct BatchReport {
+    pub games: u64,
+    pub failed: Vec<u64>,
+    // Shared wins count as a fraction
+    pub seat_wins: Vec<f64>,
+    pub mean_scores: Vec<f64>,
+    pub mean_turns: f64,
+}
+
+impl BatchConfig {
+    pub fn threadCount(&self) -> usize {
+        if self.threads > 0 {
+            self.threads
+        } else {
+            thread::available_parallelism().map(|n| { n.get() }).unwrap_or(1)
+        }
+    }
+}
+
+pub fn playSummary(data: &GameData, seats: &[AgentSpec], seed: u64) -> GameSummary {
+    let mut agents: Vec<Box<dyn Agent>> = seats.iter()
+        .enumerate()
+        .map(|(seat, spec)| { spec.build(seed.wrapping_mul(31).wrapping_add(seat as u64)) })
+        .collect();
+    match playGame(data, &mut agents, seed) {
+        Ok(result) => GameSummary {
+            seed,
+            scores: result.scores.iter().map(|s| { s.total() }).collect(),
+            winners: result.winners,
+            turns: result.turns,
+            actions: result.actions.len() as u32,
+            error: None,
+        },
+        Err(error) => GameSummary { seed, scores: vec![], winners: vec![], turns: 0, actions: 0, error: Some(error) },
+    }
+}
+
+// Plays every seed across the worker threads, writing and flushing each summary to out as a JSON line as soon as it
+// finishes. Lines arrive in whatever order the games finish, and the report is tallied as they do, in whole numbers
+// so it doesn't depend on the number of threads. When writing fails the workers stop after the game they're playing
+pub fn simulate(data: &GameData, config: &BatchConfig, mut out: Option<&mut dyn Write>) -> Result<BatchReport, GwtError> {
+    if !(2..=4).contains(&config.seats.len()) {
+        return Err(GwtError::InvalidPlayerCount(config.seats.len() as u32));
+    }
+    let next = AtomicU64::new(0);
+    let stop = AtomicBool::new(false);
+    let (sender, receiver) = channel::<GameSummary>();
+    let mut tally = Tally::new(config.seats.len());
+    thread::scope(|scope| -> Result<(), GwtError> {
+        for _ in 0..config.threadCount() {
+            let sender = sender.clone();
+            let next = &next;
+            let stop = &stop;
+            scope.spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    let game = next.fetch_add(1, Ordering::Relaxed);
+                    if game >= config.games {
+                        break;
+                    }
+                    let summary = playSummary(data, &config.seats, config.first_seed + game);
+                    if sender.send(summary).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(sender);
+        for summary in receiver.iter() {
+            if let Some(out) = out.as_mut() {
+                let written = serde_json::to_string(&summary)
+                    .map_err(|e| { GwtError::Output(e.to_string()) })
+                    .and_then(|line| {
+                        writeln!(out, "{}", line).and_then(|_| { out.flush() }).map_err(|e| { GwtError::Output(e.to_string()) })
+                    });
+                if let Err(e) = written {
+                    stop.store(true, Ordering::Relaxed);
+                    return Err(e);
+                }
+            }
+            tally.add(&summary);
+        }
+        Ok(())
+    })?;
+    Ok(tally.report())
+}
+
+// Running totals of the summaries, which come out the same in any order
+struct Tally {
+    games: u64,
+    failed: Vec<u64>,
+    // In twelfths of a win, so that wins shared by up to four players add up exactly
+    seat_wins: Vec<u64>,
+    score_totals: Vec<i64>,
+    turn_total: u64,
+    finished: u64,
+}
+
+impl Tally {
+    const SHARES: u64 = 12;
+
+    fn new(seats: usize) -> Tally {
+        Tally {
+            games: 0,
+            failed: vec![],
+            seat_wins: vec![0; seats],
+            score_totals: vec![0; seats],
+            turn_total: 0,
+            finished: 0,
+        }
+    }
+
+    fn add(&mut self, summary: &GameSummary) {
+        self.games += 1;
+        if summary.error.is_some() {
+            self.failed.push(summary.seed);
+            return;
+        }
+        self.finished += 1;
+        let share = Tally::SHARES / summary.winners.len() as u64;
+        for winner in summary.winners.iter() {
+            self.seat_wins[*winner as usize] += share;
+        }
+        for (seat, score) in summary.scores.iter().enumerate() {
+            self.score_totals[seat] += *score as i64;
+        }
+        self.turn_total += summary.turns as u64;
+    }
+
+    fn report(mut self) -> BatchReport {
+        self.failed.sort_unstable();
+        let finished = self.finished.max(1) as f64;
+        BatchReport {
+            games: self.games,
+            failed: self.failed,
+            seat_wins: self.seat_wins.iter().map(|w| { *w as f64 / Tally::SHARES as f64 }).collect(),
+            mean_scores: self.score_totals.iter().map(|s| { *s as f64 / finished }).collect(),
+            mean_turns: self.turn_total as f64 / finished,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::heuristic::Strategy;
+
+    use super::*;
+
+    #[test]
+    fn testSimulateIsDeterministic() {
+        let data = GameData::embedded().unwrap();
+        let config = BatchConfig {
+            seats: vec![AgentSpec::Random, AgentSpec::Heuristic(Strategy::CowBuyer)],
+            games: 8,
+            first_seed: 3,
+            threads: 1,
+        };
+        let mut serial = Vec::<u8>::new();
+        let report = simulate(&data, &config, Some(&mut serial)).unwrap();
+        assert_eq!(report.games, 8);
+        assert_eq!(report.seat_wins.iter().sum::<f64>() as u64 + report.failed.len() as u64, 8);
+        assert_eq!(String::from_utf8(serial).unwrap().lines().count(), 8);
+
+        let parallel = BatchConfig { threads: 4, ..config.clone() };
+        assert_eq!(report, simulate(&data, &parallel, None).unwrap());
+    }
+
+    // Counts the lines which had been flushed by the time the next one was written
+    #[derive(Default)]
+    struct Flushes {
+        buffered: usize,
+        flushed: usize,
+    }
+
+    impl Write for Flushes {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            assert_eq!(self.buffered, 0, "a line was written before the last was flushed");
+            self.buffered += buf.iter().filter(|b| { **b == b'\n' }).count();
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            self.flushed += self.buffered;
+            self.buffered = 0;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn testLinesFlushed() {
+        let data = GameData::embedded().unwrap();
+        let config = BatchConfig { seats: vec![AgentSpec::Random, AgentSpec::Random], games: 5, first_seed: 0, threads: 2 };
+        let mut out = Flushes::default();
+        simulate(&data, &config, Some(&mut out)).unwrap();
+        assert_eq!(out.flushed, 5);
+    }
+
+    struct Broken;
+
+    impl Write for Broken {
+        fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::other("disk full"))
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn testWriteErrorStopsWorkers() {
+        let data = GameData::embedded().unwrap();
+        // Far more games than could be played in the test's time if the workers carried on
+        let config = BatchConfig { seats: vec![AgentSpec::Random, AgentSpec::Random], games: 100_000, first_seed: 0, threads: 2 };
+        let result = simulate(&data, &config, Some(&mut Broken));
+        assert_eq!(result, Err(GwtError::Output("disk full".to_string())));
+    }
+}
@@ -0,0 +1,337 @@
+// The whole board as text: the cattle track with its occupants and cattlemen, the station track with its engines,
+// stations and discs, the cities, foresight, the job market and the cow market. Players are coloured when ANSI
+// escapes are on, and buildings are only named (A for the first neutral building, B5a for player buildings) when
+// the game data is given, so that an engine can be dumped on its own
+//
+//   Station track  0         1
+//                  0123456789012345
+//                  CC..CSC.B.CSC.B.
+//                      +++ + +++ +
+//   P1 engine      1
+//   P2 engine        2
+
+use std::fmt;
+use std::fmt::Write;
+
+use crate::Engine;
+use crate::buildings::{Building, Tepee};
+use crate::data::GameData;
+use crate::describe::{Describe, plural};
+use crate::notation::Notation;
+use crate::tiles::Tile;
+use crate::tracks::{EngineSpace, Space, SpaceOccupant};
+
+// Red, blue, green and yellow
+const PLAYER_COLORS: [u32; 4] = [31, 34, 32, 33];
+const TRACK_INDENT: usize = 15;
+const OCCUPANT_WIDTH: usize = 48;
+
+pub struct Board<'a> {
+    engine: &'a Engine,
+    data: Option<&'a GameData>,
+    ansi: bool,
+}
+
+impl<'a> Board<'a> {
+    pub fn new(engine: &'a Engine) -> Board<'a> {
+        Board { engine, data: None, ansi: false }
+    }
+
+    pub fn withData(self, data: &'a GameData) -> Board<'a> {
+        Board { data: Some(data), ..self }
+    }
+
+    pub fn withAnsi(self, ansi: bool) -> Board<'a> {
+        Board { ansi, ..self }
+    }
+
+    fn styled(&self, code: u32, text: &str) -> String {
+        if self.ansi { format!("\x1b[{}m{}\x1b[0m", code, text) } else { text.to_string() }
+    }
+
+    fn heading(&self, text: &str) -> String {
+        self.styled(1, text)
+    }
+
+    // Padding has to happen before colouring, escapes have no width on screen
+    fn player(&self, seat: u32, text: &str) -> String {
+        self.styled(PLAYER_COLORS[seat as usize % PLAYER_COLORS.len()], text)
+    }
+
+    fn players(&self, seats: impl Iterator<Item=u32>) -> String {
+        seats.map(|p| { self.player(p, &format!("P{}", p + 1)) }).collect::<Vec<String>>().join(" ")
+    }
+
+    fn building(&self, building: &Building, neutral: bool) -> String {
+        let data = match self.data {
+            Some(data) => data,
+            None if neutral => return "neutral building".to_string(),
+            None => return format!("building of P{}", building.owner() + 1),
+        };
+        if neutral {
+            match data.neutral_buildings.iter().position(|b| { b == building }) {
+                Some(i) => format!("neutral building {}", (b'A' + i as u8) as char),
+                None => "neutral building".to_string(),
+            }
+        } else {
+            match Notation::new(data).building(building) {
+                Ok(name) => format!("{} of P{}", name, building.owner() + 1),
+                Err(_) => format!("building of P{}", building.owner() + 1),
+            }
+        }
+    }
+
+    fn occupant(&self, space: &Space) -> String {
+        match space.occupant() {
+            SpaceOccupant::Start => "Start".to_string(),
+            SpaceOccupant::KansasCity => "Kansas City".to_string(),
+            SpaceOccupant::Building { building: Some(b), neutral, .. } => self.building(b, *neutral),
+            SpaceOccupant::Building { building: None, risk_action, forest, .. } => {
+                let mut text = if *forest { "empty forest lot".to_string() } else { "empty lot".to_string() };
+                if risk_action.items.iter().any(|t| { t.is_some() }) {
+                    text += &format!(", risk: {}", risk_action.describe());
+                }
+                text
+            }
+            SpaceOccupant::Hazard(_, Some(hazard)) => format!("hazard {}", Notation::hazard(hazard)),
+            SpaceOccupant::Hazard(area, None) => format!("empty {:?} space", area).to_lowercase(),
+            SpaceOccupant::Tepee(value, Some(tepee)) => format!("{} tepee, {}", tepeeName(*tepee), dollars(*value)),
+            SpaceOccupant::Tepee(value, None) => format!("empty tepee space, {}", dollars(*value)),
+        }
+    }
+
+    fn cattleTrack(&self, out: &mut String) -> fmt::Result {
+        writeln!(out, "{}", self.heading("Cattle track"))?;
+        let players = self.engine.players();
+        for (i, space) in self.engine.track().iter().enumerate() {
+            let next: Vec<String> = space.nextSpaces().iter().flatten().map(|n| { n.to_string() }).collect();
+            let next = if next.is_empty() { String::new() } else { format!("-> {}", next.join(" ")) };
+            let here = self.players(players.iter().filter(|p| { p.cattleManPos() == i }).map(|p| { p.id() }));
+            // Occupants too long for their column, like long risk actions, go on under it
+            let occupant = wrap(&self.occupant(space), OCCUPANT_WIDTH);
+            let line = format!("{:>4}  {:<width$} {:<10} {}", i, occupant[0], next, here, width = OCCUPANT_WIDTH);
+            writeln!(out, "{}", line.trim_end())?;
+            for more in occupant[1..].iter() {
+                writeln!(out, "      {}", more)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn stationTrack(&self, out: &mut String) -> fmt::Result {
+        let track = self.engine.stationTrack();
+        let end = track.end() as usize;
+        // A ruler, then C for cities, S for stations and B where there's both, + for the crossings transport is paid
+        // for and a row for each engine
+        let mut stops = vec!['.'; end + 1];
+        for city in track.cities().cities().iter().filter(|c| { c.location() as usize <= end }) {
+            stops[city.location() as usize] = 'C';
+        }
+        for station in (0..track.numStations()).map(|s| { track.station(s) }).filter(|s| { s.location() as usize <= end }) {
+            let stop = &mut stops[station.location() as usize];
+            *stop = if *stop == 'C' { 'B' } else { 'S' };
+        }
+        let mut crossings = vec![' '; end + 1];
+        for crossing in track.crossings().iter().filter(|c| { **c as usize <= end }) {
+            crossings[*crossing as usize] = '+';
+        }
+        let tens: String = (0..=end).map(|i| { if i % 10 == 0 { char::from_digit((i / 10 % 10) as u32, 10).unwrap() } else { ' ' } }).collect();
+        let units: String = (0..=end).map(|i| { char::from_digit((i % 10) as u32, 10).unwrap() }).collect();
+        let title = "Station track";
+        writeln!(out, "{}{}{}", self.heading(title), " ".repeat(TRACK_INDENT - title.len()), tens.trim_end())?;
+        for row in [units, stops.iter().collect(), crossings.iter().collect::<String>().trim_end().to_string()] {
+            writeln!(out, "{:<indent$}{}", "", row, indent = TRACK_INDENT)?;
+        }
+        for seat in 0..self.engine.players().len() as u32 {
+            let (location, turnout) = match track.engineSpace(seat) {
+                EngineSpace::MainTrack(location) => (location, ""),
+                EngineSpace::TurnoutTrack(location) => (location, " (turnout)"),
+            };
+            let label = format!("P{} engine", seat + 1);
+            let marker = format!("{}{}", " ".repeat(location), seat + 1);
+            writeln!(out, "{}{}", self.player(seat, &format!("{:<indent$}{}", label, marker, indent = TRACK_INDENT)), turnout)?;
+        }
+        for s in 0..track.numStations() {
+            let station = track.station(s);
+            let name = if s + 1 == track.numStations() { "Final station".to_string() } else { format!("Station {}", s + 1) };
+            let discs = (0..self.engine.players().len() as u32).filter(|p| { station.hasDisc(*p) });
+            let mut line = format!("  {} at {}: ${} to upgrade, {}", name, station.location(), station.price(),
+                                   plural(station.points(), "point"));
+            if station.advancedDisc() {
+                line += ", advanced disc";
+            }
+            let discs = self.players(discs);
+            if !discs.is_empty() {
+                line += &format!(", discs {}", discs);
+            }
+            if let Some(master) = station.stationMaster() {
+                line += &format!(", station master: {}", master.describe());
+            }
+            writeln!(out, "{}", line)?;
+        }
+        Ok(())
+    }
+
+    fn cities(&self, out: &mut String) -> fmt::Result {
+        writeln!(out, "{}", self.heading("Cities"))?;
+        for city in self.engine.stationTrack().cities().cities().iter() {
+            let discs: Vec<String> = (0..self.engine.players().len() as u32)
+                .filter(|p| { city.discs(*p) > 0 })
+                .map(|p| {
+                    let n = city.discs(p);
+                    self.player(p, &if n == 1 { format!("P{}", p + 1) } else { format!("P{}x{}", p + 1, n) })
+                })
+                .collect();
+            let mut line = format!("  {:<16} at {:>2}", city.name(), city.location());
+            if city.limited() {
+                line += ", one disc each";
+            }
+            if !discs.is_empty() {
+                line += &format!(", discs {}", discs.join(" "));
+            }
+            writeln!(out, "{}", line)?;
+        }
+        Ok(())
+    }
+
+    fn foresight(&self, out: &mut String) -> fmt::Result {
+        let foresight = self.engine.foresight();
+        writeln!(out, "{}", self.heading("Foresight"))?;
+        let row = |label: &str, cells: Vec<String>| {
+            let cells: Vec<String> = cells.iter().map(|c| { format!("{:<18}", c) }).collect();
+            format!("  {:<9}{}", label, cells.concat()).trim_end().to_string()
+        };
+        writeln!(out, "{}", row("", (1..=foresight.current().len()).map(|c| { format!("column {}", c) }).collect()))?;
+        writeln!(out, "{}", row("current", foresight.current().iter().map(tileName).collect()))?;
+        writeln!(out, "{}", row("next", foresight.next().iter().map(tileName).collect()))?;
+        writeln!(out, "{}", row("left", foresight.pileSizes().iter().map(|n| { n.to_string() }).collect()))?;
+        Ok(())
+    }
+
+    fn jobMarket(&self, out: &mut String) -> fmt::Result {
+        let market = self.engine.jobMarket();
+        writeln!(out, "{}", self.heading("Job market"))?;
+        let columns = market.columns().max(1);
+        let employees = market.employees();
+        for (row, salary) in market.rowSalaries().iter().enumerate().take(market.lastRow()) {
+            // Slots which were filled and then hired from are -, the ones still to fill are .
+            let cells: Vec<String> = (0..columns)
+                .map(|c| {
+                    let cell = match employees.get(row * columns + c) {
+                        Some(Some(emp)) => format!("{:?}", emp),
+                        Some(None) => "-".to_string(),
+                        None => ".".to_string(),
+                    };
+                    format!("{:<11}", cell)
+                })
+                .collect();
+            let mut line = format!("  {:>2}  ${:<3}{}", row + 1, salary, cells.concat());
+            if market.cowMarketRows().contains(&(row + 1)) {
+                line += "refills the cow market";
+            } else if row + 1 == market.lastRow() {
+                line += "starts the final round";
+            }
+            writeln!(out, "{}", line.trim_end())?;
+        }
+        Ok(())
+    }
+
+    fn cowMarket(&self, out: &mut String) -> fmt::Result {
+        let market = self.engine.cowMarket();
+        let cows: Vec<String> = market.cows().iter().map(Notation::cow).collect();
+        writeln!(out, "{} {} ({} left in the deck)", self.heading("Cow market"), cows.join(", "), market.deckSize())
+    }
+
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        // Writing to a String can't fail
+        let _ = self.cattleTrack(&mut out)
+            .and_then(|_| { self.stationTrack(&mut out) })
+            .and_then(|_| { self.cities(&mut out) })
+            .and_then(|_| { self.foresight(&mut out) })
+            .and_then(|_| { self.jobMarket(&mut out) })
+            .and_then(|_| { self.cowMarket(&mut out) });
+        out
+    }
+}
+
+// Breaks the text between words into lines of at most width, or longer for words which are
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = vec![String::new()];
+    for word in text.split(' ') {
+        let line = lines.last_mut().unwrap();
+        if line.is_empty() {
+            *line = word.to_string();
+        } else if line.len() + 1 + word.len() <= width {
+            *line += " ";
+            *line += word;
+        } else {
+            lines.push(word.to_string());
+        }
+    }
+    lines
+}
+
+fn tepeeName(tepee: Tepee) -> &'static str {
+    match tepee {
+        Tepee::Green => "green",
+        Tepee::Blue => "blue",
+    }
+}
+
+fn dollars(amount: i32) -> String {
+    if amount < 0 { format!("-${}", -amount) } else { format!("${}", amount) }
+}
+
+fn tileName(tile: &Tile) -> String {
+    match tile {
+        Tile::EmployeeTile(emp) => format!("{:?}", emp),
+        Tile::HazardTile(hazard) => Notation::hazard(hazard),
+        Tile::TepeeTile(tepee) => format!("{} tepee", tepeeName(*tepee)),
+        Tile::BlankTile => "-".to_string(),
+    }
+}
+
+impl fmt::Display for Board<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.render())
+    }
+}
+
+// Plain text without building names, for panics and debug dumps
+impl fmt::Display for Engine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Board::new(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{read_to_string, write};
+    use std::path::Path;
+
+    use super::*;
+
+    // Set GWT_UPDATE_SNAPSHOTS to write the snapshots again after changing the rendering
+    fn snapshot(name: &str, rendered: &str) {
+        let path = format!("./tests/fixtures/{}", name);
+        if std::env::var_os("GWT_UPDATE_SNAPSHOTS").is_some() {
+            write(&path, rendered).unwrap();
+        }
+        assert_eq!(rendered, read_to_string(&path).unwrap(), "{} differs, the board is now:\n{}", path, rendered);
+    }
+
+    #[test]
+    fn testBoard() {
+        // The game the save fixtures hold, two players on seed 11 after 40 random actions
+        let data = GameData::embedded().unwrap();
+        let engine = Engine::load(Path::new("./tests/fixtures/save_v1.json")).unwrap();
+        snapshot("board_2p_seed11.txt", &Board::new(&engine).withData(&data).render());
+
+        let plain = engine.to_string();
+        assert!(plain.contains("building of P"));
+        let ansi = Board::new(&engine).withData(&data).withAnsi(true).render();
+        assert!(ansi.contains("\x1b[31mP1\x1b[0m"));
+        assert!(!plain.contains('\x1b'));
+    }
+}
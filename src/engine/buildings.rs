@@ -2,10 +2,11 @@ use serde::{Deserialize, Serialize};
 
 use crate::actions::{ActionTag, ActionValues, DiscardCardOpts};
 use crate::deck::CowColor;
+use crate::error::GwtError;
 use crate::logical::{And, Or, XOr};
 use crate::player::Employee;
 
-#[derive(Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize, Debug)]
 pub enum Toll {
     NoToll,
     Green,
@@ -13,20 +14,37 @@ pub enum Toll {
     GreenBlack,
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+impl Toll {
+    // Dollars paid to pass, 2 player games only count black hands and 3 player games only green
+    pub fn cost(&self, num_players: usize) -> u32 {
+        let (green, black) = match self {
+            Toll::NoToll => (0, 0),
+            Toll::Green => (1, 0),
+            Toll::Black => (0, 1),
+            Toll::GreenBlack => (1, 1),
+        };
+        match num_players {
+            2 => black,
+            3 => green,
+            _ => green + black,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize, Debug)]
 pub enum Tepee {
     Green,
     Blue,
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize, Debug)]
 pub enum HazardType {
     Flood,
     Drought,
     Rockfall,
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize, Debug)]
 pub struct Hazard {
     area: HazardType,
     toll: Toll,
@@ -37,9 +55,15 @@ impl Hazard {
     pub fn new(area: HazardType, toll: Toll, points: u32) -> Hazard {
         Hazard { area, toll, points }
     }
+
+    pub fn area(&self) -> HazardType { self.area }
+
+    pub fn toll(&self) -> Toll { self.toll }
+
+    pub fn points(&self) -> u32 { self.points }
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize, Debug)]
 pub struct Building {
     owner: u32,
     laborers: u32,
@@ -49,83 +73,97 @@ pub struct Building {
 }
 
 impl Building {
-    pub fn basicBuilding(num: u32) -> Building {
+    pub fn owner(&self) -> u32 { self.owner }
+
+    pub fn points(&self) -> u32 { self.points }
+
+    pub fn laborers(&self) -> u32 { self.laborers }
+
+    pub fn toll(&self) -> Toll { self.toll }
+
+    pub fn actions(&self) -> &Or<XOr<And<ActionTag, 3>, 2>, 3> { &self.actions }
+
+    pub fn withOwner(&self, owner: u32) -> Building {
+        Building { owner, ..*self }
+    }
+
+    pub fn basicBuilding(num: u32) -> Result<Building, GwtError> {
         let actions = {
             match num {
                 0 => Or::new(&[
                     XOr::new(&[And::new(&[
                         ActionTag::DiscardCard(DiscardCardOpts::Color(CowColor::Guernsey)),
                         ActionTag::TakeCoins(ActionValues::Exact(2)),
-                    ]), ]),
+                    ])?, ])?,
                     XOr::new(&[And::new(&[
                         ActionTag::HireEmployee(ActionValues::Exact(0)),
-                    ]), ]),
+                    ])?, ])?,
                     XOr::new(&[And::new(&[
                         ActionTag::HireEmployee(ActionValues::Exact(-2)),
-                    ]), ]),
-                ]),
+                    ])?, ])?,
+                ])?,
                 1 => Or::new(&[
                     XOr::new(&[And::new(&[
                         ActionTag::DiscardCard(DiscardCardOpts::Color(CowColor::Dutch)),
                         ActionTag::TakeCoins(ActionValues::Exact(2)),
-                    ]), ]),
+                    ])?, ])?,
                     XOr::new(&[And::new(&[
                         ActionTag::PlaceBuilding(ActionValues::EmployeeMult(Employee::Craftsman, -2)),
-                    ]), ]),
-                ]),
+                    ])?, ])?,
+                ])?,
                 2 => Or::new(&[
                     XOr::new(&[
-                        And::new(&[ActionTag::MoveCertificate(ActionValues::Exact(1)), ]),
-                        And::new(&[ActionTag::TakeObjective, ]),
-                    ]),
+                        And::new(&[ActionTag::MoveCertificate(ActionValues::Exact(1)), ])?,
+                        And::new(&[ActionTag::TakeObjective, ])?,
+                    ])?,
                     XOr::new(&[And::new(&[
                         ActionTag::MoveEngine(ActionValues::EmployeeMult(Employee::Engineer, 1)),
-                    ]), ]),
-                ]),
+                    ])?, ])?,
+                ])?,
                 3 => Or::new(&[
                     XOr::new(&[
-                        And::new(&[ActionTag::TakeTepee, ]),
+                        And::new(&[ActionTag::TakeTepee, ])?,
                         And::new(&[
                             ActionTag::TakeCoins(ActionValues::Exact(-2)),
                             ActionTag::MoveEngine(ActionValues::Exact(2))
-                        ]),
-                    ]),
+                        ])?,
+                    ])?,
                     XOr::new(&[And::new(&[ActionTag::DoubleAuxiliary,
-                    ]), ]),
-                ]),
+                    ])?, ])?,
+                ])?,
                 4 => Or::new(&[
                     XOr::new(&[And::new(&[
                         ActionTag::DiscardCard(DiscardCardOpts::Color(CowColor::Angus)),
                         ActionTag::TakeCoins(ActionValues::Exact(2)),
-                    ]), ]),
+                    ])?, ])?,
                     XOr::new(&[And::new(&[
                         ActionTag::BuyCows,
-                    ]), ]),
-                ]),
+                    ])?, ])?,
+                ])?,
                 5 => Or::new(&[
                     XOr::new(&[And::new(&[
                         ActionTag::DiscardCard(DiscardCardOpts::PairCow),
                         ActionTag::TakeCoins(ActionValues::Exact(4)),
-                    ]), ]),
+                    ])?, ])?,
                     XOr::new(&[And::new(&[
                         ActionTag::TakeCoins(ActionValues::Exact(-7)),
                         ActionTag::TakeHazard,
-                    ]), ]),
-                ]),
+                    ])?, ])?,
+                ])?,
                 6 => Or::new(&[
                     XOr::new(&[And::new(&[
                         ActionTag::MoveEngine(ActionValues::EmployeeMult(Employee::Engineer, 1)),
-                    ]), ]),
+                    ])?, ])?,
                     XOr::new(&[And::new(&[ActionTag::DoubleAuxiliary,
-                    ]), ]),
-                ]),
-                _ => panic!("Invalid building specified"),
+                    ])?, ])?,
+                ])?,
+                _ => return Err(GwtError::InvalidBuilding { num, side_b: None }),
             }
         };
-        Building { owner: 0, laborers: 0, points: 0, toll: Toll::NoToll, actions }
+        Ok(Building { owner: 0, laborers: 0, points: 0, toll: Toll::NoToll, actions })
     }
 
-    pub fn playerBuilding(num: u32, side_b: bool) -> Building {
+    pub fn playerBuilding(num: u32, side_b: bool) -> Result<Building, GwtError> {
         let actions =
             {
                 if !side_b {
@@ -133,85 +171,85 @@ impl Building {
                         0 => Or::new(&[
                             XOr::new(&[And::new(&[
                                 ActionTag::TakeCoins(ActionValues::ForestMult(2)),
-                            ]), ]),
-                        ]),
+                            ])?, ])?,
+                        ])?,
                         1 => Or::new(&[
                             XOr::new(&[And::new(&[
                                 ActionTag::DiscardCard(DiscardCardOpts::Color(CowColor::Guernsey)),
                                 ActionTag::TakeCoins(ActionValues::Exact(4)),
-                            ]), ]),
+                            ])?, ])?,
                             XOr::new(&[And::new(&[
                                 ActionTag::BuyCows,
-                            ]), ]),
-                        ]),
+                            ])?, ])?,
+                        ])?,
                         2 => Or::new(&[
                             XOr::new(&[And::new(&[
                                 ActionTag::DiscardCard(DiscardCardOpts::PairCow),
                                 ActionTag::TakeCoins(ActionValues::Exact(3)),
-                            ]), ]),
+                            ])?, ])?,
                             XOr::new(&[And::new(&[
                                 ActionTag::MoveCattleman(ActionValues::Exact(1)),
-                            ]), ]),
-                        ]),
+                            ])?, ])?,
+                        ])?,
                         3 => Or::new(&[
                             XOr::new(&[And::new(&[
                                 ActionTag::TakeCoins(ActionValues::Exact(-5)),
                                 ActionTag::TakeHazard,
-                            ]), ]),
+                            ])?, ])?,
                             XOr::new(&[And::new(&[
                                 ActionTag::MoveCattleman(ActionValues::Exact(2)),
-                            ]), ]),
-                        ]),
+                            ])?, ])?,
+                        ])?,
                         4 => Or::new(&[
                             XOr::new(&[And::new(&[
                                 ActionTag::HireEmployee(ActionValues::Exact(1)),
-                            ]), ]),
+                            ])?, ])?,
                             XOr::new(&[And::new(&[
                                 ActionTag::MoveEngine(ActionValues::EmployeeMult(Employee::Engineer, 1)),
-                            ]), ]),
-                        ]),
+                            ])?, ])?,
+                        ])?,
                         5 => Or::new(&[
                             XOr::new(&[And::new(&[
                                 ActionTag::DiscardCard(DiscardCardOpts::Color(CowColor::Holstein)),
                                 ActionTag::TakeCoins(ActionValues::Exact(10)),
-                            ]), ]),
+                            ])?, ])?,
                             XOr::new(&[And::new(&[
                                 ActionTag::DoubleAuxiliary,
-                            ]), ]),
-                        ]),
+                            ])?, ])?,
+                        ])?,
                         6 => Or::new(&[
                             XOr::new(&[And::new(&[
                                 ActionTag::MoveCertificate(ActionValues::TepeePairMult(2)),
                                 ActionTag::TakeCoins(ActionValues::TepeePairMult(2)),
-                            ]), ]),
-                        ]),
+                            ])?, ])?,
+                        ])?,
                         7 => Or::new(&[
                             XOr::new(&[And::new(&[
                                 ActionTag::TakeTepee,
-                            ]), And::new(&[
+                            ])?, And::new(&[
                                 ActionTag::DoubleAuxiliary,
-                            ]), ]),
+                            ])?, ])?,
                             XOr::new(&[And::new(&[
                                 ActionTag::MoveEngine(ActionValues::Exact(2)),
-                            ]), ]),
-                        ]),
+                            ])?, ])?,
+                        ])?,
                         8 => Or::new(&[
                             XOr::new(&[And::new(&[
                                 ActionTag::MoveEngine(ActionValues::Exact(3)),
-                            ]), ]),
+                            ])?, ])?,
                             XOr::new(&[And::new(&[
                                 ActionTag::CityDiscMoveTrain,
-                            ]), ]),
-                        ]),
+                            ])?, ])?,
+                        ])?,
                         9 => Or::new(&[
                             XOr::new(&[And::new(&[
                                 ActionTag::MoveCertificate(ActionValues::Max),
-                            ]), ]),
+                            ])?, ])?,
                             XOr::new(&[And::new(&[
                                 ActionTag::MoveCattleman(ActionValues::Exact(5)),
-                            ]), ]),
-                        ]),
-                        _ => panic!("Invalid building specified")
+                            ])?, ])?,
+                        ])?,
+                        _ => return Err(GwtError::InvalidBuilding { num, side_b: Some(side_b) })
                     }
                 } else {
                     match num {
@@ -219,81 +257,81 @@ impl Building {
                             XOr::new(&[And::new(&[
                                 ActionTag::DiscardCard(DiscardCardOpts::Objective),
                                 ActionTag::MoveCertificate(ActionValues::Exact(2)),
-                            ]), ]),
+                            ])?, ])?,
                             XOr::new(&[And::new(&[
                                 ActionTag::MoveEngine(ActionValues::Exact(-1)),
                                 ActionTag::TakeCoins(ActionValues::Exact(3)),
-                            ]), ]),
-                        ]),
+                            ])?, ])?,
+                        ])?,
                         1 => Or::new(&[
                             XOr::new(&[And::new(&[
                                 ActionTag::DiscardCard(DiscardCardOpts::Color(CowColor::Jersey)),
                                 ActionTag::MoveEngine(ActionValues::Exact(1)),
-                            ]), ]),
+                            ])?, ])?,
                             XOr::new(&[And::new(&[
                                 ActionTag::DiscardCard(DiscardCardOpts::Color(CowColor::Dutch)),
                                 ActionTag::TakeCoins(ActionValues::Exact(3)),
-                            ]), ]),
-                        ]),
+                            ])?, ])?,
+                        ])?,
                         2 => Or::new(&[
                             XOr::new(&[And::new(&[
                                 ActionTag::DoubleAuxiliary,
-                            ]), ]),
+                            ])?, ])?,
                             XOr::new(&[And::new(&[
                                 ActionTag::MoveCattleman(ActionValues::Exact(1)),
-                            ]), ]),
-                        ]),
+                            ])?, ])?,
+                        ])?,
                         3 => Or::new(&[
                             XOr::new(&[And::new(&[
                                 ActionTag::DrawCards(ActionValues::EmployeeMult(Employee::Cowboy, 1)),
-                            ]), ]),
+                            ])?, ])?,
                             XOr::new(&[And::new(&[
                                 ActionTag::MoveCattleman(ActionValues::Exact(3)),
-                            ]), ]),
-                        ]),
+                            ])?, ])?,
+                        ])?,
                         4 => Or::new(&[
                             XOr::new(&[And::new(&[
                                 ActionTag::DiscardCard(DiscardCardOpts::Color(CowColor::Angus)),
                                 ActionTag::MoveCertificate(ActionValues::Exact(2)),
-                            ]), ]),
+                            ])?, ])?,
                             XOr::new(&[And::new(&[
                                 ActionTag::TakeCoins(ActionValues::EmployeeMult(Employee::Engineer, 1)),
-                            ]), ]),
-                        ]),
+                            ])?, ])?,
+                        ])?,
                         5 => Or::new(&[
                             XOr::new(&[And::new(&[
                                 ActionTag::DiscardCard(DiscardCardOpts::AnyCow),
                                 ActionTag::TakeCoins(ActionValues::Exact(3)),
                                 ActionTag::TakeObjective,
-                            ]), ]),
-                        ]),
+                            ])?, ])?,
+                        ])?,
                         6 => Or::new(&[
                             XOr::new(&[And::new(&[
                                 ActionTag::MoveEngine(ActionValues::ForestMult(1)),
-                            ]), ]),
-                        ]),
+                            ])?, ])?,
+                        ])?,
                         7 => Or::new(&[
                             XOr::new(&[And::new(&[
                                 ActionTag::AdjacentBuilding,
-                            ]), ]),
-                        ]),
+                            ])?, ])?,
+                        ])?,
                         8 => Or::new(&[
                             XOr::new(&[And::new(&[
                                 ActionTag::StationDiscBehindTrain,
-                            ]), ]),
-                        ]),
+                            ])?, ])?,
+                        ])?,
                         9 => Or::new(&[
                             XOr::new(&[And::new(&[
                                 ActionTag::TakeCoins(ActionValues::Exact(4)),
-                            ]), ]),
+                            ])?, ])?,
                             XOr::new(&[And::new(&[
                                 ActionTag::MoveEngine(ActionValues::Exact(4)),
-                            ]), ]),
+                            ])?, ])?,
                             XOr::new(&[And::new(&[
                                 ActionTag::MoveCattleman(ActionValues::Exact(4)),
-                            ]), ]),
-                        ]),
-                        _ => panic!("Invalid building specified")
+                            ])?, ])?,
+                        ])?,
+                        _ => return Err(GwtError::InvalidBuilding { num, side_b: Some(side_b) })
                     }
                 }
             };
@@ -309,7 +347,7 @@ impl Building {
                 7 => if !side_b { Toll::Green } else { Toll::NoToll },
                 8 => Toll::NoToll,
                 9 => Toll::Black,
-                _ => panic!("Invalid building specified")
+                _ => return Err(GwtError::InvalidBuilding { num, side_b: Some(side_b) })
             }
         };
         let laborers = match num {
@@ -323,7 +361,7 @@ impl Building {
             7 => if !side_b { 5 } else { 6 },
             8 => if !side_b { 7 } else { 6 },
             9 => if !side_b { 9 } else { 8 },
-            _ => panic!("Invalid building specified")
+            _ => return Err(GwtError::InvalidBuilding { num, side_b: Some(side_b) })
         };
         let points = match num {
             0 => 1,
@@ -336,8 +374,8 @@ impl Building {
             7 => if !side_b { 6 } else { 8 },
             8 => if !side_b { 9 } else { 8 },
             9 => if !side_b { 13 } else { 11 },
-            _ => panic!("Invalid building specified")
+            _ => return Err(GwtError::InvalidBuilding { num, side_b: Some(side_b) })
         };
-        Building { owner: 0, laborers, points, toll, actions }
+        Ok(Building { owner: 0, laborers, points, toll, actions })
     }
 }
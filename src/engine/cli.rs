@@ -0,0 +1,29 @@
+use std::fmt;
+use std::process::exit;
+use std::str::FromStr;
+
+// A command line tool's usage, which is printed with whatever was wrong with its arguments
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Usage(pub &'static str);
+
+impl Usage {
+    pub fn fail(&self, message: &str) -> ! {
+        eprintln!("{}", message);
+        eprintln!("{}", self.0);
+        exit(2);
+    }
+
+    // The flag's value, failing when it's missing or isn't a number
+    pub fn number<T: FromStr>(&self, flag: &str, value: Option<String>) -> T {
+        match value.map(|v| { v.parse::<T>() }) {
+            Some(Ok(n)) => n,
+            _ => self.fail(&format!("{} needs a number", flag)),
+        }
+    }
+}
+
+impl fmt::Display for Usage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
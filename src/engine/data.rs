@@ -0,0 +1,206 @@
+use std::fs::read_to_string;
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::buildings::Building;
+use crate::deck::{Cow, Objective};
+use crate::error::DataError;
+use crate::tiles::{Foresight, Tile};
+use crate::tracks::{Space, SpaceOccupant, StationMaster, StationTrack};
+
+// Everything needed to set up a game, as read from the files in data/
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+pub struct GameData {
+    pub track: Vec<Space>,
+    pub station_track: StationTrack,
+    pub station_masters: Vec<StationMaster>,
+    pub neutral_buildings: Vec<Building>,
+    pub player_buildings_a: Vec<Building>,
+    pub player_buildings_b: Vec<Building>,
+    pub starting_deck: Vec<Cow>,
+    pub starting_objectives: Vec<Objective>,
+    pub objectives: Vec<Objective>,
+    pub cow_deck: Vec<Cow>,
+    pub foresight_piles: [Vec<Tile>; Foresight::FORESIGHT_SIZE],
+}
+
+impl GameData {
+    pub const TRACK: &'static str = "default_track.json";
+    pub const STATION_TRACK: &'static str = "default_station_track.json";
+    pub const STATION_MASTERS: &'static str = "default_station_masters.json";
+    pub const NEUTRAL_BUILDINGS: &'static str = "neutral_buildings.json";
+    pub const PLAYER_BUILDINGS_A: &'static str = "player_buildings_a.json";
+    pub const PLAYER_BUILDINGS_B: &'static str = "player_buildings_b.json";
+    pub const STARTING_DECK: &'static str = "player_starting_deck.json";
+    pub const STARTING_OBJECTIVES: &'static str = "player_starting_objectives.json";
+    pub const OBJECTIVES: &'static str = "objectives_deck.json";
+    pub const COW_DECK: &'static str = "cow_deck.json";
+    pub const FORESIGHT_PILES: &'static str = "foresight_piles.json";
+
+    // The default data set, compiled into the library so games can start from any directory
+    const EMBEDDED: [(&'static str, &'static str); 11] = [
+        (GameData::TRACK, include_str!("../../data/default_track.json")),
+        (GameData::STATION_TRACK, include_str!("../../data/default_station_track.json")),
+        (GameData::STATION_MASTERS, include_str!("../../data/default_station_masters.json")),
+        (GameData::NEUTRAL_BUILDINGS, include_str!("../../data/neutral_buildings.json")),
+        (GameData::PLAYER_BUILDINGS_A, include_str!("../../data/player_buildings_a.json")),
+        (GameData::PLAYER_BUILDINGS_B, include_str!("../../data/player_buildings_b.json")),
+        (GameData::STARTING_DECK, include_str!("../../data/player_starting_deck.json")),
+        (GameData::STARTING_OBJECTIVES, include_str!("../../data/player_starting_objectives.json")),
+        (GameData::OBJECTIVES, include_str!("../../data/objectives_deck.json")),
+        (GameData::COW_DECK, include_str!("../../data/cow_deck.json")),
+        (GameData::FORESIGHT_PILES, include_str!("../../data/foresight_piles.json")),
+    ];
+
+    pub fn load(dir: &Path) -> Result<GameData, DataError> {
+        let data = GameData::read(dir)?;
+        data.check()?;
+        Ok(data)
+    }
+
+    // Only parses the files, without checking a game can be set up from them
+    pub fn read(dir: &Path) -> Result<GameData, DataError> {
+        GameData::parseSource(|file| {
+            read_to_string(dir.join(file))
+                .map_err(|e| { DataError::Io { file: file.to_string(), message: e.to_string() } })
+        })
+    }
+
+    pub fn embedded() -> Result<GameData, DataError> {
+        GameData::fromSource(|file| {
+            GameData::EMBEDDED.iter()
+                .find(|(name, _)| { *name == file })
+                .map(|(_, contents)| { contents.to_string() })
+                .ok_or_else(|| { DataError::Io { file: file.to_string(), message: "Not embedded".to_string() } })
+        })
+    }
+
+    // source reads the contents of one of the data files by name
+    pub fn fromSource<F: Fn(&str) -> Result<String, DataError>>(source: F) -> Result<GameData, DataError> {
+        let data = GameData::parseSource(source)?;
+        data.check()?;
+        Ok(data)
+    }
+
+    fn parseSource<F: Fn(&str) -> Result<String, DataError>>(source: F) -> Result<GameData, DataError> {
+        Ok(GameData {
+            track: GameData::parse(&source, GameData::TRACK)?,
+            station_track: GameData::parse(&source, GameData::STATION_TRACK)?,
+            station_masters: GameData::parse(&source, GameData::STATION_MASTERS)?,
+            neutral_buildings: GameData::parse(&source, GameData::NEUTRAL_BUILDINGS)?,
+            player_buildings_a: GameData::parse(&source, GameData::PLAYER_BUILDINGS_A)?,
+            player_buildings_b: GameData::parse(&source, GameData::PLAYER_BUILDINGS_B)?,
+            starting_deck: GameData::parse(&source, GameData::STARTING_DECK)?,
+            starting_objectives: GameData::parse(&source, GameData::STARTING_OBJECTIVES)?,
+            objectives: GameData::parse(&source, GameData::OBJECTIVES)?,
+            cow_deck: GameData::parse(&source, GameData::COW_DECK)?,
+            foresight_piles: GameData::parse(&source, GameData::FORESIGHT_PILES)?,
+        })
+    }
+
+    fn parse<T: DeserializeOwned, F: Fn(&str) -> Result<String, DataError>>(source: &F, file: &str) -> Result<T, DataError> {
+        let contents = source(file)?;
+        serde_json::from_str(&contents).map_err(|e| {
+            DataError::Schema { file: file.to_string(), line: e.line(), column: e.column(), message: e.to_string() }
+        })
+    }
+
+    // FNV-1a of the data set's JSON, for checking a game record is replayed with the data it was played with
+    pub fn hash(&self) -> u64 {
+        let json = serde_json::to_string(self).unwrap_or_default();
+        json.bytes().fold(0xcbf29ce484222325, |hash, byte| { (hash ^ byte as u64).wrapping_mul(0x100000001b3) })
+    }
+
+    pub fn neutralSpaces(&self) -> Vec<usize> {
+        self.track.iter()
+            .enumerate()
+            .filter(|(_, space)| { matches!(space.occupant(), SpaceOccupant::Building { neutral: true, .. }) })
+            .map(|(i, _)| { i })
+            .collect()
+    }
+
+    // The checks a game needs to be set up at all, see the validator for everything else
+    fn check(&self) -> Result<(), DataError> {
+        let invalid = |file: &str, message: String| -> Result<(), DataError> {
+            Err(DataError::Invalid { file: file.to_string(), message })
+        };
+        if !self.track.iter().any(|s| { *s.occupant() == SpaceOccupant::Start }) {
+            return invalid(GameData::TRACK, "No start space".to_string());
+        }
+        if !self.track.iter().any(|s| { *s.occupant() == SpaceOccupant::KansasCity }) {
+            return invalid(GameData::TRACK, "No Kansas City space".to_string());
+        }
+        if self.neutral_buildings.len() != self.neutralSpaces().len() {
+            return invalid(GameData::NEUTRAL_BUILDINGS,
+                           format!("{} neutral buildings for {} neutral spaces",
+                                   self.neutral_buildings.len(), self.neutralSpaces().len()));
+        }
+        if self.station_masters.len() > self.station_track.stations().len() {
+            return invalid(GameData::STATION_MASTERS,
+                           format!("{} station masters for {} stations",
+                                   self.station_masters.len(), self.station_track.stations().len()));
+        }
+        for (file, empty) in [
+            (GameData::PLAYER_BUILDINGS_A, self.player_buildings_a.is_empty()),
+            (GameData::PLAYER_BUILDINGS_B, self.player_buildings_b.is_empty()),
+            (GameData::STARTING_DECK, self.starting_deck.is_empty()),
+            (GameData::STARTING_OBJECTIVES, self.starting_objectives.is_empty()),
+            (GameData::OBJECTIVES, self.objectives.is_empty()),
+            (GameData::COW_DECK, self.cow_deck.is_empty()),
+            (GameData::FORESIGHT_PILES, self.foresight_piles.iter().any(|pile| { pile.is_empty() })),
+        ] {
+            if empty {
+                return invalid(file, "Nothing to deal from".to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn testEmbeddedMatchesDirectory() {
+        let embedded = GameData::embedded().unwrap();
+        assert_eq!(GameData::load(Path::new("./data")).unwrap(), embedded);
+        assert_eq!(embedded.neutralSpaces().len(), 7);
+        assert_eq!(embedded.foresight_piles.iter().map(|p| { p.len() }).sum::<usize>(), 35 + 33 + 26);
+    }
+
+    #[test]
+    fn testSchemaError() {
+        let dir = env::temp_dir().join(format!("gwt_test_schema_error_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for (file, contents) in GameData::EMBEDDED {
+            fs::write(dir.join(file), contents).unwrap();
+        }
+        fs::write(dir.join(GameData::COW_DECK), "[\n  {\n    \"color\": \"Purple\",\n    \"points\": 1\n  }\n]").unwrap();
+        match GameData::load(&dir) {
+            Err(DataError::Schema { file, line, .. }) => {
+                assert_eq!(file, GameData::COW_DECK);
+                assert_eq!(line, 3);
+            }
+            result => panic!("Expected a schema error, found {:?}", result),
+        }
+
+        fs::remove_file(dir.join(GameData::COW_DECK)).unwrap();
+        match GameData::load(&dir) {
+            Err(DataError::Io { file, .. }) => assert_eq!(file, GameData::COW_DECK),
+            result => panic!("Expected an io error, found {:?}", result),
+        }
+
+        fs::write(dir.join(GameData::COW_DECK), "[]").unwrap();
+        match GameData::load(&dir) {
+            Err(DataError::Invalid { file, .. }) => assert_eq!(file, GameData::COW_DECK),
+            result => panic!("Expected an invalid data error, found {:?}", result),
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,205 @@
+// Training examples stored as little endian binary. The header is the magic bytes, the file format version,
+// the features layout version, the feature size and the action space size, each a u32 after the magic.
+// Every example after it is the number of legal actions, the features, each legal action's index and
+// visit share, and the final value:
+//
+//   u32 legal, f32 x feature size, (u32 index, f32 share) x legal, f32 value
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::Path;
+
+use crate::error::{DataError, GwtError};
+use crate::features::{ACTION_SPACE, FEATURE_SIZE, LAYOUT_VERSION};
+use crate::network::Example;
+
+pub const MAGIC: [u8; 4] = *b"GWTX";
+pub const FORMAT_VERSION: u32 = 1;
+
+pub struct ExampleWriter<W: Write> {
+    out: W,
+    written: u64,
+}
+
+impl ExampleWriter<BufWriter<File>> {
+    pub fn create(path: &Path) -> Result<ExampleWriter<BufWriter<File>>, GwtError> {
+        let file = File::create(path).map_err(|e| { GwtError::Output(e.to_string()) })?;
+        ExampleWriter::new(BufWriter::new(file))
+    }
+}
+
+impl<W: Write> ExampleWriter<W> {
+    pub fn new(mut out: W) -> Result<ExampleWriter<W>, GwtError> {
+        let mut header = MAGIC.to_vec();
+        for value in [FORMAT_VERSION, LAYOUT_VERSION, FEATURE_SIZE as u32, ACTION_SPACE as u32] {
+            header.extend(value.to_le_bytes());
+        }
+        out.write_all(&header).map_err(|e| { GwtError::Output(e.to_string()) })?;
+        Ok(ExampleWriter { out, written: 0 })
+    }
+
+    pub fn written(&self) -> u64 { self.written }
+
+    pub fn write(&mut self, example: &Example) -> Result<(), GwtError> {
+        let mut bytes = Vec::with_capacity(4 * (2 + example.features.len() + 2 * example.legal.len()));
+        bytes.extend((example.legal.len() as u32).to_le_bytes());
+        for x in example.features.iter() {
+            bytes.extend(x.to_le_bytes());
+        }
+        for (index, share) in example.legal.iter().zip(example.policy.iter()) {
+            bytes.extend((*index as u32).to_le_bytes());
+            bytes.extend(share.to_le_bytes());
+        }
+        bytes.extend(example.value.to_le_bytes());
+        self.out.write_all(&bytes).map_err(|e| { GwtError::Output(e.to_string()) })?;
+        self.written += 1;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<W, GwtError> {
+        self.out.flush().map_err(|e| { GwtError::Output(e.to_string()) })?;
+        Ok(self.out)
+    }
+}
+
+// Reads the examples back, the header has to match this build's features layout
+pub struct ExampleReader<R: Read> {
+    input: R,
+    // Used to name the source in errors
+    name: String,
+}
+
+impl ExampleReader<BufReader<File>> {
+    pub fn open(path: &Path) -> Result<ExampleReader<BufReader<File>>, GwtError> {
+        let name = path.display().to_string();
+        let file = File::open(path).map_err(|e| { DataError::Io { file: name.clone(), message: e.to_string() } })?;
+        ExampleReader::new(BufReader::new(file), &name)
+    }
+}
+
+impl<R: Read> ExampleReader<R> {
+    pub fn new(input: R, name: &str) -> Result<ExampleReader<R>, GwtError> {
+        let mut reader = ExampleReader { input, name: name.to_string() };
+        let mut magic = [0; 4];
+        reader.input.read_exact(&mut magic).map_err(|e| { reader.ioError(e) })?;
+        if magic != MAGIC {
+            return Err(reader.invalid("Not a training examples file".to_string()));
+        }
+        let expected = [("format version", FORMAT_VERSION), ("features layout", LAYOUT_VERSION),
+            ("feature size", FEATURE_SIZE as u32), ("action space", ACTION_SPACE as u32)];
+        for (field, value) in expected {
+            let found = reader.u32()?;
+            if found != value {
+                return Err(reader.invalid(format!("The file's {} is {}, expected {}", field, found, value)));
+            }
+        }
+        Ok(reader)
+    }
+
+    fn ioError(&self, e: std::io::Error) -> GwtError {
+        DataError::Io { file: self.name.clone(), message: e.to_string() }.into()
+    }
+
+    fn invalid(&self, message: String) -> GwtError {
+        DataError::Invalid { file: self.name.clone(), message }.into()
+    }
+
+    fn u32(&mut self) -> Result<u32, GwtError> {
+        let mut bytes = [0; 4];
+        self.input.read_exact(&mut bytes).map_err(|e| { self.ioError(e) })?;
+        Ok(u32::from_le_bytes(bytes))
+    }
+
+    fn f32(&mut self) -> Result<f32, GwtError> {
+        Ok(f32::from_bits(self.u32()?))
+    }
+
+    // None at the end of the file, which has to fall between two examples
+    pub fn nextExample(&mut self) -> Result<Option<Example>, GwtError> {
+        let mut bytes = [0; 4];
+        let mut read = 0;
+        while read < bytes.len() {
+            match self.input.read(&mut bytes[read..]) {
+                Ok(0) => break,
+                Ok(n) => read += n,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(self.ioError(e)),
+            }
+        }
+        match read {
+            0 => return Ok(None),
+            4 => {}
+            _ => return Err(self.invalid(format!("The last example is cut off after {} bytes", read))),
+        }
+        let legal_len = u32::from_le_bytes(bytes) as usize;
+        if legal_len > ACTION_SPACE {
+            return Err(self.invalid(format!("An example has {} legal actions", legal_len)));
+        }
+        let features = (0..FEATURE_SIZE).map(|_| { self.f32() }).collect::<Result<Vec<f32>, GwtError>>()?;
+        let mut legal = Vec::with_capacity(legal_len);
+        let mut policy = Vec::with_capacity(legal_len);
+        for _ in 0..legal_len {
+            let index = self.u32()? as usize;
+            if index >= ACTION_SPACE {
+                return Err(self.invalid(format!("An example has legal action {}, beyond the action space", index)));
+            }
+            legal.push(index);
+            policy.push(self.f32()?);
+        }
+        let value = self.f32()?;
+        Ok(Some(Example { features, legal, policy, value }))
+    }
+
+    pub fn readAll(mut self) -> Result<Vec<Example>, GwtError> {
+        let mut examples = vec![];
+        while let Some(example) = self.nextExample()? {
+            examples.push(example);
+        }
+        Ok(examples)
+    }
+}
+
+// The legal action mask of an example, 1.0 at each legal index
+pub fn mask(example: &Example) -> Vec<f32> {
+    let mut mask = vec![0.0; ACTION_SPACE];
+    for index in example.legal.iter() {
+        mask[*index] = 1.0;
+    }
+    mask
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn testRoundTrip() {
+        let examples = vec![
+            Example { features: vec![1.5; FEATURE_SIZE], legal: vec![3, 7], policy: vec![0.25, 0.75], value: 1.0 },
+            Example { features: vec![0.0; FEATURE_SIZE], legal: vec![], policy: vec![], value: 0.5 },
+        ];
+        let mut writer = ExampleWriter::new(Vec::<u8>::new()).unwrap();
+        for example in examples.iter() {
+            writer.write(example).unwrap();
+        }
+        assert_eq!(writer.written(), 2);
+        let bytes = writer.finish().unwrap();
+        assert_eq!(ExampleReader::new(&bytes[..], "test").unwrap().readAll().unwrap(), examples);
+        assert_eq!(mask(&examples[0]).iter().sum::<f32>(), 2.0);
+
+        let mut wrong_layout = bytes.clone();
+        wrong_layout[8] += 1;
+        assert!(matches!(ExampleReader::new(&wrong_layout[..], "test"), Err(GwtError::Data(DataError::Invalid { .. }))));
+        let truncated = &bytes[..bytes.len() - 2];
+        assert!(ExampleReader::new(truncated, "test").unwrap().readAll().is_err());
+        // A record cut off inside its length
+        let mut cut = bytes.clone();
+        cut.extend_from_slice(&[1, 0]);
+        assert!(matches!(ExampleReader::new(&cut[..], "test").unwrap().readAll(), Err(GwtError::Data(DataError::Invalid { .. }))));
+
+        let mut writer = ExampleWriter::new(Vec::<u8>::new()).unwrap();
+        writer.write(&Example { features: vec![0.0; FEATURE_SIZE], legal: vec![ACTION_SPACE], policy: vec![1.0], value: 0.0 }).unwrap();
+        let beyond = writer.finish().unwrap();
+        assert!(matches!(ExampleReader::new(&beyond[..], "test").unwrap().readAll(), Err(GwtError::Data(DataError::Invalid { .. }))));
+    }
+}
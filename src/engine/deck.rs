@@ -1,21 +1,36 @@
+use std::cmp::Ordering;
 use std::mem::swap;
 
-use rand::{SeedableRng, thread_rng};
+use rand::{Rng, SeedableRng, thread_rng};
 use rand::prelude::SliceRandom;
 use rand_pcg::Pcg64;
 use serde::{Deserialize, Serialize};
 
 use crate::actions::ActionTag;
 use crate::deck::Card::{CowCard, ObjectiveCard};
+use crate::error::{DeckError, GwtError};
 use crate::logical::And;
 use crate::player::ObjectiveResources;
 
-#[derive(Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize, Debug)]
 pub enum Card {
     CowCard(Cow),
     ObjectiveCard(Objective),
 }
 
+// A card move the engine may have to take back, with whatever the move lost
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub(crate) enum DeckChange {
+    // The top card went to the hand, after the discard pile in this order was reshuffled with this rng
+    Drew(Option<(Vec<Card>, Pcg64)>),
+    // The card at this index of the hand went to the discard pile
+    Played(usize),
+    Trashed(usize, Card),
+    Added,
+    // The hand of this many cards went to the discard pile
+    HandDiscarded(usize),
+}
+
 #[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
 pub struct Deck {
     hand: Vec<Card>,
@@ -23,25 +38,45 @@ pub struct Deck {
     discard: Vec<Card>,
     hand_size: usize,
     rng: Pcg64,
+    // The moves made while the engine records an action
+    #[serde(skip)]
+    log: Option<Vec<DeckChange>>,
 }
 
 impl Deck {
     pub fn new_unshuffled(hand_size: usize, draw: Vec<Card>) -> Deck {
+        Deck::new_unshuffled_seeded(hand_size, draw, Pcg64::from_rng(thread_rng()).unwrap())
+    }
+
+    pub fn new_unshuffled_seeded(hand_size: usize, draw: Vec<Card>, rng: Pcg64) -> Deck {
         Deck {
             hand: Vec::with_capacity(hand_size + 2),
             draw,
             discard: Vec::with_capacity(hand_size * 4),
             hand_size,
-            rng: Pcg64::from_rng(thread_rng()).unwrap(),
+            rng,
+            log: None,
         }
     }
 
+    // A deck with its cards already dealt out, for a decoded position
+    pub(crate) fn restored(hand: Vec<Card>, draw: Vec<Card>, discard: Vec<Card>, hand_size: usize, seed: u64) -> Deck {
+        Deck { hand, draw, discard, hand_size, rng: Pcg64::seed_from_u64(seed), log: None }
+    }
+
     pub fn new(hand_size: usize, pile: Vec<Card>) -> Deck {
         let mut deck = Deck::new_unshuffled(hand_size, pile);
         deck.draw.shuffle(&mut deck.rng);
         deck
     }
 
+    // Reshuffles and redraws the same way every time for a given seed
+    pub fn new_seeded(hand_size: usize, pile: Vec<Card>, seed: u64) -> Deck {
+        let mut deck = Deck::new_unshuffled_seeded(hand_size, pile, Pcg64::seed_from_u64(seed));
+        deck.draw.shuffle(&mut deck.rng);
+        deck
+    }
+
     // Refills either to the hand limit, or until all cards are in the hand
     pub fn refillHand(&mut self) {
         while self.hand.len() < self.hand_size {
@@ -51,15 +86,62 @@ impl Deck {
         }
     }
 
-    pub fn drawCard(&mut self) -> Result<(), String> {
-        if self.draw.len() == 0 && self.discard.len() > 0 {
+    pub fn hand(&self) -> &Vec<Card> { &self.hand }
+
+    pub fn handSize(&self) -> usize { self.hand_size }
+
+    pub fn discardPile(&self) -> &Vec<Card> { &self.discard }
+
+    // In draw order, which only the engine may know
+    pub(crate) fn drawPile(&self) -> &Vec<Card> { &self.draw }
+
+    // The cards in the draw pile, and the hand when include_hand is set, counted since their order is hidden
+    pub fn unseenCounts(&self, include_hand: bool) -> Vec<(Card, u32)> {
+        let hand: &[Card] = if include_hand { &self.hand } else { &[] };
+        let mut counts = Vec::<(Card, u32)>::new();
+        for card in self.draw.iter().chain(hand.iter()) {
+            match counts.iter_mut().find(|(c, _)| { c == card }) {
+                Some((_, n)) => *n += 1,
+                None => counts.push((*card, 1)),
+            }
+        }
+        // Cards aren't ordered, but the order they were found in would give the hidden order away. Cows come first by
+        // colour and points, then objectives by how they print, which is only worked out for the few there are
+        counts.sort_by(|(a, _), (b, _)| {
+            match (a, b) {
+                (CowCard(a), CowCard(b)) => (a.color() as u32, a.points()).cmp(&(b.color() as u32, b.points())),
+                (CowCard(_), ObjectiveCard(_)) => Ordering::Less,
+                (ObjectiveCard(_), CowCard(_)) => Ordering::Greater,
+                (ObjectiveCard(a), ObjectiveCard(b)) => format!("{:?}", a).cmp(&format!("{:?}", b)),
+            }
+        });
+        counts
+    }
+
+    pub fn setHandSize(&mut self, hand_size: usize) {
+        self.hand_size = hand_size;
+    }
+
+    // Moves every card in the hand to the discard pile
+    pub fn discardHand(&mut self) {
+        self.note(DeckChange::HandDiscarded(self.hand.len()));
+        self.discard.append(&mut self.hand);
+    }
+
+    pub fn drawCard(&mut self) -> Result<(), DeckError> {
+        let mut reshuffled = None;
+        if self.draw.is_empty() && !self.discard.is_empty() {
+            if self.log.is_some() {
+                reshuffled = Some((self.discard.clone(), self.rng.clone()));
+            }
             self.shuffleDiscard();
         }
-        if self.draw.len() > 0 {
+        if !self.draw.is_empty() {
             self.hand.push(self.draw.pop().unwrap());
+            self.note(DeckChange::Drew(reshuffled));
             Result::Ok(())
         } else {
-            Result::Err("No cards left to draw".to_string())
+            Result::Err(DeckError::NoCardsLeft)
         }
     }
 
@@ -68,33 +150,98 @@ impl Deck {
         self.draw.shuffle(&mut self.rng);
     }
 
+    // Reshuffles what an observer can't see: the draw pile, and the hand when it isn't theirs.
+    // The deck gets a new rng so later reshuffles can't be predicted either
+    pub fn determinize<R: Rng>(&mut self, rng: &mut R, hide_hand: bool) {
+        let hand_size = self.hand.len();
+        if hide_hand {
+            self.draw.append(&mut self.hand);
+        }
+        self.draw.shuffle(rng);
+        if hide_hand {
+            let split = self.draw.len() - hand_size;
+            self.hand = self.draw.split_off(split);
+        }
+        self.rng = Pcg64::seed_from_u64(rng.gen());
+    }
+
     pub fn addCard(&mut self, card: Card) {
         self.discard.push(card);
+        self.note(DeckChange::Added);
     }
 
-    pub fn trashCard(&mut self, card: Card) -> Result<(), String> {
-        match self.hand.iter().position(|c| { *c == card }) {
-            Some(idx) => {
-                self.hand.remove(idx);
-                Ok(())
-            }
-            None => Err("Card isn't in hand".to_string())
+    pub fn trashCard(&mut self, card: Card) -> Result<(), DeckError> {
+        let idx = self.takeFromHand(card)?;
+        self.note(DeckChange::Trashed(idx, card));
+        Ok(())
+    }
+
+    pub fn playCard(&mut self, card: Card) -> Result<(), DeckError> {
+        let idx = self.takeFromHand(card)?;
+        self.discard.push(card);
+        self.note(DeckChange::Played(idx));
+        Ok(())
+    }
+
+    fn takeFromHand(&mut self, card: Card) -> Result<usize, DeckError> {
+        let idx = self.hand.iter().position(|c| { *c == card }).ok_or(DeckError::CardNotInHand(card))?;
+        self.hand.remove(idx);
+        Ok(idx)
+    }
+
+    fn note(&mut self, change: DeckChange) {
+        if let Some(log) = self.log.as_mut() {
+            log.push(change);
         }
     }
 
-    pub fn playCard(&mut self, card: Card) -> Result<(), String> {
-        match self.trashCard(card) {
-            Ok(()) => {
-                self.discard.push(card);
-                Ok(())
+    // Logs the card moves from now on
+    pub(crate) fn startLog(&mut self) {
+        self.log.get_or_insert_with(Vec::new);
+    }
+
+    // Stops logging, returning the moves logged
+    pub(crate) fn takeLog(&mut self) -> Vec<DeckChange> {
+        self.log.take().unwrap_or_default()
+    }
+
+    // Takes back a logged move, the moves after it must have been taken back already
+    pub(crate) fn revert(&mut self, change: DeckChange) {
+        match change {
+            DeckChange::Drew(reshuffled) => {
+                if let Some(card) = self.hand.pop() {
+                    self.draw.push(card);
+                }
+                if let Some((discard, rng)) = reshuffled {
+                    self.draw.clear();
+                    self.discard = discard;
+                    self.rng = rng;
+                }
+            }
+            DeckChange::Played(idx) => {
+                if let Some(card) = self.discard.pop() {
+                    self.hand.insert(idx, card);
+                }
+            }
+            DeckChange::Trashed(idx, card) => self.hand.insert(idx, card),
+            DeckChange::Added => {
+                self.discard.pop();
+            }
+            DeckChange::HandDiscarded(n) => {
+                let split = self.discard.len() - n;
+                self.hand = self.discard.split_off(split);
             }
-            err => err
         }
     }
 
     pub fn handValue(&self) -> u32 {
-        let mut cows = Deck::deckCowCards(&self.hand);
-        if cows.len() > 0 {
+        Deck::cardsValue(&self.hand)
+    }
+
+    // What the cards would sell for in Kansas City, each breed counts once
+    pub(crate) fn cardsValue(cards: &[Card]) -> u32 {
+        let mut cows = Deck::deckCowCards(cards);
+        if !cows.is_empty() {
             cows.sort_by_key(|cow| { cow.color });
             cows.dedup_by_key(|cow| { cow.color });
             cows.iter().map(|cow| { cow.value() })
@@ -106,10 +253,10 @@ impl Deck {
 
     pub fn cowInHand(&self, color: CowColor) -> Option<Cow> {
         let cows: Vec<Cow> = Deck::deckCowCards(&self.hand).iter()
-            .filter(|c| { (**c).color == color })
-            .map(|c| { *c })
+            .filter(|c| { c.color == color })
+            .copied()
             .collect();
-        if cows.len() > 0 {
+        if !cows.is_empty() {
             Some(cows[0])
         } else {
             None
@@ -118,9 +265,9 @@ impl Deck {
 
     pub fn pairInHand(&self) -> Vec<CowColor> {
         let mut cows: Vec<CowColor> = Deck::deckCowCards(&self.hand).iter()
-            .map(|c| { (*c).color })
+            .map(|c| { c.color })
             .collect();
-        if cows.len() == 0 {
+        if cows.is_empty() {
             vec![]
         } else {
             cows.sort_unstable();
@@ -128,7 +275,7 @@ impl Deck {
             let mut iter = cows.iter();
             let mut prev = iter.next().unwrap();
             let mut next = iter.next();
-            while next != None {
+            while next.is_some() {
                 if *prev == *next.unwrap() && !dup_cow.contains(prev) {
                     dup_cow.push(*prev);
                 }
@@ -143,7 +290,7 @@ impl Deck {
         Deck::deckCowCards(&self.hand).iter()
             .chain(Deck::deckCowCards(&self.draw).iter())
             .chain(Deck::deckCowCards(&self.discard).iter())
-            .map(|c| { *c })
+            .copied()
             .collect()
     }
 
@@ -158,26 +305,26 @@ impl Deck {
         Deck::deckObjectiveCards(&self.hand).iter()
             .chain(Deck::deckObjectiveCards(&self.draw).iter())
             .chain(Deck::deckObjectiveCards(&self.discard).iter())
-            .map(|obj| { *obj })
+            .copied()
             .collect()
     }
 
-    fn deckCowCards(cards: &Vec<Card>) -> Vec<Cow> {
+    fn deckCowCards(cards: &[Card]) -> Vec<Cow> {
         cards.iter()
-            .filter(|card| { if let CowCard(_) = **card { true } else { false } })
+            .filter(|card| { matches!(card, CowCard(_)) })
             .map(|cow_card| { if let CowCard(cow) = *cow_card { cow } else { unreachable!() } })
             .collect()
     }
 
-    fn deckObjectiveCards(cards: &Vec<Card>) -> Vec<Objective> {
+    fn deckObjectiveCards(cards: &[Card]) -> Vec<Objective> {
         cards.iter()
-            .filter(|card| { if let ObjectiveCard(_) = **card { true } else { false } })
+            .filter(|card| { matches!(card, ObjectiveCard(_)) })
             .map(|obj_card| { if let ObjectiveCard(obj) = *obj_card { obj } else { unreachable!() } })
             .collect()
     }
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Ord, PartialOrd, Serialize, Deserialize, Debug)]
 pub enum CowColor {
     Jersey,
     Dutch,
@@ -190,7 +337,7 @@ pub enum CowColor {
     Longhorn,
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize, Debug)]
 pub struct Cow {
     color: CowColor,
     points: u32,
@@ -201,6 +348,10 @@ impl Cow {
         Cow { color, points }
     }
 
+    pub fn color(&self) -> CowColor { self.color }
+
+    pub fn points(&self) -> u32 { self.points }
+
     pub fn value(&self) -> u32 {
         match self.color {
             CowColor::Jersey => 1,
@@ -224,17 +375,112 @@ pub struct CowMarket {
     purple_market: Vec<Cow>,
 }
 
+impl Default for CowMarket {
+    fn default() -> CowMarket {
+        CowMarket::new()
+    }
+}
+
 impl CowMarket {
     pub fn new() -> CowMarket {
-        let cow_deck = vec![Cow::new(CowColor::Holstein, 3)];
+        CowMarket::fromDeck(vec![Cow::new(CowColor::Holstein, 3)])
+    }
+
+    // Cows are dealt from the back of the deck, so it should already be shuffled
+    pub fn fromDeck(cow_deck: Vec<Cow>) -> CowMarket {
         let ryb_market = Vec::<Cow>::new();
         let brown_market = Vec::<Cow>::new();
         let purple_market = Vec::<Cow>::new();
         CowMarket { cow_deck, ryb_market, brown_market, purple_market }
     }
+
+    // The cows are put on display in the order given, for a decoded position
+    pub(crate) fn restored(cow_deck: Vec<Cow>, market: &[Cow]) -> CowMarket {
+        let mut cows = CowMarket::fromDeck(cow_deck);
+        for cow in market.iter() {
+            cows.market(*cow).push(*cow);
+        }
+        cows
+    }
+
+    pub(crate) fn deck(&self) -> &[Cow] { &self.cow_deck }
+
+    // Every cow on display, cheapest first
+    pub fn cows(&self) -> Vec<Cow> {
+        self.ryb_market.iter()
+            .chain(self.brown_market.iter())
+            .chain(self.purple_market.iter())
+            .copied()
+            .collect()
+    }
+
+    // Removes a cow from the display, returns false if it isn't there
+    pub fn buy(&mut self, cow: Cow) -> bool {
+        self.take(cow).is_some()
+    }
+
+    // The market a cow is displayed in goes by its value
+    fn market(&mut self, cow: Cow) -> &mut Vec<Cow> {
+        match cow.value() {
+            4 => &mut self.brown_market,
+            5 => &mut self.purple_market,
+            _ => &mut self.ryb_market,
+        }
+    }
+
+    // Removes a cow from the display, returning where it was in its market
+    pub(crate) fn take(&mut self, cow: Cow) -> Option<usize> {
+        let market = self.market(cow);
+        let idx = market.iter().position(|c| { *c == cow })?;
+        market.remove(idx);
+        Some(idx)
+    }
+
+    pub(crate) fn putBack(&mut self, idx: usize, cow: Cow) {
+        self.market(cow).insert(idx, cow);
+    }
+
+    pub fn deckSize(&self) -> usize { self.cow_deck.len() }
+
+    pub fn marketSize(&self) -> usize {
+        self.ryb_market.len() + self.brown_market.len() + self.purple_market.len()
+    }
+
+    pub fn shuffleDeck<R: Rng>(&mut self, rng: &mut R) {
+        self.cow_deck.shuffle(rng);
+    }
+
+    // Deals cows from the cow deck until the market holds market_size cows or the deck runs out
+    pub fn refill(&mut self, market_size: usize) {
+        self.deal(market_size);
+    }
+
+    // Refills like refill, returning the cows dealt in the order they were dealt
+    pub(crate) fn deal(&mut self, market_size: usize) -> Vec<Cow> {
+        let mut dealt = vec![];
+        while self.marketSize() < market_size {
+            match self.cow_deck.pop() {
+                Some(cow) => {
+                    self.market(cow).push(cow);
+                    dealt.push(cow);
+                }
+                None => break,
+            }
+        }
+        dealt
+    }
+
+    // Puts dealt cows back on top of the deck
+    pub(crate) fn undeal(&mut self, dealt: &[Cow]) {
+        for cow in dealt.iter().rev() {
+            if let Some(cow) = self.market(*cow).pop() {
+                self.cow_deck.push(cow);
+            }
+        }
+    }
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize, Debug)]
 pub enum ObjectiveRequirements {
     Building,
     Hazard,
@@ -245,7 +491,7 @@ pub enum ObjectiveRequirements {
     Cow(u32), // The cows value
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize, Debug)]
 pub struct Objective {
     immediate: Option<ActionTag>,
     success_pts: u32,
@@ -255,82 +501,88 @@ pub struct Objective {
 
 impl Objective {
     pub fn new(immediate: Option<ActionTag>, success_pts: u32, fail_pts: u32,
-               requirements: &[ObjectiveRequirements]) -> Objective {
-        Objective { immediate, success_pts, fail_pts, requirements: And::new(requirements) }
+               requirements: &[ObjectiveRequirements]) -> Result<Objective, GwtError> {
+        Ok(Objective { immediate, success_pts, fail_pts, requirements: And::new(requirements)? })
     }
 
+    pub fn immediate(&self) -> Option<ActionTag> { self.immediate }
+
+    pub fn successPoints(&self) -> u32 { self.success_pts }
+
+    pub fn failPoints(&self) -> u32 { self.fail_pts }
+
+    pub fn requirements(&self) -> &And<ObjectiveRequirements, 4> { &self.requirements }
+
     pub fn meetsRequirements(&self, mut resources: ObjectiveResources) -> Option<ObjectiveResources> {
-        for req_opt in self.requirements.items {
-            if let Some(obj_req) = req_opt {
-                let req_test =
-                    match obj_req {
-                        ObjectiveRequirements::Building => {
-                            if resources.buildings > 0
-                            {
-                                resources.buildings -= 1;
-                                true
-                            } else { false }
-                        }
-                        ObjectiveRequirements::Hazard => {
-                            if resources.hazards > 0
-                            {
-                                resources.hazards -= 1;
-                                true
-                            } else { false }
-                        }
-                        ObjectiveRequirements::SanFran => {
-                            if resources.san_fran > 0
-                            {
-                                resources.san_fran -= 1;
-                                true
-                            } else { false }
-                        }
-                        ObjectiveRequirements::GreenTepee => {
-                            if resources.green_tepees > 0
+        for obj_req in self.requirements.items.iter().flatten().copied() {
+            let req_test =
+                match obj_req {
+                    ObjectiveRequirements::Building => {
+                        if resources.buildings > 0
+                        {
+                            resources.buildings -= 1;
+                            true
+                        } else { false }
+                    }
+                    ObjectiveRequirements::Hazard => {
+                        if resources.hazards > 0
+                        {
+                            resources.hazards -= 1;
+                            true
+                        } else { false }
+                    }
+                    ObjectiveRequirements::SanFran => {
+                        if resources.san_fran > 0
+                        {
+                            resources.san_fran -= 1;
+                            true
+                        } else { false }
+                    }
+                    ObjectiveRequirements::GreenTepee => {
+                        if resources.green_tepees > 0
+                        {
+                            resources.green_tepees -= 1;
+                            true
+                        } else { false }
+                    }
+                    ObjectiveRequirements::BlueTepee => {
+                        if resources.blue_tepees > 0
+                        {
+                            resources.blue_tepees -= 1;
+                            true
+                        } else { false }
+                    }
+                    ObjectiveRequirements::StationDisc => {
+                        if resources.station_discs > 0
+                        {
+                            resources.station_discs -= 1;
+                            true
+                        } else { false }
+                    }
+                    ObjectiveRequirements::Cow(v) => {
+                        if v == 3 {
+                            if resources.ryb_cows > 0
                             {
-                                resources.green_tepees -= 1;
+                                resources.ryb_cows -= 1;
                                 true
                             } else { false }
-                        }
-                        ObjectiveRequirements::BlueTepee => {
-                            if resources.blue_tepees > 0
+                        } else if v == 4 {
+                            if resources.brown_cows > 0
                             {
-                                resources.blue_tepees -= 1;
+                                resources.brown_cows -= 1;
                                 true
                             } else { false }
-                        }
-                        ObjectiveRequirements::StationDisc => {
-                            if resources.station_discs > 0
+                        } else if v == 5 {
+                            if resources.purple_cows > 0
                             {
-                                resources.station_discs -= 1;
+                                resources.purple_cows -= 1;
                                 true
                             } else { false }
-                        }
-                        ObjectiveRequirements::Cow(v) => {
-                            if v == 3 {
-                                if resources.ryb_cows > 0
-                                {
-                                    resources.ryb_cows -= 1;
-                                    true
-                                } else { false }
-                            } else if v == 4 {
-                                if resources.brown_cows > 0
-                                {
-                                    resources.brown_cows -= 1;
-                                    true
-                                } else { false }
-                            } else if v == 5 {
-                                if resources.purple_cows > 0
-                                {
-                                    resources.purple_cows -= 1;
-                                    true
-                                } else { false }
-                            } else { unreachable!() }
-                        }
-                    };
-                if req_test == false {
-                    return None;
-                }
+                        } else { unreachable!() }
+                    }
+                };
+            if !req_test {
+                return None;
             }
         }
         Some(resources)
@@ -369,6 +621,8 @@ mod tests {
         assert_eq!(d.handValue(), 4);
 
         assert_ne!(d.trashCard(Card::CowCard(Cow::new(CowColor::Jersey, 0))), Ok(()));
+        assert_eq!(d.trashCard(Card::CowCard(Cow::new(CowColor::Jersey, 0))),
+                   Err(DeckError::CardNotInHand(Card::CowCard(Cow::new(CowColor::Jersey, 0)))));
         assert_eq!(d.pairInHand(), vec![CowColor::Angus]);
         assert_eq!(d.hand.len(), d.hand_size);
         assert_eq!(d.draw.len(), starting_cows.len() - d.hand_size);
@@ -409,7 +663,7 @@ mod tests {
         assert_eq!(d.handValue(), 2);
 
         assert_eq!(d.objectiveCards(), Vec::<Objective>::new());
-        let obj = Objective::new(None, 5, 5, &[]);
+        let obj = Objective::new(None, 5, 5, &[]).unwrap();
         d.addCard(Card::ObjectiveCard(obj));
         assert_eq!(d.pairInHand(), Vec::<CowColor>::new());
         assert_eq!(d.hand.len(), d.hand_size - 3);
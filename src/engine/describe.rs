@@ -0,0 +1,232 @@
+// Short English for the rules printed on buildings, station masters and objectives, for tooltips and reports:
+// "Either: discard a Guernsey for $2; and/or hire an employee; and/or hire an employee for $2 more"
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use crate::actions::{ActionTag, ActionValues, DiscardCardOpts};
+use crate::buildings::Building;
+use crate::deck::{Objective, ObjectiveRequirements};
+use crate::logical::{And, XOr};
+use crate::tracks::StationMaster;
+
+pub trait Describe {
+    fn describe(&self) -> String;
+}
+
+pub(crate) fn plural(n: u32, noun: &str) -> String {
+    if n == 1 { format!("1 {}", noun) } else { format!("{} {}s", n, noun) }
+}
+
+pub(crate) fn article(word: &str) -> &'static str {
+    if word.starts_with(['A', 'E', 'I', 'O', 'U', 'a', 'e', 'i', 'o', 'u']) { "an" } else { "a" }
+}
+
+// The multiplier, whose sign says which way the action goes
+fn multiplier(v: ActionValues) -> i32 {
+    match v {
+        ActionValues::Exact(n) | ActionValues::EmployeeMult(_, n) | ActionValues::AllEmployeesMult(n)
+        | ActionValues::ForestMult(n) | ActionValues::TepeePairMult(n) | ActionValues::HazardPairMult(n)
+        | ActionValues::CertificatePairMult(n) | ActionValues::ObjectivePairMult(n) | ActionValues::AtMost(n) => n,
+        ActionValues::Max => 1,
+    }
+}
+
+// How much of something a value gives, ignoring its sign. unit words a whole amount: "2 cards" or "$2"
+fn amount(v: ActionValues, unit: &dyn Fn(u32) -> String) -> String {
+    let n = multiplier(v).unsigned_abs();
+    match v {
+        ActionValues::Exact(_) => unit(n),
+        ActionValues::EmployeeMult(emp, _) => format!("{} per {:?}", unit(n), emp),
+        ActionValues::AllEmployeesMult(_) => format!("{} per employee", unit(n)),
+        ActionValues::ForestMult(_) => format!("{} per building of yours in the forest", unit(n)),
+        ActionValues::TepeePairMult(_) => format!("{} per pair of green and blue tepees", unit(n)),
+        ActionValues::HazardPairMult(_) => format!("{} per pair of hazards", unit(n)),
+        ActionValues::CertificatePairMult(_) => format!("{} per pair of certificates", unit(n)),
+        ActionValues::ObjectivePairMult(_) => format!("{} per pair of completed objectives", unit(n)),
+        ActionValues::AtMost(_) => format!("up to {}", unit(n)),
+        ActionValues::Max => "as many as possible".to_string(),
+    }
+}
+
+fn dollars(v: ActionValues) -> String {
+    amount(v, &|n| { format!("${}", n) })
+}
+
+fn counted(v: ActionValues, noun: &str) -> String {
+    amount(v, &|n| { plural(n, noun) })
+}
+
+impl Describe for ActionTag {
+    fn describe(&self) -> String {
+        match *self {
+            ActionTag::TakeCoins(v) if multiplier(v) < 0 => format!("pay {}", dollars(v)),
+            ActionTag::TakeCoins(v) => format!("take {}", dollars(v)),
+            ActionTag::BuyCows => "buy cows".to_string(),
+            ActionTag::DiscardCard(DiscardCardOpts::AnyCow) => "discard a cow".to_string(),
+            ActionTag::DiscardCard(DiscardCardOpts::Color(color)) => {
+                let color = format!("{:?}", color);
+                format!("discard {} {}", article(&color), color)
+            }
+            ActionTag::DiscardCard(DiscardCardOpts::PairCow) => "discard two cows of one breed".to_string(),
+            ActionTag::DiscardCard(DiscardCardOpts::Objective) => "discard an objective card".to_string(),
+            ActionTag::DrawCards(v) => format!("draw {} and discard as many", counted(v, "card")),
+            ActionTag::TrashCard => "remove a card in hand from your deck".to_string(),
+            ActionTag::MoveCattleman(v) => format!("move your cattleman up to {}", counted(v, "space")),
+            ActionTag::TeleportCattleman(v) =>
+                format!("move your cattleman up to {} without paying tolls", counted(v, "space")),
+            ActionTag::MoveEngine(v) if multiplier(v) < 0 => format!("move your engine back {}", counted(v, "space")),
+            ActionTag::MoveEngine(v) => format!("move your engine forward {}", counted(v, "space")),
+            ActionTag::MoveCertificate(ActionValues::Max) => "raise your certificates to the maximum".to_string(),
+            ActionTag::MoveCertificate(v) => format!("gain {}", counted(v, "certificate")),
+            ActionTag::TakeTepee => "take a tepee".to_string(),
+            ActionTag::TakeHazard => "take a hazard".to_string(),
+            ActionTag::TakeObjective => "take an objective card".to_string(),
+            ActionTag::PlaceDisc(_) => "deliver to a city".to_string(),
+            ActionTag::HireEmployee(v) => match multiplier(v) {
+                0 => "hire an employee".to_string(),
+                n if n < 0 => format!("hire an employee for {} more", dollars(v)),
+                _ => format!("hire an employee for {} less", dollars(v)),
+            },
+            // Any other value costs the default $2, see Engine::buildingCost
+            ActionTag::PlaceBuilding(v @ (ActionValues::Exact(_) | ActionValues::EmployeeMult(_, _))) =>
+                format!("place a building for ${} per laborer", multiplier(v).unsigned_abs()),
+            ActionTag::PlaceBuilding(_) => "place a building for $2 per laborer".to_string(),
+            ActionTag::CityDiscMoveTrain => "move your engine forward 1 space per city you've delivered to".to_string(),
+            ActionTag::StationDiscBehindTrain => "upgrade a station your engine has passed".to_string(),
+            ActionTag::AdjacentBuilding => "move your cattleman 1 space without paying tolls".to_string(),
+            ActionTag::Auxiliary => "take an auxiliary action".to_string(),
+            ActionTag::DoubleAuxiliary => "take a single or double auxiliary action".to_string(),
+        }
+    }
+}
+
+impl<const LENGTH: usize> Describe for And<ActionTag, LENGTH>
+    where [Option<ActionTag>; LENGTH]: Serialize + DeserializeOwned {
+    // A discard followed by the dollars it earns reads as one step
+    fn describe(&self) -> String {
+        let tags: Vec<&ActionTag> = self.items.iter().flatten().collect();
+        let mut steps = Vec::<String>::new();
+        let mut i = 0;
+        while i < tags.len() {
+            match (tags[i], tags.get(i + 1)) {
+                (ActionTag::DiscardCard(_), Some(ActionTag::TakeCoins(v))) if multiplier(*v) >= 0 => {
+                    steps.push(format!("{} for {}", tags[i].describe(), dollars(*v)));
+                    i += 2;
+                }
+                (tag, _) => {
+                    steps.push(tag.describe());
+                    i += 1;
+                }
+            }
+        }
+        steps.join(" and ")
+    }
+}
+
+impl<T: Describe + Copy, const LENGTH: usize> Describe for XOr<T, LENGTH>
+    where [Option<T>; LENGTH]: Serialize + DeserializeOwned {
+    fn describe(&self) -> String {
+        self.items.iter().flatten().map(|item| { item.describe() }).collect::<Vec<String>>().join(" or ")
+    }
+}
+
+impl Describe for Building {
+    // Every option can be used once, in any order
+    fn describe(&self) -> String {
+        let options: Vec<String> = self.actions().items.iter().flatten().map(|xor| { xor.describe() }).collect();
+        match options.len() {
+            0 => "nothing".to_string(),
+            1 => options[0].clone(),
+            _ => format!("Either: {}", options.join("; and/or ")),
+        }
+    }
+}
+
+impl Describe for StationMaster {
+    fn describe(&self) -> String {
+        let mut text = format!("{} at the end of the game", counted(self.points(), "point"));
+        if self.permCertificate() {
+            text += " and a certificate that is never spent";
+        }
+        let bonus = self.bonus().describe();
+        if !bonus.is_empty() {
+            text += &format!("; when taken: {}", bonus);
+        }
+        text
+    }
+}
+
+// "a hazard" or "2 hazards"
+fn some(n: u32, one: &str, many: &str) -> String {
+    if n == 1 { format!("{} {}", article(one), one) } else { format!("{} {}", n, many) }
+}
+
+fn requirement(req: ObjectiveRequirements, n: u32) -> String {
+    match req {
+        ObjectiveRequirements::Building => some(n, "building", "buildings"),
+        ObjectiveRequirements::Hazard => some(n, "hazard", "hazards"),
+        ObjectiveRequirements::SanFran => some(n, "delivery to San Francisco", "deliveries to San Francisco"),
+        ObjectiveRequirements::GreenTepee => some(n, "green tepee", "green tepees"),
+        ObjectiveRequirements::BlueTepee => some(n, "blue tepee", "blue tepees"),
+        ObjectiveRequirements::StationDisc => some(n, "upgraded station", "upgraded stations"),
+        ObjectiveRequirements::Cow(value) => format!("{} worth {}", some(n, "cow", "cows"), value),
+    }
+}
+
+impl Describe for Objective {
+    fn describe(&self) -> String {
+        // Repeated requirements are counted together, in the order they first appear
+        let mut needs = Vec::<(ObjectiveRequirements, u32)>::new();
+        for req in self.requirements().items.iter().flatten() {
+            match needs.iter_mut().find(|(r, _)| { r == req }) {
+                Some((_, n)) => *n += 1,
+                None => needs.push((*req, 1)),
+            }
+        }
+        let needs: Vec<String> = needs.iter().map(|(req, n)| { requirement(*req, *n) }).collect();
+        let failed = match self.failPoints() {
+            0 => "nothing".to_string(),
+            pts => format!("-{}", pts),
+        };
+        let mut text = format!("Needs {}: {} if completed, {} if not", needs.join(", "),
+                               plural(self.successPoints(), "point"), failed);
+        if let Some(tag) = self.immediate() {
+            text += &format!("; when played: {}", tag.describe());
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::data::GameData;
+    use crate::player::Employee;
+
+    use super::*;
+
+    #[test]
+    fn testDescriptions() {
+        assert_eq!(Building::basicBuilding(0).unwrap().describe(),
+                   "Either: discard a Guernsey for $2; and/or hire an employee; and/or hire an employee for $2 more");
+        assert_eq!(Building::basicBuilding(2).unwrap().describe(),
+                   "Either: gain 1 certificate or take an objective card; and/or move your engine forward 1 space per Engineer");
+        assert_eq!(Building::basicBuilding(3).unwrap().describe(),
+                   "Either: take a tepee or pay $2 and move your engine forward 2 spaces; and/or take a single or double auxiliary action");
+
+        let objective = Objective::new(Some(ActionTag::DoubleAuxiliary), 5, 3, &[
+            ObjectiveRequirements::Building, ObjectiveRequirements::Cow(4), ObjectiveRequirements::Building,
+        ]).unwrap();
+        assert_eq!(objective.describe(),
+                   "Needs 2 buildings, a cow worth 4: 5 points if completed, -3 if not; when played: take a single or double auxiliary action");
+        assert_eq!(ActionTag::TakeCoins(ActionValues::EmployeeMult(Employee::Cowboy, 1)).describe(), "take $1 per Cowboy");
+
+        let data = GameData::embedded().unwrap();
+        let descriptions: Vec<String> = data.station_masters.iter().map(|sm| { sm.describe() }).collect();
+        assert!(descriptions.contains(&"1 point per employee at the end of the game; when taken: take $2".to_string()),
+                "{:?}", descriptions);
+        for building in data.neutral_buildings.iter().chain(data.player_buildings_a.iter()).chain(data.player_buildings_b.iter()) {
+            assert!(!building.describe().contains("nothing"));
+        }
+    }
+}
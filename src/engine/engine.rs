@@ -1,14 +1,22 @@
 #![allow(non_snake_case)]
 
-use std::fs::read_to_string;
-use std::path::Path;
-
+use rand::{Rng, SeedableRng, thread_rng};
+use rand::prelude::SliceRandom;
+use rand_pcg::Pcg64;
 use serde::{Deserialize, Serialize};
 
 use crate::buildings::Building;
-use crate::deck::{Cow, CowMarket, Objective};
-use crate::player::Player;
-use crate::tracks::SpaceOccupant;
+use crate::data::GameData;
+use crate::deck::{CowMarket, Objective};
+use crate::error::{DataError, GwtError};
+use crate::events::{GameEvent, Observers};
+use crate::history::{Change, Delta, History, Part};
+use crate::position::HashCache;
+use crate::player::{Employee, Player};
+use crate::rules::{Pending, Phase};
+use crate::scoring::ScoreSheet;
+use crate::tiles::{Foresight, JobMarket, JobMarketEvent};
+use crate::tracks::{Space, StationTrack};
 
 pub mod actions;
 pub mod player;
@@ -17,6 +25,55 @@ pub mod tracks;
 pub mod logical;
 pub mod deck;
 pub mod tiles;
+pub mod scoring;
+pub mod error;
+pub mod data;
+pub mod validate;
+pub mod rules;
+pub mod view;
+pub mod agent;
+pub mod runner;
+pub mod mcts;
+pub mod heuristic;
+pub mod tournament;
+pub mod batch;
+pub mod features;
+pub mod env;
+pub mod network;
+pub mod dataset;
+pub mod record;
+pub mod notation;
+pub mod history;
+pub mod savegame;
+pub mod position;
+pub mod events;
+pub mod narration;
+pub mod describe;
+pub mod board;
+pub mod cli;
+
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+pub enum GameState {
+    Playing,
+    // The last job market slot was filled during triggered_by's turn,
+    // everyone else gets one more turn before the game ends
+    FinalRound { triggered_by: usize },
+    GameOver { scores: Vec<ScoreSheet>, winners: Vec<u32> },
+}
+
+// How a game is dealt. side_b has an entry for each player building, choosing its B side, and is empty for all A sides
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+pub struct GameSetup {
+    pub players: u32,
+    pub seed: u64,
+    pub side_b: Vec<bool>,
+}
+
+impl GameSetup {
+    pub fn new(players: u32, seed: u64) -> GameSetup {
+        GameSetup { players, seed, side_b: vec![] }
+    }
+}
 
 #[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
 pub struct Engine {
@@ -25,22 +82,200 @@ pub struct Engine {
     players: Vec<Player>,
     cows: CowMarket,
     objectives: Vec<Objective>,
-    track: Vec<SpaceOccupant>,
+    track: Vec<Space>,
+    station_track: StationTrack,
+    foresight: Foresight,
+    job_market: JobMarket,
+    state: GameState,
+    objective_market: Vec<Objective>,
+    phase: Phase,
+    pending: Vec<Vec<Pending>>,
+    // Only kept once asked for, and never saved
+    #[serde(skip)]
+    history: Option<History>,
+    // The action being applied, which is taken back if it fails
+    #[serde(skip)]
+    recording: Option<Delta>,
+    #[serde(skip)]
+    hash_cache: HashCache,
+    #[serde(skip)]
+    observers: Observers,
 }
 
 impl Engine {
-    pub fn startDefaultGame(numPlayers: u32) -> Engine {
-        let buildings = Vec::<Building>::with_capacity(10);
+    pub fn new(players: Vec<Player>, track: Vec<Space>, station_track: StationTrack) -> Engine {
+        let job_market = JobMarket::new(players.len());
+        Engine {
+            starting_player: 0,
+            current_player: 0,
+            players,
+            cows: CowMarket::new(),
+            objectives: Vec::<Objective>::new(),
+            track,
+            station_track,
+            foresight: Foresight::new([vec![], vec![], vec![]]),
+            job_market,
+            state: GameState::Playing,
+            objective_market: Vec::<Objective>::with_capacity(Engine::OBJECTIVE_MARKET_SIZE),
+            phase: Phase::Move,
+            pending: Vec::<Vec<Pending>>::new(),
+            history: None,
+            recording: None,
+            hash_cache: HashCache::default(),
+            observers: Observers::default(),
+        }
+    }
+
+    pub fn startDefaultGame(numPlayers: u32) -> Result<Engine, GwtError> {
+        Engine::newGame(&GameData::embedded()?, numPlayers, thread_rng().gen())
+    }
+
+    // Shuffles and deals everything from the data set, the same seed always sets up the same game
+    pub fn newGame(data: &GameData, numPlayers: u32, seed: u64) -> Result<Engine, GwtError> {
+        Engine::setup(data, &GameSetup::new(numPlayers, seed))
+    }
+
+    pub fn setup(data: &GameData, setup: &GameSetup) -> Result<Engine, GwtError> {
+        let numPlayers = setup.players;
+        if !(2..=4).contains(&numPlayers) {
+            return Err(GwtError::InvalidPlayerCount(numPlayers));
+        }
+        let buildings: Vec<Building> = match setup.side_b.len() {
+            0 => data.player_buildings_a.clone(),
+            n if n == data.player_buildings_a.len() && n == data.player_buildings_b.len() => {
+                (0..n).map(|i| { if setup.side_b[i] { data.player_buildings_b[i] } else { data.player_buildings_a[i] } }).collect()
+            }
+            n => {
+                let message = format!("{} building sides chosen for {} buildings", n, data.player_buildings_a.len());
+                return Err(DataError::Invalid { file: GameData::PLAYER_BUILDINGS_B.to_string(), message }.into());
+            }
+        };
+        let mut rng = Pcg64::seed_from_u64(setup.seed);
+
+        let mut starting_objectives = data.starting_objectives.clone();
+        starting_objectives.shuffle(&mut rng);
         let mut players = Vec::<Player>::with_capacity(numPlayers as usize);
-        let starting_deck_path = Path::new("data/default_track.json");
-        let starting_deck_str = read_to_string(starting_deck_path).unwrap();
-        let starting_deck: Vec<Cow> = serde_json::from_str(&starting_deck_str).unwrap();
         for i in 0..numPlayers {
-            players[i as usize] = Player::new(i, starting_deck.clone(), buildings.clone());
+            let mut player = Player::newSeeded(i, data.starting_deck.clone(), buildings.clone(), rng.gen());
+            if let Some(objective) = starting_objectives.pop() {
+                player.takeStartingObjective(objective);
+            }
+            player.refillHand();
+            players.push(player);
         }
-        let track_path = Path::new("data/default_track.json");
-        let track_str = read_to_string(track_path).unwrap();
-        let track: Vec<SpaceOccupant> = serde_json::from_str(&track_str).unwrap();
-        return Engine { starting_player: 0, current_player: 0, players, cows: CowMarket::new(), objectives: Vec::<Objective>::new(), track };
+
+        let mut track = data.track.clone();
+        let mut neutral_buildings = data.neutral_buildings.clone();
+        neutral_buildings.shuffle(&mut rng);
+        for (space, building) in data.neutralSpaces().iter().zip(neutral_buildings) {
+            track[*space].placeBuilding(building);
+        }
+
+        let mut station_track = data.station_track.clone();
+        let mut station_masters = data.station_masters.clone();
+        station_masters.shuffle(&mut rng);
+        for (station, station_master) in station_masters.into_iter().enumerate() {
+            station_track.placeStationMaster(station, station_master);
+        }
+
+        let mut engine = Engine::new(players, track, station_track);
+
+        let mut cow_deck = data.cow_deck.clone();
+        cow_deck.shuffle(&mut rng);
+        engine.cows = CowMarket::fromDeck(cow_deck);
+        let size = engine.cowMarketSize();
+        engine.cows.refill(size);
+
+        engine.objectives = data.objectives.clone();
+        engine.objectives.shuffle(&mut rng);
+        engine.refillObjectives();
+
+        let mut piles = data.foresight_piles.clone();
+        for pile in piles.iter_mut() {
+            pile.shuffle(&mut rng);
+        }
+        engine.foresight = Foresight::new(piles);
+        Ok(engine)
+    }
+
+    pub fn state(&self) -> &GameState { &self.state }
+
+    pub fn isGameOver(&self) -> bool {
+        matches!(self.state, GameState::GameOver { .. })
+    }
+
+    pub fn currentPlayer(&self) -> usize { self.current_player }
+
+    pub fn players(&self) -> &Vec<Player> { &self.players }
+
+    pub fn jobMarket(&self) -> &JobMarket { &self.job_market }
+
+    pub fn cowMarket(&self) -> &CowMarket { &self.cows }
+
+    pub fn objectiveMarket(&self) -> &Vec<Objective> { &self.objective_market }
+
+    pub fn track(&self) -> &Vec<Space> { &self.track }
+
+    pub fn stationTrack(&self) -> &StationTrack { &self.station_track }
+
+    pub fn foresight(&self) -> &Foresight { &self.foresight }
+
+    // The number of cows on display in the market after it is refilled
+    pub fn cowMarketSize(&self) -> usize {
+        3 * self.players.len() + 1
+    }
+
+    // Adds an employee to the job market for the current player, resolving whatever the new slot triggers
+    pub fn addEmployee(&mut self, emp: Employee) -> JobMarketEvent {
+        self.touch(Part::JobMarket);
+        let event = self.job_market.addEmployee(emp);
+        self.record(Change::EmployeeAdded);
+        self.emit(GameEvent::EmployeeAdded { employee: emp });
+        let filled = self.job_market.employees().len();
+        if filled.is_multiple_of(self.players.len()) {
+            self.emit(GameEvent::JobMarketRowFilled { row: filled / self.players.len() });
+        }
+        match event {
+            JobMarketEvent::RefillCowMarket => {
+                let size = self.cowMarketSize();
+                self.touch(Part::Cows);
+                let dealt = self.cows.deal(size);
+                self.record(Change::CowsDealt(dealt));
+                self.emit(GameEvent::CowMarketRefilled);
+            }
+            JobMarketEvent::FinalRound => {
+                if self.state == GameState::Playing {
+                    self.touch(Part::Player(self.current_player));
+                    self.players[self.current_player].takeJobMarketToken();
+                    self.state = GameState::FinalRound { triggered_by: self.current_player };
+                    self.emit(GameEvent::FinalRoundTriggered { player: self.current_player as u32 });
+                }
+            }
+            JobMarketEvent::NoEvent => {}
+        }
+        event
+    }
+
+    // Passes play to the next player, ending the game once play returns to whoever triggered the final round
+    pub fn endTurn(&mut self) {
+        if self.isGameOver() {
+            return;
+        }
+        self.emit(GameEvent::TurnEnded { player: self.current_player as u32 });
+        self.current_player = (self.current_player + 1) % self.players.len();
+        if let GameState::FinalRound { triggered_by } = self.state {
+            if self.current_player == triggered_by {
+                let scores = self.scoreSheets();
+                let winners = ScoreSheet::winners(&scores);
+                self.emit(GameEvent::GameOver { winners: winners.clone() });
+                self.state = GameState::GameOver { scores, winners };
+            }
+        }
+    }
+
+    pub fn scoreSheets(&self) -> Vec<ScoreSheet> {
+        self.players.iter()
+            .map(|p| { ScoreSheet::new(p, &self.track, &self.station_track) })
+            .collect()
     }
 }
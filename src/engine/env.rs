@@ -0,0 +1,138 @@
+use crate::{Engine, GameState};
+use crate::actions::Action;
+use crate::agent::Agent;
+use crate::data::GameData;
+use crate::error::GwtError;
+use crate::features::{actionAt, encodeObservation, legalMask};
+use crate::runner::MAX_ACTIONS;
+use crate::view::PlayerView;
+
+// What the learner sees after reset or step, observation is the features encoding and
+// mask has 1.0 at the index of every legal action
+#[derive(Clone, PartialEq, Debug)]
+pub struct Step {
+    pub observation: Vec<f32>,
+    pub reward: f32,
+    pub done: bool,
+    pub mask: Vec<f32>,
+}
+
+// One learner seat against fixed opponents. The learner only acts on its own turns, the opponents' actions are
+// played out inside reset and step
+pub struct Environment<'a> {
+    data: &'a GameData,
+    seat: u32,
+    // One agent for each other seat, in seat order
+    opponents: Vec<Box<dyn Agent>>,
+    engine: Engine,
+    actions: u32,
+}
+
+impl<'a> Environment<'a> {
+    pub fn new(data: &'a GameData, seat: u32, opponents: Vec<Box<dyn Agent>>) -> Result<Environment<'a>, GwtError> {
+        let players = opponents.len() as u32 + 1;
+        if seat >= players {
+            return Err(GwtError::InvalidSeat { seat, players });
+        }
+        let engine = Engine::newGame(data, players, 0)?;
+        Ok(Environment { data, seat, opponents, engine, actions: 0 })
+    }
+
+    pub fn engine(&self) -> &Engine { &self.engine }
+
+    pub fn reset(&mut self, seed: u64) -> Result<Step, GwtError> {
+        self.engine = Engine::newGame(self.data, self.opponents.len() as u32 + 1, seed)?;
+        self.actions = 0;
+        self.playOpponents()?;
+        Ok(self.observe())
+    }
+
+    pub fn step(&mut self, action_index: usize) -> Result<Step, GwtError> {
+        let view = PlayerView::new(&self.engine, self.seat);
+        let action = actionAt(&view.observe(), &self.engine.legalActions(), action_index)
+            .ok_or(GwtError::InvalidActionIndex(action_index))?;
+        self.apply(action)?;
+        self.playOpponents()?;
+        Ok(self.observe())
+    }
+
+    // 1 for a win, shared between tied winners, and 0 for anything else including games still being played
+    pub fn reward(&self) -> f32 {
+        match self.engine.state() {
+            GameState::GameOver { winners, .. } if winners.contains(&self.seat) => 1.0 / winners.len() as f32,
+            _ => 0.0,
+        }
+    }
+
+    fn apply(&mut self, action: Action) -> Result<(), GwtError> {
+        if self.actions >= MAX_ACTIONS {
+            return Err(GwtError::TurnLimit(MAX_ACTIONS));
+        }
+        self.engine.applyAction(action)?;
+        self.actions += 1;
+        Ok(())
+    }
+
+    fn playOpponents(&mut self) -> Result<(), GwtError> {
+        while !self.engine.isGameOver() && self.engine.currentPlayer() as u32 != self.seat {
+            let seat = self.engine.currentPlayer() as u32;
+            let opponent = if seat < self.seat { seat } else { seat - 1 };
+            let legal = self.engine.legalActions();
+            let action = self.opponents[opponent as usize].choose(&PlayerView::new(&self.engine, seat), &legal);
+            self.apply(action)?;
+        }
+        Ok(())
+    }
+
+    fn observe(&self) -> Step {
+        let observation = PlayerView::new(&self.engine, self.seat).observe();
+        let done = self.engine.isGameOver();
+        let legal = if done { vec![] } else { self.engine.legalActions() };
+        Step {
+            observation: encodeObservation(&observation),
+            reward: self.reward(),
+            done,
+            mask: legalMask(&observation, &legal),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng};
+    use rand_pcg::Pcg64;
+
+    use crate::agent::RandomAgent;
+    use crate::features::{ACTION_SPACE, FEATURE_SIZE};
+
+    use super::*;
+
+    fn playEpisode(env: &mut Environment, seed: u64) -> (Step, u32) {
+        let mut rng = Pcg64::seed_from_u64(seed);
+        let mut step = env.reset(seed).unwrap();
+        let mut steps = 0;
+        while !step.done {
+            assert_eq!(step.observation.len(), FEATURE_SIZE);
+            assert_eq!(step.mask.len(), ACTION_SPACE);
+            let legal: Vec<usize> = (0..ACTION_SPACE).filter(|i| { step.mask[*i] > 0.0 }).collect();
+            step = env.step(legal[rng.gen_range(0..legal.len())]).unwrap();
+            steps += 1;
+        }
+        (step, steps)
+    }
+
+    #[test]
+    fn testEpisode() {
+        let data = GameData::embedded().unwrap();
+        let opponents: Vec<Box<dyn Agent>> = vec![Box::new(RandomAgent::new(1)), Box::new(RandomAgent::new(2))];
+        let mut env = Environment::new(&data, 1, opponents).unwrap();
+        let (last, steps) = playEpisode(&mut env, 4);
+        assert!(steps > 0);
+        assert_eq!(last.reward, env.reward());
+        assert!(last.mask.iter().all(|m| { *m == 0.0 }));
+        assert_eq!(env.step(0), Err(GwtError::InvalidActionIndex(0)));
+
+        let opponents: Vec<Box<dyn Agent>> = vec![Box::new(RandomAgent::new(1))];
+        assert_eq!(Environment::new(&data, 2, opponents).err(), Some(GwtError::InvalidSeat { seat: 2, players: 2 }));
+    }
+}
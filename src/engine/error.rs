@@ -0,0 +1,205 @@
+use std::error::Error;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::actions::Action;
+use crate::deck::Card;
+use crate::player::Employee;
+
+#[derive(Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+pub enum DeckError {
+    // Both the draw and discard piles are empty
+    NoCardsLeft,
+    CardNotInHand(Card),
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+pub enum JobMarketError {
+    EmployeeNotFound { employee: Employee, salary: i32 },
+}
+
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+pub enum ReplayError {
+    // The record was written in a format version this build doesn't read
+    Version(u32),
+    // The record was played with a different data set
+    DataHash { expected: u64, found: u64 },
+    // The action at index was taken by player, but it was someone else's turn
+    WrongPlayer { index: usize, player: u32, current: u32 },
+    // The game ended before all the actions were replayed
+    ExtraActions(usize),
+    // The replayed game doesn't reach the recorded final scores
+    Scores,
+}
+
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+pub enum DataError {
+    Io { file: String, message: String },
+    // The file isn't valid JSON or doesn't match the structure expected
+    Schema { file: String, line: usize, column: usize, message: String },
+    // The file parsed, but its contents can't be used to set up a game
+    Invalid { file: String, message: String },
+}
+
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+pub enum GwtError {
+    // player is None when the deck isn't known to belong to a player
+    Deck { player: Option<u32>, error: DeckError },
+    JobMarket(JobMarketError),
+    InsufficientFunds { player: u32, required: u32, available: u32 },
+    InvalidBuilding { num: u32, side_b: Option<bool> },
+    // More items were given to a logical group (Or, XOr, And, NAnd) than it can hold
+    TooManyItems { length: usize, capacity: usize },
+    AuxiliaryActionUnlocked,
+    Data(DataError),
+    // Games are for 2 to 4 players
+    InvalidPlayerCount(u32),
+    // The action isn't one of the legal actions in the current state
+    IllegalAction(Box<Action>),
+    // A disc was taken from a player board slot which has none left
+    NoDiscInSlot(u32),
+    // A game was stopped after this many actions without finishing
+    TurnLimit(u32),
+    // An agent description that doesn't name a known agent
+    UnknownAgent(String),
+    // Writing results out failed
+    Output(String),
+    // The index doesn't belong to one of the legal actions
+    InvalidActionIndex(usize),
+    Replay(ReplayError),
+    // Text which isn't an action in the action notation
+    InvalidNotation(String),
+    NothingToUndo,
+    NothingToRedo,
+    // There's no station with this index, counting the final station last
+    InvalidStation(usize),
+    // Seats count from 0, so seat has to be below players
+    InvalidSeat { seat: u32, players: u32 },
+    // An objective, building or station master which isn't in the game data, so the notation has no name for it and
+    // the position encoding no index
+    Unnamed(String),
+    // Bytes which aren't a position encoded with the game data they're decoded with
+    InvalidEncoding(String),
+}
+
+impl GwtError {
+    pub fn deck(player: u32, error: DeckError) -> GwtError {
+        GwtError::Deck { player: Some(player), error }
+    }
+
+    pub fn illegal(action: Action) -> GwtError {
+        GwtError::IllegalAction(Box::new(action))
+    }
+}
+
+impl From<DeckError> for GwtError {
+    fn from(error: DeckError) -> GwtError {
+        GwtError::Deck { player: None, error }
+    }
+}
+
+impl From<JobMarketError> for GwtError {
+    fn from(error: JobMarketError) -> GwtError {
+        GwtError::JobMarket(error)
+    }
+}
+
+impl From<ReplayError> for GwtError {
+    fn from(error: ReplayError) -> GwtError {
+        GwtError::Replay(error)
+    }
+}
+
+impl From<DataError> for GwtError {
+    fn from(error: DataError) -> GwtError {
+        GwtError::Data(error)
+    }
+}
+
+impl fmt::Display for DeckError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeckError::NoCardsLeft => write!(f, "No cards left to draw"),
+            DeckError::CardNotInHand(card) => write!(f, "Card isn't in hand: {:?}", card),
+        }
+    }
+}
+
+impl fmt::Display for JobMarketError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JobMarketError::EmployeeNotFound { employee, salary } =>
+                write!(f, "No {:?} found with a salary of {}", employee, salary),
+        }
+    }
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Version(version) => write!(f, "Unsupported game record version {}", version),
+            ReplayError::DataHash { expected, found } =>
+                write!(f, "The record was played with data set {:016x}, not {:016x}", expected, found),
+            ReplayError::WrongPlayer { index, player, current } =>
+                write!(f, "Action {} was taken by player {} during player {}'s turn", index, player, current),
+            ReplayError::ExtraActions(index) => write!(f, "The game was over before action {}", index),
+            ReplayError::Scores => write!(f, "The replayed game doesn't reach the recorded scores"),
+        }
+    }
+}
+
+impl fmt::Display for DataError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DataError::Io { file, message } => write!(f, "{}: {}", file, message),
+            DataError::Schema { file, line, column, message } =>
+                write!(f, "{}:{}:{}: {}", file, line, column, message),
+            DataError::Invalid { file, message } => write!(f, "{}: {}", file, message),
+        }
+    }
+}
+
+impl fmt::Display for GwtError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GwtError::Deck { player: Some(player), error } => write!(f, "Player {}: {}", player, error),
+            GwtError::Deck { player: None, error } => write!(f, "{}", error),
+            GwtError::JobMarket(error) => write!(f, "{}", error),
+            GwtError::InsufficientFunds { player, required, available } =>
+                write!(f, "Player {} needs {} dollars but only has {}", player, required, available),
+            GwtError::InvalidBuilding { num, side_b: None } => write!(f, "Invalid neutral building {}", num),
+            GwtError::InvalidBuilding { num, side_b: Some(side_b) } =>
+                write!(f, "Invalid player building {}{}", num, if *side_b { 'b' } else { 'a' }),
+            GwtError::TooManyItems { length, capacity } =>
+                write!(f, "{} items given, but only {} fit", length, capacity),
+            GwtError::AuxiliaryActionUnlocked => write!(f, "The auxiliary action is already fully unlocked"),
+            GwtError::Data(error) => write!(f, "{}", error),
+            GwtError::InvalidPlayerCount(count) => write!(f, "Games are for 2 to 4 players, not {}", count),
+            GwtError::IllegalAction(action) => write!(f, "Illegal action: {:?}", action),
+            GwtError::NoDiscInSlot(slot) => write!(f, "No disc left in player board slot {}", slot),
+            GwtError::TurnLimit(actions) => write!(f, "The game didn't finish within {} actions", actions),
+            GwtError::UnknownAgent(name) => write!(f, "Unknown agent: {}", name),
+            GwtError::Output(message) => write!(f, "Couldn't write results: {}", message),
+            GwtError::InvalidActionIndex(index) => write!(f, "No legal action has index {}", index),
+            GwtError::Replay(error) => write!(f, "{}", error),
+            GwtError::InvalidNotation(text) => write!(f, "Not an action: {}", text),
+            GwtError::NothingToUndo => write!(f, "There is no action to undo"),
+            GwtError::NothingToRedo => write!(f, "There is no undone action to redo"),
+            GwtError::InvalidStation(station) => write!(f, "There is no station {}", station),
+            GwtError::InvalidSeat { seat, players } => write!(f, "There's no seat {} in a {} player game", seat, players),
+            GwtError::Unnamed(item) => write!(f, "{} isn't in the game data", item),
+            GwtError::InvalidEncoding(message) => write!(f, "Not an encoded position: {}", message),
+        }
+    }
+}
+
+impl Error for DeckError {}
+
+impl Error for JobMarketError {}
+
+impl Error for ReplayError {}
+
+impl Error for DataError {}
+
+impl Error for GwtError {}
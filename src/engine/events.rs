@@ -0,0 +1,163 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::Engine;
+use crate::actions::Action;
+use crate::buildings::{Building, Hazard, Tepee, Toll};
+use crate::deck::{Card, Cow, Objective};
+use crate::player::Employee;
+use crate::tiles::Tile;
+use crate::tracks::StationMaster;
+
+// What happened while an action was applied, in the order it happened. Players are seat numbers, spaces index the
+// cattle track and cities and stations index the station track
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+pub enum GameEvent {
+    // Always the first event of an action
+    ActionTaken { player: u32, action: Action },
+    CattlemanMoved { player: u32, from: usize, to: usize },
+    // to is None for hazard tolls, which go to the bank
+    TollPaid { from: u32, to: Option<u32>, toll: Toll, amount: u32 },
+    // Negative amounts were paid, only as much as the player had
+    CoinsTaken { player: u32, amount: i32 },
+    AuxiliaryPaid { player: u32, amount: u32 },
+    CertificatesGained { player: u32, amount: u32 },
+    CardsDrawn { player: u32, count: u32 },
+    CardPlayed { player: u32, card: Card },
+    CardTrashed { player: u32, card: Card },
+    CowBought { player: u32, cow: Cow, price: u32 },
+    EmployeeHired { player: u32, employee: Employee, cost: u32 },
+    BuildingPlaced { player: u32, building: Building, space: usize, cost: u32 },
+    BuildingUsed { player: u32, building: Building, space: usize },
+    HazardTaken { player: u32, hazard: Hazard },
+    // dollars is negative when the player paid for the tepee
+    TepeeTaken { player: u32, tepee: Tepee, dollars: i32 },
+    ObjectiveTaken { player: u32, objective: Objective },
+    ObjectivePlayed { player: u32, objective: Objective },
+    EngineMoved { player: u32, from: u32, to: u32 },
+    StationUpgraded { player: u32, station: usize, price: u32 },
+    StationMasterTaken { player: u32, station_master: StationMaster },
+    DiscRemoved { player: u32, slot: u32 },
+    TileTaken { player: u32, column: usize, row: usize, tile: Tile },
+    // The tile revealed from a foresight pile to refill a column
+    TileDrawn { column: usize, tile: Tile },
+    EmployeeAdded { employee: Employee },
+    HazardPlaced { hazard: Hazard, space: usize },
+    TepeePlaced { tepee: Tepee, space: usize },
+    // row counts from 1
+    JobMarketRowFilled { row: usize },
+    CowMarketRefilled,
+    HandSold { player: u32, value: u32 },
+    DiscPlaced { player: u32, city: usize, cost: u32 },
+    TurnEnded { player: u32 },
+    FinalRoundTriggered { player: u32 },
+    GameOver { winners: Vec<u32> },
+    // The engine went back to before the action, or forward again to after it
+    Undone { action: Action },
+    Redone { action: Action },
+}
+
+pub type ObserverId = u64;
+
+type Observer = Box<dyn FnMut(&GameEvent) + Send>;
+
+// An engine's observers and the events of the action being applied. Clones have no observers, so searching copies
+// of an engine doesn't show up in a UI watching it, and the observers are never compared or saved
+#[derive(Default)]
+pub struct Observers {
+    observers: Vec<(ObserverId, Observer)>,
+    next_id: ObserverId,
+    events: Vec<GameEvent>,
+}
+
+impl Clone for Observers {
+    fn clone(&self) -> Observers { Observers::default() }
+}
+
+impl PartialEq for Observers {
+    fn eq(&self, _other: &Observers) -> bool { true }
+}
+
+impl Eq for Observers {}
+
+impl fmt::Debug for Observers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} observers", self.observers.len())
+    }
+}
+
+impl Engine {
+    // The observer is called with every event of every action applied from now on, once the action has been applied
+    pub fn observe(&mut self, observer: impl FnMut(&GameEvent) + Send + 'static) -> ObserverId {
+        let id = self.observers.next_id;
+        self.observers.next_id += 1;
+        self.observers.observers.push((id, Box::new(observer)));
+        id
+    }
+
+    // Returns false if there was no such observer
+    pub fn unobserve(&mut self, id: ObserverId) -> bool {
+        let before = self.observers.observers.len();
+        self.observers.observers.retain(|(i, _)| { *i != id });
+        self.observers.observers.len() != before
+    }
+
+    // Only kept when someone is listening
+    pub(crate) fn emit(&mut self, event: GameEvent) {
+        if !self.observers.observers.is_empty() {
+            self.observers.events.push(event);
+        }
+    }
+
+    // Hands the events of an applied action to the observers, a failed action's events are dropped
+    pub(crate) fn flushEvents(&mut self, applied: bool) {
+        let events = std::mem::take(&mut self.observers.events);
+        if !applied {
+            return;
+        }
+        for event in events.iter() {
+            for (_, observer) in self.observers.observers.iter_mut() {
+                observer(event);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use crate::GameState;
+    use crate::agent::testing::playRandom;
+    use crate::data::GameData;
+
+    use super::*;
+
+    #[test]
+    fn testEvents() {
+        let data = GameData::embedded().unwrap();
+        let mut engine = Engine::newGame(&data, 2, 3).unwrap();
+        let events = Arc::new(Mutex::new(Vec::<GameEvent>::new()));
+        let sink = events.clone();
+        let id = engine.observe(move |event| { sink.lock().unwrap().push(event.clone()) });
+        assert!(engine.clone().observers.observers.is_empty());
+
+        let illegal = Action::PlaceDisc(Some(99));
+        assert!(engine.applyAction(illegal).is_err());
+        assert!(events.lock().unwrap().is_empty());
+
+        let actions = playRandom(&mut engine, 3, usize::MAX, |_| {});
+        let events = events.lock().unwrap();
+        assert_eq!(events.iter().filter(|e| { matches!(e, GameEvent::ActionTaken { .. }) }).count(), actions);
+        assert!(events.iter().any(|e| { matches!(e, GameEvent::CowBought { .. }) }));
+        assert!(events.iter().any(|e| { matches!(e, GameEvent::JobMarketRowFilled { .. }) }));
+        let winners = match engine.state() {
+            GameState::GameOver { winners, .. } => winners.clone(),
+            _ => unreachable!(),
+        };
+        assert_eq!(events.last(), Some(&GameEvent::GameOver { winners }));
+        assert!(engine.unobserve(id));
+        assert!(!engine.unobserve(id));
+    }
+}
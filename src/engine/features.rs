@@ -0,0 +1,436 @@
+// A fixed size numeric encoding of what one seat observes, for learning experiments.
+//
+// Players are numbered relative to the observing seat, so the observer is always player 0 and the player after
+// them player 1. Seats missing from smaller games are all zeros. Values are raw counts, 0/1 flags or one-hots,
+// laid out in this order (LAYOUT_VERSION 1):
+//
+//   global         current player one-hot (4), phase one-hot Move/Location/KansasCity (3), pending groups,
+//                  final round flag, cow market cows by color (9), cow deck size, objective market size,
+//                  objective deck size, job market slots (48 x Cowboy/Craftsman/Engineer one-hot),
+//                  job market slots filled, foresight tiles current then next (6 x TILE_KINDS), foresight pile sizes (3)
+//   track          TRACK_SPACES x SPACE_FEATURES: neutral building, player building owner one-hot (4),
+//                  empty building spot, hazard, green tepee, blue tepee, cattlemen one-hot (4)
+//   stations       STATIONS x (discs one-hot (4), station master still there)
+//   cities         CITIES x discs per player (4)
+//   engines        engine location per player (4)
+//   players        MAX_PLAYERS x PLAYER_FEATURES: present, dollars, certificates, hired cowboys/craftsmen/engineers,
+//                  green tepees, blue tepees, hazards, placed buildings, unplaced buildings, station masters,
+//                  job market token, played objectives, cards in hand, hand size, step limit,
+//                  auxiliary single/double available (5 x 2), deck cows by color (9), deck objectives,
+//                  hand cows by color (9, only for the observer), hand objectives
+//
+// Tracks longer than TRACK_SPACES, and anything else past its block's size, is left out.
+
+use crate::actions::Action;
+use crate::buildings::Tepee;
+use crate::deck::{Card, Cow};
+use crate::player::PlayerBoard;
+use crate::rules::Phase;
+use crate::tiles::{Foresight, Tile};
+use crate::tracks::SpaceOccupant;
+use crate::view::{DeckView, Observation, PlayerView, SeatView};
+use crate::GameState;
+
+pub const LAYOUT_VERSION: u32 = 1;
+
+pub const MAX_PLAYERS: usize = 4;
+pub const TRACK_SPACES: usize = 52;
+pub const STATIONS: usize = 11;
+pub const CITIES: usize = 10;
+pub const JOB_MARKET_SLOTS: usize = 48;
+pub const COW_COLORS: usize = 9;
+// Cow cards are worth 0 to 7 points
+pub const COW_POINTS: usize = 8;
+pub const TILE_KINDS: usize = 9;
+pub const SPACE_FEATURES: usize = 13;
+pub const PLAYER_FEATURES: usize = 47;
+const GLOBAL_FEATURES: usize = 4 + 3 + 1 + 1 + COW_COLORS + 3 + JOB_MARKET_SLOTS * 3 + 1
+    + 2 * Foresight::FORESIGHT_SIZE * TILE_KINDS + Foresight::FORESIGHT_SIZE;
+
+pub const FEATURE_SIZE: usize = GLOBAL_FEATURES + TRACK_SPACES * SPACE_FEATURES + STATIONS * (MAX_PLAYERS + 1)
+    + CITIES * MAX_PLAYERS + MAX_PLAYERS + MAX_PLAYERS * PLAYER_FEATURES;
+
+pub fn encode(view: &PlayerView) -> Vec<f32> {
+    encodeObservation(&view.observe())
+}
+
+pub fn encodeObservation(obs: &Observation) -> Vec<f32> {
+    let mut features = Features { values: Vec::with_capacity(FEATURE_SIZE) };
+    let n = obs.players.len();
+    let relative = |player: usize| { (player + n - obs.seat as usize) % n };
+
+    features.oneHot(MAX_PLAYERS, Some(relative(obs.current_player)));
+    features.oneHot(3, Some(match obs.phase {
+        Phase::Move => 0,
+        Phase::Location { .. } => 1,
+        Phase::KansasCity(_) => 2,
+    }));
+    features.push(obs.pending.len());
+    features.flag(matches!(obs.state, GameState::FinalRound { .. }));
+    features.cowColors(obs.cow_market.iter().copied());
+    features.push(obs.cow_deck_size);
+    features.push(obs.objective_market.len());
+    features.push(obs.objective_deck_size);
+    let employees = obs.job_market.employees();
+    for slot in 0..JOB_MARKET_SLOTS {
+        features.oneHot(3, employees.get(slot).copied().flatten().map(|e| { e as usize }));
+    }
+    features.push(employees.len());
+    for tile in obs.foresight_current.iter().chain(obs.foresight_next.iter()) {
+        features.oneHot(TILE_KINDS, Some(tileKind(tile)));
+    }
+    for size in obs.foresight_pile_sizes.iter() {
+        features.push(*size);
+    }
+
+    for space in 0..TRACK_SPACES {
+        let occupant = obs.track.get(space).map(|s| { *s.occupant() });
+        let (neutral, owner, empty) = match occupant {
+            Some(SpaceOccupant::Building { building: Some(_), neutral: true, .. }) => (true, None, false),
+            Some(SpaceOccupant::Building { building: Some(b), neutral: false, .. }) => (false, Some(relative(b.owner() as usize)), false),
+            Some(SpaceOccupant::Building { building: None, .. }) => (false, None, true),
+            _ => (false, None, false),
+        };
+        features.flag(neutral);
+        features.oneHot(MAX_PLAYERS, owner);
+        features.flag(empty);
+        features.flag(matches!(occupant, Some(SpaceOccupant::Hazard(_, Some(_)))));
+        features.flag(matches!(occupant, Some(SpaceOccupant::Tepee(_, Some(Tepee::Green)))));
+        features.flag(matches!(occupant, Some(SpaceOccupant::Tepee(_, Some(Tepee::Blue)))));
+        for seat in 0..MAX_PLAYERS {
+            features.flag(seat < n && obs.players[(obs.seat as usize + seat) % n].cattleman_pos == space);
+        }
+    }
+
+    for station in 0..STATIONS {
+        let station = (station < obs.station_track.numStations()).then(|| { obs.station_track.station(station) });
+        for seat in 0..MAX_PLAYERS {
+            features.flag(seat < n && station.map(|s| { s.hasDisc(((obs.seat as usize + seat) % n) as u32) }).unwrap_or(false));
+        }
+        features.flag(station.map(|s| { s.stationMaster().is_some() }).unwrap_or(false));
+    }
+
+    let cities = obs.station_track.cities().cities();
+    for city in 0..CITIES {
+        for seat in 0..MAX_PLAYERS {
+            let discs = match cities.get(city) {
+                Some(c) if seat < n => c.discs(((obs.seat as usize + seat) % n) as u32),
+                _ => 0,
+            };
+            features.push(discs as usize);
+        }
+    }
+    for seat in 0..MAX_PLAYERS {
+        let location = if seat < n { obs.station_track.engineLocation(((obs.seat as usize + seat) % n) as u32) } else { 0 };
+        features.push(location as usize);
+    }
+
+    for seat in 0..MAX_PLAYERS {
+        if seat < n {
+            features.player(&obs.players[(obs.seat as usize + seat) % n]);
+        } else {
+            features.zeros(PLAYER_FEATURES);
+        }
+    }
+    features.values
+}
+
+fn tileKind(tile: &Tile) -> usize {
+    match tile {
+        Tile::EmployeeTile(emp) => *emp as usize,
+        Tile::HazardTile(hazard) => 3 + hazard.area() as usize,
+        Tile::TepeeTile(Tepee::Green) => 6,
+        Tile::TepeeTile(Tepee::Blue) => 7,
+        Tile::BlankTile => 8,
+    }
+}
+
+struct Features {
+    values: Vec<f32>,
+}
+
+impl Features {
+    fn push(&mut self, value: usize) {
+        self.values.push(value as f32);
+    }
+
+    fn flag(&mut self, value: bool) {
+        self.values.push(if value { 1.0 } else { 0.0 });
+    }
+
+    fn zeros(&mut self, count: usize) {
+        self.values.extend(std::iter::repeat_n(0.0, count));
+    }
+
+    fn oneHot(&mut self, size: usize, index: Option<usize>) {
+        for i in 0..size {
+            self.flag(index == Some(i));
+        }
+    }
+
+    fn cowColors(&mut self, cows: impl Iterator<Item=Cow>) {
+        let mut counts = [0; COW_COLORS];
+        for cow in cows {
+            counts[cow.color() as usize] += 1;
+        }
+        for count in counts.iter() {
+            self.push(*count);
+        }
+    }
+
+    // Cows by color then the number of objective cards
+    fn cards<'a>(&mut self, cards: impl Iterator<Item=(&'a Card, u32)>) {
+        let mut counts = [0; COW_COLORS + 1];
+        for (card, n) in cards {
+            match card {
+                Card::CowCard(cow) => counts[cow.color() as usize] += n,
+                Card::ObjectiveCard(_) => counts[COW_COLORS] += n,
+            }
+        }
+        for count in counts.iter() {
+            self.push(*count as usize);
+        }
+    }
+
+    fn player(&mut self, player: &SeatView) {
+        let start = self.values.len();
+        self.flag(true);
+        self.push(player.dollars as usize);
+        self.push(player.certificates as usize);
+        for hired in player.hired.iter() {
+            self.push(*hired as usize);
+        }
+        self.push(player.green_tepees as usize);
+        self.push(player.blue_tepees as usize);
+        self.push(player.hazards.len());
+        self.push(player.played_buildings.len());
+        self.push(player.buildings.len());
+        self.push(player.station_masters.len());
+        self.flag(player.job_market_token);
+        self.push(player.played_objectives.len());
+        self.push(player.deck.hand_len);
+        self.push(player.deck.hand_size);
+        self.push(player.board.stepLimit() as usize);
+        for i in 0..PlayerBoard::NUM_AUX_ACTIONS {
+            let (single, double) = player.board.auxAction(i).availableActions();
+            self.flag(single.is_some());
+            self.flag(double.is_some());
+        }
+        let deck: &DeckView = &player.deck;
+        let hand = deck.hand.as_deref().unwrap_or(&[]);
+        self.cards(deck.unseen.iter().map(|(card, n)| { (card, *n) })
+            .chain(deck.discard.iter().map(|card| { (card, 1) }))
+            .chain(hand.iter().map(|card| { (card, 1) })));
+        self.cards(hand.iter().map(|card| { (card, 1) }));
+        debug_assert_eq!(self.values.len() - start, PLAYER_FEATURES);
+    }
+}
+
+// Fixed indices for actions. Parameters are mapped to table positions where the action's value isn't enough on
+// its own: cards and cows by color and points, objectives by their place in the market or hand, hazards by their
+// track space and buildings by their place among the player's unplaced buildings. Every None parameter shares
+// its action's first index
+const MAX_ENGINE_MOVE: usize = 40;
+const MAX_CERTIFICATE_MOVE: usize = 6;
+const MAX_DRAW: usize = 8;
+const HAND_OBJECTIVES: usize = 4;
+const OBJECTIVE_MARKET: usize = 4;
+const DISC_SLOTS: usize = 7;
+const UNPLACED_BUILDINGS: usize = 10;
+const AUX_SLOTS: usize = PlayerBoard::NUM_AUX_ACTIONS;
+const BUILDING_OPTIONS: usize = 3 * 2;
+const COWS: usize = COW_COLORS * COW_POINTS;
+const CARDS: usize = COWS + HAND_OBJECTIVES;
+
+// Sizes of each variant's block, in the order of the Action enum
+const ACTION_BLOCKS: [usize; 30] = [
+    1,                                  // PayCoins
+    COWS,                               // BuyCow
+    COW_COLORS * COW_COLORS,            // Buy2Cows
+    COWS,                               // SellCow
+    COW_COLORS,                         // SellCowPair
+    1,                                  // SellHand
+    1,                                  // FillHand
+    1 + MAX_DRAW + 1,                   // DrawCards
+    1 + MAX_DRAW + 1,                   // DiscardCards
+    1 + CARDS,                          // TrashCard
+    1 + TRACK_SPACES,                   // MoveCattleman
+    1 + 2 * MAX_ENGINE_MOVE + 1,        // MoveEngine
+    1 + 2 * MAX_CERTIFICATE_MOVE + 1,   // MoveCertificate
+    1,                                  // MaxCertificate
+    3,                                  // TakeTepee
+    1 + TRACK_SPACES,                   // TakeHazard
+    1 + OBJECTIVE_MARKET,               // TakeObjective
+    4,                                  // TakeStationmaster
+    1 + DISC_SLOTS,                     // RemoveDisc
+    1 + CITIES,                         // PlaceDisc
+    4,                                  // HireEmployee
+    1 + TRACK_SPACES * UNPLACED_BUILDINGS, // PlaceBuilding
+    AUX_SLOTS,                          // Auxiliary1
+    AUX_SLOTS,                          // Auxiliary2
+    BUILDING_OPTIONS,                   // UseBuilding
+    CARDS,                              // DiscardCard
+    2 * Foresight::FORESIGHT_SIZE,      // ChooseTile
+    STATIONS,                           // UpgradeStation
+    HAND_OBJECTIVES,                    // PlayObjective
+    1,                                  // Pass
+];
+
+pub const ACTION_SPACE: usize = {
+    let mut total = 0;
+    let mut i = 0;
+    while i < ACTION_BLOCKS.len() {
+        total += ACTION_BLOCKS[i];
+        i += 1;
+    }
+    total
+};
+
+fn blockStart(block: usize) -> usize {
+    ACTION_BLOCKS[..block].iter().sum()
+}
+
+fn cowIndex(cow: &Cow) -> Option<usize> {
+    (cow.points() < COW_POINTS as u32).then(|| { cow.color() as usize * COW_POINTS + cow.points() as usize })
+}
+
+// Objectives in the observer's hand are numbered by first appearance, so copies of one objective share an index
+fn cardIndex(obs: &Observation, card: &Card) -> Option<usize> {
+    match card {
+        Card::CowCard(cow) => cowIndex(cow),
+        Card::ObjectiveCard(objective) => {
+            let hand = obs.players[obs.seat as usize].deck.hand.as_deref().unwrap_or(&[]);
+            let mut objectives: Vec<&Card> = vec![];
+            for c in hand.iter().filter(|c| { matches!(c, Card::ObjectiveCard(_)) }) {
+                if !objectives.contains(&c) {
+                    objectives.push(c);
+                }
+            }
+            objectives.iter()
+                .position(|c| { **c == Card::ObjectiveCard(*objective) })
+                .filter(|i| { *i < HAND_OBJECTIVES })
+                .map(|i| { COWS + i })
+        }
+    }
+}
+
+fn optional(index: Option<Option<usize>>) -> Option<usize> {
+    match index {
+        None => Some(0),
+        Some(i) => i.map(|i| { i + 1 }),
+    }
+}
+
+fn bounded(value: usize, limit: usize) -> Option<usize> {
+    (value < limit).then_some(value)
+}
+
+// The action's index in 0..ACTION_SPACE, or None when one of its parameters doesn't fit the fixed layout
+pub fn actionIndex(obs: &Observation, action: &Action) -> Option<usize> {
+    let me = &obs.players[obs.seat as usize];
+    let (block, offset) = match action {
+        Action::PayCoins(_) => (0, Some(0)),
+        Action::BuyCow(cow) => (1, cowIndex(cow)),
+        Action::Buy2Cows(a, b) => (2, Some(a.color() as usize * COW_COLORS + b.color() as usize)),
+        Action::SellCow(cow) => (3, cowIndex(cow)),
+        Action::SellCowPair(cow, _) => (4, Some(cow.color() as usize)),
+        Action::SellHand => (5, Some(0)),
+        Action::FillHand => (6, Some(0)),
+        Action::DrawCards(n) => (7, optional(n.map(|n| { bounded(n as usize, MAX_DRAW + 1) }))),
+        Action::DiscardCards(n) => (8, optional(n.map(|n| { bounded(n as usize, MAX_DRAW + 1) }))),
+        Action::TrashCard(card) => (9, optional(card.map(|c| { cardIndex(obs, &c) }))),
+        Action::MoveCattleman(space) => (10, optional(space.map(|s| { bounded(s.max(0) as usize, TRACK_SPACES) }))),
+        Action::MoveEngine(k) => (11, optional(k.map(|k| { bounded((k + MAX_ENGINE_MOVE as i32).max(0) as usize, 2 * MAX_ENGINE_MOVE + 1) }))),
+        Action::MoveCertificate(k) => (12, optional(k.map(|k| {
+            bounded((k + MAX_CERTIFICATE_MOVE as i32).max(0) as usize, 2 * MAX_CERTIFICATE_MOVE + 1)
+        }))),
+        Action::MaxCertificate => (13, Some(0)),
+        Action::TakeTepee(tepee) => (14, Some(match tepee {
+            None => 0,
+            Some(Tepee::Green) => 1,
+            Some(Tepee::Blue) => 2,
+        })),
+        Action::TakeHazard(hazard) => (15, optional(hazard.map(|h| {
+            obs.track.iter()
+                .position(|s| { *s.occupant() == SpaceOccupant::Hazard(h.area(), Some(h)) })
+                .filter(|i| { *i < TRACK_SPACES })
+        }))),
+        Action::TakeObjective(objective) => (16, optional(objective.map(|o| {
+            obs.objective_market.iter().position(|m| { *m == o }).filter(|i| { *i < OBJECTIVE_MARKET })
+        }))),
+        Action::TakeStationmaster(emp) => (17, Some(emp.map(|e| { e as usize + 1 }).unwrap_or(0))),
+        Action::RemoveDisc(slot) => (18, optional(slot.map(|s| { bounded(s as usize, DISC_SLOTS) }))),
+        Action::PlaceDisc(city) => (19, optional(city.map(|c| { bounded(c as usize, CITIES) }))),
+        Action::HireEmployee(emp) => (20, Some(emp.map(|e| { e as usize + 1 }).unwrap_or(0))),
+        Action::PlaceBuilding(space, building) => (21, match (space, building) {
+            (Some(space), Some(building)) => me.buildings.iter()
+                .position(|b| { b == building })
+                .filter(|b| { *b < UNPLACED_BUILDINGS && (*space as usize) < TRACK_SPACES })
+                .map(|b| { 1 + *space as usize * UNPLACED_BUILDINGS + b }),
+            _ => Some(0),
+        }),
+        Action::Auxiliary1(i) => (22, bounded(*i as usize, AUX_SLOTS)),
+        Action::Auxiliary2(i) => (23, bounded(*i as usize, AUX_SLOTS)),
+        Action::UseBuilding(or, xor) => (24, (*or < 3 && *xor < 2).then(|| { *or as usize * 2 + *xor as usize })),
+        Action::DiscardCard(card) => (25, cardIndex(obs, card)),
+        Action::ChooseTile(col, row) => (26, (*col < Foresight::FORESIGHT_SIZE as u32 && *row < 2)
+            .then(|| { *row as usize * Foresight::FORESIGHT_SIZE + *col as usize })),
+        Action::UpgradeStation(station) => (27, bounded(*station as usize, STATIONS)),
+        Action::PlayObjective(objective) => (28, cardIndex(obs, &Card::ObjectiveCard(*objective)).map(|i| { i - COWS })),
+        Action::Pass => (29, Some(0)),
+    };
+    offset.map(|offset| { blockStart(block) + offset })
+}
+
+// 1.0 at the index of every legal action, for masking a policy's output
+pub fn legalMask(obs: &Observation, legal: &[Action]) -> Vec<f32> {
+    let mut mask = vec![0.0; ACTION_SPACE];
+    for action in legal {
+        if let Some(i) = actionIndex(obs, action) {
+            mask[i] = 1.0;
+        }
+    }
+    mask
+}
+
+// The legal action at an index, the first one if several share it
+pub fn actionAt(obs: &Observation, legal: &[Action], index: usize) -> Option<Action> {
+    legal.iter().find(|a| { actionIndex(obs, a) == Some(index) }).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::agent::{Agent, RandomAgent};
+    use crate::data::GameData;
+    use crate::Engine;
+
+    use super::*;
+
+    #[test]
+    fn testEncodingSizes() {
+        let data = GameData::embedded().unwrap();
+        let mut agent = RandomAgent::new(5);
+        for players in 2..=4 {
+            let mut engine = Engine::newGame(&data, players, 11).unwrap();
+            for _ in 0..300 {
+                if engine.isGameOver() {
+                    break;
+                }
+                let seat = engine.currentPlayer() as u32;
+                let view = PlayerView::new(&engine, seat);
+                let obs = view.observe();
+                assert_eq!(encode(&view).len(), FEATURE_SIZE);
+                let legal = engine.legalActions();
+                let mut indices: Vec<usize> = legal.iter().map(|a| { actionIndex(&obs, a).unwrap() }).collect();
+                assert!(indices.iter().all(|i| { *i < ACTION_SPACE }));
+                assert_eq!(actionAt(&obs, &legal, indices[0]), Some(legal[0]));
+                // Different legal actions never share an index
+                indices.sort();
+                indices.dedup();
+                assert_eq!(indices.len(), legal.len());
+                let action = agent.choose(&view, &legal);
+                engine.applyAction(action).unwrap();
+            }
+        }
+    }
+}
@@ -0,0 +1,285 @@
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg64;
+use serde::{Deserialize, Serialize};
+
+use crate::actions::{Action, ActionTag, ActionValues};
+use crate::agent::Agent;
+use crate::deck::Card;
+use crate::player::{Employee, PlayerBoard};
+use crate::tiles::Tile;
+use crate::tracks::SpaceOccupant;
+use crate::view::{PlayerView, SeatView};
+
+#[derive(Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+pub enum Strategy {
+    // Buys expensive cows and delivers big hands far down the line
+    CowBuyer,
+    // Hires engineers and upgrades stations
+    EngineRush,
+    // Hires craftsmen and covers the trail with buildings
+    BuildingSprawl,
+    // Collects and completes objective cards
+    ObjectiveHunter,
+}
+
+impl Strategy {
+    pub const ALL: [Strategy; 4] = [Strategy::CowBuyer, Strategy::EngineRush, Strategy::BuildingSprawl, Strategy::ObjectiveHunter];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Strategy::CowBuyer => "cows",
+            Strategy::EngineRush => "engine",
+            Strategy::BuildingSprawl => "buildings",
+            Strategy::ObjectiveHunter => "objectives",
+        }
+    }
+
+    pub fn fromName(name: &str) -> Option<Strategy> {
+        Strategy::ALL.iter().find(|s| { s.name() == name }).copied()
+    }
+
+    fn employee(&self) -> Employee {
+        match self {
+            Strategy::CowBuyer => Employee::Cowboy,
+            Strategy::EngineRush => Employee::Engineer,
+            Strategy::BuildingSprawl => Employee::Craftsman,
+            Strategy::ObjectiveHunter => Employee::Engineer,
+        }
+    }
+}
+
+// Scores every legal action by hand and takes the best one, ties are broken at random.
+// Passing and ending the turn score 0, so only actions worth something are taken
+pub struct HeuristicAgent {
+    strategy: Strategy,
+    rng: Pcg64,
+}
+
+impl HeuristicAgent {
+    // Hands worth at least this much go to Kansas City
+    const DELIVERY_VALUE: u32 = 8;
+
+    pub fn new(strategy: Strategy, seed: u64) -> HeuristicAgent {
+        HeuristicAgent { strategy, rng: Pcg64::seed_from_u64(seed) }
+    }
+
+    pub fn strategy(&self) -> Strategy { self.strategy }
+
+    // How much the strategy wants the effect of a building or auxiliary action
+    fn tagWeight(&self, me: &SeatView, tag: &ActionTag) -> i32 {
+        let favoured = |s: Strategy| { if self.strategy == s { 6 } else { 0 } };
+        match tag {
+            ActionTag::TakeCoins(ActionValues::Exact(v)) => *v * 2,
+            ActionTag::TakeCoins(_) => 4,
+            ActionTag::BuyCows => {
+                if me.dollars >= 6 { 3 + favoured(Strategy::CowBuyer) * 2 } else { -1 }
+            }
+            ActionTag::DiscardCard(_) => 1,
+            ActionTag::DrawCards(_) => 2,
+            ActionTag::TrashCard => 2 + favoured(Strategy::CowBuyer) / 2,
+            ActionTag::MoveCattleman(_) | ActionTag::TeleportCattleman(_) => 1,
+            ActionTag::MoveEngine(v) => {
+                let distance = match v {
+                    ActionValues::Exact(d) => *d,
+                    ActionValues::EmployeeMult(emp, m) => m * me.hired(*emp) as i32,
+                    _ => 1,
+                };
+                distance + favoured(Strategy::EngineRush)
+            }
+            ActionTag::MoveCertificate(_) => 3,
+            ActionTag::TakeTepee => 3,
+            ActionTag::TakeHazard => 2,
+            ActionTag::TakeObjective => 2 + favoured(Strategy::ObjectiveHunter) * 2,
+            ActionTag::HireEmployee(_) => 4 + favoured(Strategy::BuildingSprawl) / 2 + favoured(Strategy::EngineRush) / 2,
+            ActionTag::PlaceBuilding(_) => {
+                if me.buildings.is_empty() { -1 } else { 3 + favoured(Strategy::BuildingSprawl) * 2 }
+            }
+            ActionTag::PlaceDisc(_) | ActionTag::CityDiscMoveTrain | ActionTag::StationDiscBehindTrain => {
+                2 + favoured(Strategy::EngineRush)
+            }
+            ActionTag::AdjacentBuilding => 2,
+            ActionTag::Auxiliary => 2,
+            ActionTag::DoubleAuxiliary => 3,
+        }
+    }
+
+    // The best option of a building the cattleman could stop at, or nothing if it can't be used
+    fn locationValue(&self, view: &PlayerView, me: &SeatView, space: usize) -> i32 {
+        let track = view.track();
+        match track[space].occupant() {
+            SpaceOccupant::KansasCity => {
+                let value = me.deck.handValue() + me.certificates;
+                if value >= HeuristicAgent::DELIVERY_VALUE || me.deck.hand_len < 3 {
+                    10 + value as i32
+                } else {
+                    value as i32 - 4
+                }
+            }
+            SpaceOccupant::Building { building: Some(b), neutral, .. } if *neutral || b.owner() == me.id => {
+                b.actions().items.iter()
+                    .flatten()
+                    .map(|xor| {
+                        xor.items.iter()
+                            .flatten()
+                            .map(|and| { and.items.iter().flatten().map(|t| { self.tagWeight(me, t) }).sum::<i32>() })
+                            .max()
+                            .unwrap_or(0)
+                    })
+                    .filter(|v| { *v > 0 })
+                    .sum()
+            }
+            _ => 1,
+        }
+    }
+
+    // me is the view's own seat, built once for every action scored
+    fn score(&self, view: &PlayerView, me: &SeatView, action: &Action) -> i32 {
+        let dollars = me.dollars as i32;
+        match action {
+            Action::MoveCattleman(Some(space)) => {
+                let space = *space as usize;
+                self.locationValue(view, me, space) * 4 - HeuristicAgent::tollTo(view, me, space) * 3
+            }
+            Action::UseBuilding(or, xor) => {
+                let space = me.cattleman_pos;
+                view.track()[space].building()
+                    .and_then(|b| { b.actions().items[*or as usize] })
+                    .and_then(|option| { option.items[*xor as usize] })
+                    .map(|and| { and.items.iter().flatten().map(|t| { self.tagWeight(me, t) }).sum::<i32>() })
+                    .unwrap_or(0)
+            }
+            Action::Auxiliary1(idx) | Action::Auxiliary2(idx) => {
+                let (single, double) = me.board.auxAction(*idx as usize).availableActions();
+                let tag = if matches!(action, Action::Auxiliary2(_)) { double } else { single };
+                let cost = PlayerBoard::auxCost(*idx as usize, matches!(action, Action::Auxiliary2(_))) as i32;
+                tag.map(|t| { self.tagWeight(me, &t) }).unwrap_or(0) - cost
+            }
+            Action::BuyCow(cow) => {
+                let bonus = if self.strategy == Strategy::CowBuyer { 8 } else { 0 };
+                if dollars < 10 && self.strategy != Strategy::CowBuyer {
+                    return -1;
+                }
+                (cow.value() * 3 + cow.points() * 2) as i32 + bonus - 6
+            }
+            Action::SellCow(cow) => 8 - cow.value() as i32,
+            Action::SellCowPair(first, _) => 10 - first.value() as i32,
+            Action::DiscardCard(Card::CowCard(cow)) => 10 - cow.value() as i32,
+            Action::DiscardCard(Card::ObjectiveCard(_)) => if self.strategy == Strategy::ObjectiveHunter { 0 } else { 5 },
+            Action::TrashCard(Some(Card::CowCard(cow))) => 4 - 2 * cow.value() as i32,
+            Action::TrashCard(Some(Card::ObjectiveCard(_))) => -5,
+            Action::HireEmployee(Some(emp)) => {
+                let market = view.jobMarket().findEmployees(*emp);
+                let salary = market.first().map(|(s, _)| { *s }).unwrap_or(100);
+                let want = if *emp == self.strategy.employee() { 12 } else { 4 };
+                let have = me.hired(*emp) as i32;
+                want - salary / 2 - have
+            }
+            Action::PlaceBuilding(Some(space), Some(building)) => {
+                let bonus = if self.strategy == Strategy::BuildingSprawl { 10 } else { 0 };
+                let forest = matches!(view.track()[*space as usize].occupant(), SpaceOccupant::Building { forest: true, .. });
+                (building.points() * 3) as i32 - building.laborers() as i32 * 2 + bonus + forest as i32
+            }
+            Action::MoveEngine(Some(distance)) => {
+                let rush = if self.strategy == Strategy::EngineRush { 3 } else { 1 };
+                distance * rush
+            }
+            Action::UpgradeStation(station) => {
+                let s = view.stationTrack().station(*station as usize);
+                let rush = if self.strategy == Strategy::EngineRush { 10 } else { 2 };
+                rush + s.points() as i32 * 2 + s.stationMaster().map(|_| { 5 }).unwrap_or(0) - s.price() as i32
+            }
+            Action::RemoveDisc(Some(slot)) => {
+                match *slot {
+                    PlayerBoard::HAND_SLOT => 10,
+                    PlayerBoard::STEP_SLOT => if self.strategy == Strategy::EngineRush { 5 } else { 8 },
+                    slot => 6 - slot as i32,
+                }
+            }
+            Action::PlaceDisc(Some(city)) => {
+                let cities = view.stationTrack().cities();
+                let c = &cities.cities()[*city as usize];
+                if c.location() == 0 {
+                    return 1;
+                }
+                let engine = view.stationTrack().engineLocation(me.id);
+                let crossings = view.stationTrack().crossings().iter()
+                    .filter(|x| { engine < **x && **x <= c.location() })
+                    .count() as i32;
+                c.location() as i32 * 2 - crossings * 3
+            }
+            Action::ChooseTile(col, row) => {
+                let tile = if *row == 0 { view.foresightCurrent()[*col as usize] } else { view.foresightNext()[*col as usize] };
+                match tile {
+                    Tile::EmployeeTile(emp) if emp == self.strategy.employee() => 3,
+                    Tile::EmployeeTile(_) => 2,
+                    Tile::TepeeTile(_) => 1,
+                    Tile::HazardTile(_) => 0,
+                    Tile::BlankTile => -1,
+                }
+            }
+            Action::TakeObjective(Some(objective)) => {
+                let hunter = if self.strategy == Strategy::ObjectiveHunter { 5 } else { 0 };
+                objective.successPoints() as i32 + hunter - objective.failPoints() as i32
+            }
+            Action::PlayObjective(objective) => {
+                if self.strategy == Strategy::ObjectiveHunter { objective.successPoints() as i32 } else { -1 }
+            }
+            Action::TakeHazard(Some(hazard)) => hazard.points() as i32 * 2,
+            Action::TakeTepee(Some(_)) => 4,
+            Action::SellHand => 1,
+            Action::Pass | Action::FillHand => 0,
+            _ => 0,
+        }
+    }
+
+    fn tollTo(view: &PlayerView, me: &SeatView, space: usize) -> i32 {
+        let n = view.playerCount();
+        match view.track()[space].occupant() {
+            SpaceOccupant::Building { building: Some(b), neutral: false, .. } if b.owner() != me.id => b.toll().cost(n) as i32,
+            SpaceOccupant::Hazard(_, Some(h)) => h.toll().cost(n) as i32,
+            _ => 0,
+        }
+    }
+}
+
+impl Agent for HeuristicAgent {
+    fn choose(&mut self, view: &PlayerView, legal: &[Action]) -> Action {
+        if legal.len() == 1 {
+            return legal[0];
+        }
+        let me = view.me();
+        let scores: Vec<i32> = legal.iter().map(|a| { self.score(view, &me, a) }).collect();
+        let best = *scores.iter().max().unwrap();
+        let ties: Vec<usize> = (0..legal.len()).filter(|i| { scores[*i] == best }).collect();
+        legal[ties[self.rng.gen_range(0..ties.len())]]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::agent::RandomAgent;
+    use crate::data::GameData;
+    use crate::runner::playGame;
+
+    use super::*;
+
+    #[test]
+    fn testStrategiesBeatRandom() {
+        let data = GameData::embedded().unwrap();
+        for strategy in Strategy::ALL {
+            assert_eq!(Strategy::fromName(strategy.name()), Some(strategy));
+            let mut wins = 0;
+            for seed in 0..6 {
+                let mut agents: Vec<Box<dyn Agent>> = vec![
+                    Box::new(HeuristicAgent::new(strategy, seed)),
+                    Box::new(RandomAgent::new(seed)),
+                ];
+                let result = playGame(&data, &mut agents, seed).unwrap();
+                if result.winners.contains(&0) {
+                    wins += 1;
+                }
+            }
+            assert!(wins >= 4, "{:?} only won {} of 6", strategy, wins);
+        }
+    }
+}
@@ -0,0 +1,276 @@
+use crate::{Engine, GameState};
+use crate::actions::Action;
+use crate::deck::{Cow, Objective};
+use crate::error::GwtError;
+use crate::events::GameEvent;
+use crate::player::{Counters, Employee, PlayerChange};
+use crate::rules::{Pending, Phase};
+use crate::tiles::Tile;
+use crate::tracks::{EngineSpace, Space, StationMaster};
+
+// The parts of the engine an action can change, which the kept hash is made of
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub(crate) enum Part {
+    Player(usize),
+    Space(usize),
+    StationTrack,
+    Cows,
+    Foresight,
+    JobMarket,
+    // The objectives deck and the objective market
+    Objectives,
+}
+
+// One change an action made, with whatever it takes to put it back
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub(crate) enum Change {
+    // Saved the first time the action changes the player
+    Counters(usize, Counters),
+    // What the action added to the player's lists and moved in their deck
+    Player(usize, Vec<PlayerChange>),
+    // Spaces are small enough to save whole
+    Space(usize, Space),
+    // Where the player's engine was
+    EngineMoved(u32, EngineSpace),
+    StationUpgraded(u32, usize, Option<StationMaster>),
+    Delivered(u32, u32),
+    // The cow was at this index of its market
+    CowBought(usize, Cow),
+    CowsDealt(Vec<Cow>),
+    TileTaken { column: usize, row: usize, tile: Tile, refilled: bool },
+    EmployeeAdded,
+    EmployeeHired(usize, Employee),
+    ObjectiveTaken(usize, Objective),
+    ObjectivesDealt(usize),
+}
+
+impl Change {
+    fn part(&self) -> Part {
+        match self {
+            Change::Counters(i, _) | Change::Player(i, _) => Part::Player(*i),
+            Change::Space(i, _) => Part::Space(*i),
+            Change::EngineMoved(..) | Change::StationUpgraded(..) | Change::Delivered(..) => Part::StationTrack,
+            Change::CowBought(..) | Change::CowsDealt(_) => Part::Cows,
+            Change::TileTaken { .. } => Part::Foresight,
+            Change::EmployeeAdded | Change::EmployeeHired(..) => Part::JobMarket,
+            Change::ObjectiveTaken(..) | Change::ObjectivesDealt(_) => Part::Objectives,
+        }
+    }
+}
+
+// An applied action and the changes it made, in order. The turn state is small enough to always keep.
+// The players' shuffle RNGs are only saved when a draw reshuffles
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub(crate) struct Delta {
+    action: Action,
+    current_player: usize,
+    state: GameState,
+    phase: Phase,
+    pending: Vec<Vec<Pending>>,
+    changes: Vec<Change>,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct History {
+    done: Vec<Delta>,
+    // Redoing applies these again, which the engine's state makes come out the same
+    undone: Vec<Action>,
+}
+
+impl Engine {
+    // Starts or stops keeping the actions applied from now on for undo and redo, stopping forgets them
+    pub fn keepHistory(&mut self, keep: bool) {
+        if !keep {
+            self.history = None;
+        } else if self.history.is_none() {
+            self.history = Some(History::default());
+        }
+    }
+
+    pub fn canUndo(&self) -> bool {
+        self.history.as_ref().is_some_and(|h| { !h.done.is_empty() })
+    }
+
+    pub fn canRedo(&self) -> bool {
+        self.history.as_ref().is_some_and(|h| { !h.undone.is_empty() })
+    }
+
+    // The actions which can be undone, oldest first
+    pub fn appliedActions(&self) -> Vec<Action> {
+        self.history.as_ref().map(|h| { h.done.iter().map(|d| { d.action }).collect() }).unwrap_or_default()
+    }
+
+    // Takes back the last action, returning it
+    pub fn undo(&mut self) -> Result<Action, GwtError> {
+        let delta = self.history.as_mut().and_then(|h| { h.done.pop() }).ok_or(GwtError::NothingToUndo)?;
+        let action = delta.action;
+        self.revert(delta);
+        if let Some(history) = self.history.as_mut() {
+            history.undone.push(action);
+        }
+        self.emit(GameEvent::Undone { action });
+        self.flushEvents(true);
+        Ok(action)
+    }
+
+    // Puts the last undone action back, returning it. The observers are only told it was redone
+    pub fn redo(&mut self) -> Result<Action, GwtError> {
+        let action = self.history.as_mut().and_then(|h| { h.undone.pop() }).ok_or(GwtError::NothingToRedo)?;
+        let undone = self.history.as_mut().map(|h| { std::mem::take(&mut h.undone) }).unwrap_or_default();
+        let result = self.apply(action, false);
+        if let Some(history) = self.history.as_mut() {
+            history.undone = undone;
+            if result.is_err() {
+                history.undone.push(action);
+            }
+        }
+        result?;
+        self.emit(GameEvent::Redone { action });
+        self.flushEvents(true);
+        Ok(action)
+    }
+
+    // Every action is recorded, whether or not history is kept, so that a failed one can be taken back
+    pub(crate) fn beginDelta(&mut self, action: Action) {
+        self.recording = Some(Delta {
+            action,
+            current_player: self.current_player,
+            state: self.state.clone(),
+            phase: self.phase,
+            pending: self.pending.clone(),
+            changes: vec![],
+        });
+    }
+
+    // Keeps the delta when the action was applied and history is kept, and takes back whatever a failed action had
+    // already changed
+    pub(crate) fn endDelta(&mut self, applied: bool) {
+        let mut delta = match self.recording.take() {
+            Some(delta) => delta,
+            None => return,
+        };
+        let players: Vec<usize> = delta.changes.iter()
+            .filter_map(|c| { if let Change::Counters(i, _) = c { Some(*i) } else { None } })
+            .collect();
+        for i in players {
+            delta.changes.push(Change::Player(i, self.players[i].takeLog()));
+        }
+        if applied {
+            if let Some(history) = self.history.as_mut() {
+                history.done.push(delta);
+                history.undone.clear();
+            }
+        } else {
+            self.revert(delta);
+        }
+    }
+
+    // Called before a part changes, to take it out of the kept hash. While an action is recorded, saves the player's
+    // counters or the space the first time the action changes them and starts logging the player's lists and deck
+    pub(crate) fn touch(&mut self, part: Part) {
+        self.unhash(part);
+        let delta = match self.recording.as_mut() {
+            Some(delta) => delta,
+            None => return,
+        };
+        match part {
+            Part::Player(i) if !delta.changes.iter().any(|c| { matches!(c, Change::Counters(p, _) if *p == i) }) => {
+                delta.changes.push(Change::Counters(i, self.players[i].counters()));
+                self.players[i].startLog();
+            }
+            Part::Space(i) if !delta.changes.iter().any(|c| { matches!(c, Change::Space(s, _) if *s == i) }) => {
+                delta.changes.push(Change::Space(i, self.track[i]));
+            }
+            _ => {}
+        }
+    }
+
+    // Keeps a change just made to the station track, markets, foresight or objectives
+    pub(crate) fn record(&mut self, change: Change) {
+        if let Some(delta) = self.recording.as_mut() {
+            delta.changes.push(change);
+        }
+    }
+
+    // Takes the changes back, last first
+    fn revert(&mut self, delta: Delta) {
+        self.current_player = delta.current_player;
+        self.state = delta.state;
+        self.phase = delta.phase;
+        self.pending = delta.pending;
+        for change in delta.changes.into_iter().rev() {
+            self.unhash(change.part());
+            match change {
+                Change::Counters(i, counters) => self.players[i].setCounters(counters),
+                Change::Player(i, changes) => {
+                    for change in changes.into_iter().rev() {
+                        self.players[i].revert(change);
+                    }
+                }
+                Change::Space(i, space) => self.track[i] = space,
+                Change::EngineMoved(player, space) => {
+                    self.station_track.moveEngine(player, space);
+                }
+                Change::StationUpgraded(player, station, station_master) =>
+                    self.station_track.downgradeStation(player, station, station_master),
+                Change::Delivered(player, city) => self.station_track.takeBackDelivery(player, city),
+                Change::CowBought(idx, cow) => self.cows.putBack(idx, cow),
+                Change::CowsDealt(cows) => self.cows.undeal(&cows),
+                Change::TileTaken { column, row, tile, refilled } => self.foresight.putBack(column, row, tile, refilled),
+                Change::EmployeeAdded => self.job_market.removeLast(),
+                Change::EmployeeHired(slot, emp) => self.job_market.putBack(slot, emp),
+                Change::ObjectiveTaken(idx, objective) => self.objective_market.insert(idx, objective),
+                Change::ObjectivesDealt(n) => {
+                    for _ in 0..n {
+                        if let Some(objective) = self.objective_market.pop() {
+                            self.objectives.push(objective);
+                        }
+                    }
+                }
+            }
+        }
+        self.settleHash();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::agent::testing::playRandom;
+    use crate::data::GameData;
+
+    use super::*;
+
+    fn json(engine: &Engine) -> String {
+        serde_json::to_string(engine).unwrap()
+    }
+
+    #[test]
+    fn testUndoRedo() {
+        let data = GameData::embedded().unwrap();
+        let mut engine = Engine::newGame(&data, 3, 8).unwrap();
+        assert_eq!(engine.undo(), Err(GwtError::NothingToUndo));
+        engine.keepHistory(true);
+        let mut states = vec![json(&engine)];
+        playRandom(&mut engine, 5, usize::MAX, |engine| { states.push(json(engine)) });
+        let applied = engine.appliedActions();
+        assert_eq!(applied.len(), states.len() - 1);
+
+        for i in (0..applied.len()).rev() {
+            assert_eq!(engine.undo(), Ok(applied[i]));
+            assert_eq!(json(&engine), states[i]);
+        }
+        assert!(!engine.canUndo());
+        for i in 0..applied.len() {
+            assert_eq!(engine.redo(), Ok(applied[i]));
+            assert_eq!(json(&engine), states[i + 1]);
+        }
+        assert_eq!(engine.redo(), Err(GwtError::NothingToRedo));
+
+        // A new action after undoing drops what was undone
+        engine.undo().unwrap();
+        engine.undo().unwrap();
+        let action = engine.legalActions()[0];
+        engine.applyAction(action).unwrap();
+        assert!(!engine.canRedo());
+    }
+}
@@ -1,7 +1,9 @@
 use serde::{Deserialize, Serialize};
 use serde::de::DeserializeOwned;
 
-#[derive(Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+use crate::error::GwtError;
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize, Debug)]
 pub struct Or<T, const LENGTH: usize>
     where [Option<T>; LENGTH]: Serialize + DeserializeOwned {
     pub items: [Option<T>; LENGTH],
@@ -9,24 +11,24 @@ pub struct Or<T, const LENGTH: usize>
 
 impl<T: Copy + Clone, const LENGTH: usize> Or<T, LENGTH>
     where [Option<T>; LENGTH]: Serialize + DeserializeOwned {
-    pub fn new(items: &[T]) -> Or<T, LENGTH> {
+    pub fn new(items: &[T]) -> Result<Or<T, LENGTH>, GwtError> {
         if items.len() > LENGTH {
-            panic!("input is too long")
+            Err(GwtError::TooManyItems { length: items.len(), capacity: LENGTH })
         } else {
             let mut r = Or::<T, LENGTH>::empty();
-            for i in 0..items.len() {
-                r.items[i] = Some(items[i]);
+            for (slot, item) in r.items.iter_mut().zip(items) {
+                *slot = Some(*item);
             }
-            return r;
+            Ok(r)
         }
     }
 
     pub fn empty() -> Or<T, LENGTH> {
-        return Or::<T, LENGTH> { items: [None; LENGTH] };
+        Or::<T, LENGTH> { items: [None; LENGTH] }
     }
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize, Debug)]
 pub struct XOr<T, const LENGTH: usize>
     where [Option<T>; LENGTH]: Serialize + DeserializeOwned {
     pub items: [Option<T>; LENGTH],
@@ -34,23 +36,23 @@ pub struct XOr<T, const LENGTH: usize>
 
 impl<T: Copy + Clone, const LENGTH: usize> XOr<T, LENGTH>
     where [Option<T>; LENGTH]: Serialize + DeserializeOwned {
-    pub fn new(items: &[T]) -> XOr<T, LENGTH> {
+    pub fn new(items: &[T]) -> Result<XOr<T, LENGTH>, GwtError> {
         if items.len() > LENGTH {
-            panic!("input is too long")
+            Err(GwtError::TooManyItems { length: items.len(), capacity: LENGTH })
         } else {
             let mut r = XOr::<T, LENGTH>::empty();
-            for i in 0..items.len() {
-                r.items[i] = Some(items[i]);
+            for (slot, item) in r.items.iter_mut().zip(items) {
+                *slot = Some(*item);
             }
-            return r;
+            Ok(r)
         }
     }
     pub fn empty() -> XOr<T, LENGTH> {
-        return XOr::<T, LENGTH> { items: [None; LENGTH] };
+        XOr::<T, LENGTH> { items: [None; LENGTH] }
     }
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize, Debug)]
 pub struct And<T, const LENGTH: usize>
     where [Option<T>; LENGTH]: Serialize + DeserializeOwned {
     pub items: [Option<T>; LENGTH],
@@ -58,24 +60,24 @@ pub struct And<T, const LENGTH: usize>
 
 impl<T: Copy + Clone, const LENGTH: usize> And<T, LENGTH>
     where [Option<T>; LENGTH]: Serialize + DeserializeOwned {
-    pub fn new(items: &[T]) -> And<T, LENGTH> {
+    pub fn new(items: &[T]) -> Result<And<T, LENGTH>, GwtError> {
         if items.len() > LENGTH {
-            panic!("input is too long")
+            Err(GwtError::TooManyItems { length: items.len(), capacity: LENGTH })
         } else {
             let mut r = And::<T, LENGTH>::empty();
-            for i in 0..items.len() {
-                r.items[i] = Some(items[i]);
+            for (slot, item) in r.items.iter_mut().zip(items) {
+                *slot = Some(*item);
             }
-            return r;
+            Ok(r)
         }
     }
 
     pub fn empty() -> And<T, LENGTH> {
-        return And::<T, LENGTH> { items: [None; LENGTH] };
+        And::<T, LENGTH> { items: [None; LENGTH] }
     }
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize, Debug)]
 pub struct NAnd<T, const LENGTH: usize>
     where [Option<T>; LENGTH]: Serialize + DeserializeOwned {
     pub items: [Option<T>; LENGTH],
@@ -83,19 +85,19 @@ pub struct NAnd<T, const LENGTH: usize>
 
 impl<T: Copy + Clone, const LENGTH: usize> NAnd<T, LENGTH>
     where [Option<T>; LENGTH]: Serialize + DeserializeOwned {
-    pub fn new(items: &[T]) -> NAnd<T, LENGTH> {
+    pub fn new(items: &[T]) -> Result<NAnd<T, LENGTH>, GwtError> {
         if items.len() > LENGTH {
-            panic!("input is too long")
+            Err(GwtError::TooManyItems { length: items.len(), capacity: LENGTH })
         } else {
             let mut r = NAnd::<T, LENGTH>::empty();
-            for i in 0..items.len() {
-                r.items[i] = Some(items[i]);
+            for (slot, item) in r.items.iter_mut().zip(items) {
+                *slot = Some(*item);
             }
-            return r;
+            Ok(r)
         }
     }
 
     pub fn empty() -> NAnd<T, LENGTH> {
-        return NAnd::<T, LENGTH> { items: [None; LENGTH] };
+        NAnd::<T, LENGTH> { items: [None; LENGTH] }
     }
 }
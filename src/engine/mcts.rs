@@ -0,0 +1,276 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg64;
+
+use crate::Engine;
+use crate::actions::Action;
+use crate::agent::Agent;
+use crate::network::Network;
+use crate::view::PlayerView;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct MctsConfig {
+    pub iterations: u32,
+    // Stops searching early once this much time has passed, searches are only reproducible without it
+    pub time_limit: Option<Duration>,
+    // Random actions played after leaving the tree before the position is scored
+    pub rollout_depth: u32,
+    pub exploration: f64,
+}
+
+impl MctsConfig {
+    pub fn new(iterations: u32) -> MctsConfig {
+        MctsConfig { iterations, time_limit: None, rollout_depth: 200, exploration: 0.7 }
+    }
+}
+
+// A node is reached by taking action, chosen by player, from its parent
+struct Node {
+    action: Option<Action>,
+    player: usize,
+    children: Vec<usize>,
+    visits: u32,
+    // The number of times the node's action was legal when its parent was visited
+    availability: u32,
+    reward: f64,
+}
+
+impl Node {
+    fn new(action: Option<Action>, player: usize) -> Node {
+        Node { action, player, children: vec![], visits: 0, availability: 1, reward: 0.0 }
+    }
+}
+
+// Single observer information set MCTS: every iteration deals the hidden cards and tiles at random,
+// then searches the shared tree restricted to the actions legal in that deal.
+// With a network, new nodes are tried in order of the policy and leaves are scored by the value instead of a rollout
+pub struct MctsAgent {
+    config: MctsConfig,
+    network: Option<Arc<Network>>,
+    rng: Pcg64,
+}
+
+impl MctsAgent {
+    pub fn new(config: MctsConfig, seed: u64) -> MctsAgent {
+        MctsAgent { config, network: None, rng: Pcg64::seed_from_u64(seed) }
+    }
+
+    pub fn withNetwork(config: MctsConfig, network: Arc<Network>, seed: u64) -> MctsAgent {
+        MctsAgent { config, network: Some(network), rng: Pcg64::seed_from_u64(seed) }
+    }
+
+    pub fn config(&self) -> &MctsConfig { &self.config }
+
+    // The number of visits of each root action after searching
+    pub fn search(&mut self, view: &PlayerView, legal: &[Action]) -> Vec<(Action, u32)> {
+        let start = Instant::now();
+        let mut nodes = vec![Node::new(None, view.seat() as usize)];
+        // One engine for the whole search, each iteration is undone before the next deals again
+        let mut engine = view.determinize(&mut self.rng);
+        engine.keepHistory(true);
+        for i in 0..self.config.iterations {
+            if let Some(limit) = self.config.time_limit {
+                if i > 0 && start.elapsed() >= limit {
+                    break;
+                }
+            }
+            if i > 0 {
+                view.redeal(&mut engine, &mut self.rng);
+            }
+            let mut path = vec![0];
+            let mut node = 0;
+            // Selection and expansion, stopping where no action is legal even though the game isn't over
+            while !engine.isGameOver() {
+                let player = engine.currentPlayer();
+                let actions = engine.legalActions();
+                if actions.is_empty() {
+                    break;
+                }
+                let untried: Vec<Action> = actions.iter()
+                    .filter(|a| { !nodes[node].children.iter().any(|c| { nodes[*c].action == Some(**a) }) })
+                    .copied()
+                    .collect();
+                if !untried.is_empty() {
+                    let action = match &self.network {
+                        Some(network) => {
+                            let priors = network.priors(&engine, &untried);
+                            let best = (0..untried.len()).max_by(|a, b| { priors[*a].total_cmp(&priors[*b]) }).unwrap();
+                            untried[best]
+                        }
+                        None => untried[self.rng.gen_range(0..untried.len())],
+                    };
+                    // The child only joins the tree once its action applied, so every child has been visited
+                    if engine.applyAction(action).is_err() {
+                        break;
+                    }
+                    nodes.push(Node::new(Some(action), player));
+                    let child = nodes.len() - 1;
+                    nodes[node].children.push(child);
+                    path.push(child);
+                    break;
+                }
+                let child = match self.select(&mut nodes, node, &actions) {
+                    Some(child) => child,
+                    None => break,
+                };
+                if engine.applyAction(nodes[child].action.unwrap()).is_err() {
+                    break;
+                }
+                path.push(child);
+                node = child;
+            }
+            let rewards = match &self.network {
+                Some(network) => network.values(&engine),
+                None => {
+                    // Rollout
+                    for _ in 0..self.config.rollout_depth {
+                        if engine.isGameOver() {
+                            break;
+                        }
+                        let actions = engine.legalActions();
+                        if actions.is_empty() {
+                            break;
+                        }
+                        let action = actions[self.rng.gen_range(0..actions.len())];
+                        if engine.applyAction(action).is_err() {
+                            break;
+                        }
+                    }
+                    MctsAgent::rewards(&engine)
+                }
+            };
+            for n in path {
+                nodes[n].visits += 1;
+                nodes[n].reward += rewards[nodes[n].player];
+            }
+            while engine.undo().is_ok() {}
+        }
+        let mut visits: Vec<(Action, u32)> = nodes[0].children.iter()
+            .filter_map(|c| { nodes[*c].action.map(|a| { (a, nodes[*c].visits) }) })
+            .filter(|(a, _)| { legal.contains(a) })
+            .collect();
+        for action in legal {
+            if !visits.iter().any(|(a, _)| { a == action }) {
+                visits.push((*action, 0));
+            }
+        }
+        visits
+    }
+
+    // UCB over the children legal in this deal, each of them was available for selection once more.
+    // None when none of them is
+    fn select(&self, nodes: &mut [Node], node: usize, actions: &[Action]) -> Option<usize> {
+        let children: Vec<usize> = nodes[node].children.iter()
+            .filter(|c| { actions.contains(&nodes[**c].action.unwrap()) })
+            .copied()
+            .collect();
+        let mut best = *children.first()?;
+        let mut best_score = f64::MIN;
+        for child in children {
+            nodes[child].availability += 1;
+            let n = &nodes[child];
+            let score = n.reward / n.visits as f64
+                + self.config.exploration * ((n.availability as f64).ln() / n.visits as f64).sqrt();
+            if score > best_score {
+                best = child;
+                best_score = score;
+            }
+        }
+        Some(best)
+    }
+
+    // Scores scaled between the worst and best player, finished games reward the winners
+    fn rewards(engine: &Engine) -> Vec<f64> {
+        let totals: Vec<f64> = engine.scoreSheets().iter().map(|s| { s.total() as f64 }).collect();
+        let best = totals.iter().cloned().fold(f64::MIN, f64::max);
+        let worst = totals.iter().cloned().fold(f64::MAX, f64::min);
+        let bonus = if engine.isGameOver() { 0.5 } else { 0.0 };
+        totals.iter()
+            .map(|t| {
+                let scaled = if best > worst { (t - worst) / (best - worst) } else { 0.5 };
+                if *t == best { scaled + bonus } else { scaled }
+            })
+            .collect()
+    }
+}
+
+impl Agent for MctsAgent {
+    fn choose(&mut self, view: &PlayerView, legal: &[Action]) -> Action {
+        if legal.len() == 1 {
+            return legal[0];
+        }
+        self.search(view, legal).iter()
+            .max_by_key(|(_, visits)| { *visits })
+            .map(|(action, _)| { *action })
+            .unwrap_or(legal[0])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::data::GameData;
+    use crate::deck::Card;
+    use crate::rules::Pending;
+
+    use super::*;
+
+    #[test]
+    fn testDeterminize() {
+        let data = GameData::embedded().unwrap();
+        let engine = Engine::newGame(&data, 3, 4).unwrap();
+        let view = PlayerView::new(&engine, 1);
+        let mut rng = Pcg64::seed_from_u64(1);
+        let sorted = |cards: &Vec<Card>| {
+            let mut cards: Vec<String> = cards.iter().map(|c| { format!("{:?}", c) }).collect();
+            cards.sort();
+            cards
+        };
+        let mut changed = false;
+        for _ in 0..5 {
+            let deal = view.determinize(&mut rng);
+            for (before, after) in engine.players().iter().zip(deal.players()) {
+                assert_eq!(before.deck().cowCards().len(), after.deck().cowCards().len());
+                assert_eq!(before.deck().hand().len(), after.deck().hand().len());
+                if before.id() == 1 {
+                    assert_eq!(before.deck().hand(), after.deck().hand());
+                } else {
+                    changed |= sorted(before.deck().hand()) != sorted(after.deck().hand());
+                }
+            }
+            assert_eq!(deal.legalActions(), engine.legalActions());
+        }
+        assert!(changed);
+    }
+
+    #[test]
+    fn testSearch() {
+        let data = GameData::embedded().unwrap();
+        let engine = Engine::newGame(&data, 2, 8).unwrap();
+        let legal = engine.legalActions();
+        let mut agent = MctsAgent::new(MctsConfig { rollout_depth: 20, ..MctsConfig::new(50) }, 3);
+        let visits = agent.search(&PlayerView::new(&engine, 0), &legal);
+        assert_eq!(visits.len(), legal.len());
+        assert_eq!(visits.iter().map(|(_, v)| { *v }).sum::<u32>(), 50);
+        let action = agent.choose(&PlayerView::new(&engine, 0), &legal);
+        assert!(legal.contains(&action));
+    }
+
+    #[test]
+    fn testSearchStuck() {
+        let data = GameData::embedded().unwrap();
+        let mut engine = Engine::newGame(&data, 2, 8).unwrap();
+        // A card to discard from an empty hand, which play settles before it's reached: nothing is legal, yet the
+        // game isn't over
+        for card in engine.players[0].deck().hand().clone() {
+            engine.players[0].deckMut().playCard(card).unwrap();
+        }
+        engine.pending = vec![vec![Pending::Discard(1)]];
+        assert!(engine.legalActions().is_empty() && !engine.isGameOver());
+
+        let mut agent = MctsAgent::new(MctsConfig::new(20), 3);
+        let visits = agent.search(&PlayerView::new(&engine, 0), &[Action::FillHand]);
+        assert_eq!(visits, vec![(Action::FillHand, 0)]);
+    }
+}
@@ -0,0 +1,237 @@
+// Game events told as English, one line for each action: "Player 2 moved from space 3 to space 7, paid a $2 green
+// toll to Player 1, used building 4a, hired an Engineer for $5". Players are numbered from 1 here, unlike seats.
+
+use std::sync::{Arc, Mutex};
+
+use crate::Engine;
+use crate::actions::Action;
+use crate::buildings::{Building, Tepee, Toll};
+use crate::data::GameData;
+use crate::deck::{Card, Cow};
+use crate::describe::{article, plural};
+use crate::error::GwtError;
+use crate::events::GameEvent;
+use crate::notation::Notation;
+use crate::record::GameRecord;
+use crate::tiles::Tile;
+
+pub struct Narrator<'a> {
+    data: &'a GameData,
+    notation: Notation<'a>,
+    // The player and what they've done so far in the action being narrated
+    player: Option<u32>,
+    clauses: Vec<String>,
+}
+
+fn player(seat: u32) -> String {
+    format!("Player {}", seat + 1)
+}
+
+fn cow(cow: &Cow) -> String {
+    let color = format!("{:?}", cow.color());
+    match cow.points() {
+        0 => format!("{} {}", article(&color), color),
+        points => format!("{} {} ({})", article(&color), color, plural(points, "point")),
+    }
+}
+
+fn card(card: &Card) -> String {
+    match card {
+        Card::CowCard(c) => cow(c),
+        Card::ObjectiveCard(_) => "an objective card".to_string(),
+    }
+}
+
+fn tile(tile: &Tile) -> String {
+    match tile {
+        Tile::EmployeeTile(emp) => format!("{} {:?}", article(&format!("{:?}", emp)), emp),
+        Tile::HazardTile(hazard) => format!("a {:?} hazard", hazard.area()).to_lowercase(),
+        Tile::TepeeTile(tepee) => format!("a {} tepee", tepeeColor(*tepee)),
+        Tile::BlankTile => "a blank tile".to_string(),
+    }
+}
+
+fn tepeeColor(tepee: Tepee) -> &'static str {
+    match tepee {
+        Tepee::Green => "green",
+        Tepee::Blue => "blue",
+    }
+}
+
+fn tollColor(toll: Toll) -> &'static str {
+    match toll {
+        Toll::NoToll => "free",
+        Toll::Green => "green",
+        Toll::Black => "black",
+        Toll::GreenBlack => "green and black",
+    }
+}
+
+fn dollars(amount: i32) -> String {
+    if amount < 0 { format!("-${}", -amount) } else { format!("${}", amount) }
+}
+
+impl<'a> Narrator<'a> {
+    pub fn new(data: &'a GameData) -> Narrator<'a> {
+        Narrator { data, notation: Notation::new(data), player: None, clauses: vec![] }
+    }
+
+    fn building(&self, building: &Building) -> String {
+        match self.data.neutral_buildings.iter().position(|b| { b == building }) {
+            Some(i) => format!("neutral building {}", (b'A' + i as u8) as char),
+            None => match self.notation.building(building) {
+                Ok(name) => format!("building {}", &name[1..]),
+                Err(_) => "a building".to_string(),
+            },
+        }
+    }
+
+    // What the acting player did, or None for events which aren't worth telling
+    fn clause(&self, event: &GameEvent) -> Option<String> {
+        let clause = match event {
+            GameEvent::ActionTaken { action, .. } => match action {
+                Action::Pass => "passed".to_string(),
+                Action::Auxiliary1(i) => format!("took auxiliary action {}", i + 1),
+                Action::Auxiliary2(i) => format!("took auxiliary action {} twice", i + 1),
+                Action::FillHand => "refilled their hand".to_string(),
+                _ => return None,
+            },
+            GameEvent::CattlemanMoved { from, to, .. } => format!("moved from space {} to space {}", from, to),
+            GameEvent::TollPaid { to: Some(owner), toll, amount, .. } =>
+                format!("paid a ${} {} toll to {}", amount, tollColor(*toll), player(*owner)),
+            GameEvent::TollPaid { to: None, toll, amount, .. } => format!("paid a ${} {} hazard toll", amount, tollColor(*toll)),
+            GameEvent::CoinsTaken { amount, .. } if *amount < 0 => format!("paid ${}", -amount),
+            GameEvent::CoinsTaken { amount, .. } => format!("took ${}", amount),
+            GameEvent::AuxiliaryPaid { amount, .. } => format!("paid ${} for it", amount),
+            GameEvent::CertificatesGained { amount, .. } => format!("gained {}", plural(*amount, "certificate")),
+            GameEvent::CardsDrawn { count, .. } => format!("drew {}", plural(*count, "card")),
+            GameEvent::CardPlayed { card: c, .. } => format!("discarded {}", card(c)),
+            GameEvent::CardTrashed { card: c, .. } => format!("removed {} from their deck", card(c)),
+            GameEvent::CowBought { cow: c, price, .. } => format!("bought {} for ${}", cow(c), price),
+            GameEvent::EmployeeHired { employee, cost, .. } => {
+                let name = format!("{:?}", employee);
+                format!("hired {} {} for ${}", article(&name), name, cost)
+            }
+            GameEvent::BuildingPlaced { building, space, cost, .. } =>
+                format!("built {} on space {} for ${}", self.building(building), space, cost),
+            GameEvent::BuildingUsed { building, .. } => format!("used {}", self.building(building)),
+            GameEvent::HazardTaken { hazard, .. } => format!("took a {:?} hazard", hazard.area()).to_lowercase(),
+            GameEvent::TepeeTaken { tepee, dollars: d, .. } =>
+                format!("took a {} tepee for {}", tepeeColor(*tepee), dollars(*d)),
+            GameEvent::ObjectiveTaken { .. } => "took an objective card".to_string(),
+            GameEvent::ObjectivePlayed { .. } => "played an objective card".to_string(),
+            GameEvent::EngineMoved { from, to, .. } => format!("moved their engine from {} to {}", from, to),
+            GameEvent::StationUpgraded { station, price, .. } => format!("upgraded station {} for ${}", station + 1, price),
+            GameEvent::StationMasterTaken { .. } => "took its station master".to_string(),
+            GameEvent::DiscRemoved { slot, .. } => format!("took the disc off slot {}", slot),
+            GameEvent::TileTaken { column, tile: t, .. } => format!("took {} from foresight column {}", tile(t), column + 1),
+            GameEvent::JobMarketRowFilled { row } => format!("filled row {} of the job market", row),
+            GameEvent::CowMarketRefilled => "refilled the cow market".to_string(),
+            GameEvent::HandSold { value, .. } => format!("sold their hand in Kansas City for ${}", value),
+            GameEvent::DiscPlaced { city, cost, .. } => {
+                let name = self.data.station_track.cities().cities().get(*city).map(|c| { c.name() }).unwrap_or("?");
+                format!("delivered to {} paying ${} in transport", name, cost)
+            }
+            GameEvent::FinalRoundTriggered { .. } => "triggered the final round".to_string(),
+            _ => return None,
+        };
+        Some(clause)
+    }
+
+    // The sentence for the action narrated so far, and starts on the next one
+    pub fn finish(&mut self) -> Option<String> {
+        let seat = self.player.take()?;
+        let clauses = std::mem::take(&mut self.clauses);
+        let did = if clauses.is_empty() { "did nothing".to_string() } else { clauses.join(", ") };
+        Some(format!("{} {}", player(seat), did))
+    }
+
+    // Returns the lines completed by the event: the last action's once the next starts, and lines of their own for
+    // undos and the end of the game
+    pub fn push(&mut self, event: &GameEvent) -> Vec<String> {
+        let mut lines = vec![];
+        match event {
+            GameEvent::ActionTaken { player, .. } => {
+                lines.extend(self.finish());
+                self.player = Some(*player);
+            }
+            GameEvent::GameOver { winners } => {
+                lines.extend(self.finish());
+                let names: Vec<String> = winners.iter().map(|w| { player(*w) }).collect();
+                lines.push(format!("The game is over, won by {}", names.join(" and ")));
+                return lines;
+            }
+            GameEvent::Undone { action } | GameEvent::Redone { action } => {
+                lines.extend(self.finish());
+                let verb = if matches!(event, GameEvent::Undone { .. }) { "Undid" } else { "Redid" };
+                let printed = self.notation.print(action).unwrap_or_else(|_| { format!("{:?}", action) });
+                lines.push(format!("{} {}", verb, printed));
+                return lines;
+            }
+            _ => {}
+        }
+        if let Some(clause) = self.clause(event) {
+            self.clauses.push(clause);
+        }
+        lines
+    }
+
+    pub fn narrate(&mut self, events: &[GameEvent]) -> Vec<String> {
+        let mut lines: Vec<String> = events.iter().flat_map(|e| { self.push(e) }).collect();
+        lines.extend(self.finish());
+        lines
+    }
+}
+
+// Plays a record back, checked the same way as a replay, and tells its story
+pub fn narrateRecord(data: &GameData, record: &GameRecord) -> Result<Vec<String>, GwtError> {
+    record.check(data)?;
+    let mut engine = Engine::setup(data, &record.setup)?;
+    let events = Arc::new(Mutex::new(Vec::<GameEvent>::new()));
+    let sink = events.clone();
+    engine.observe(move |event| { sink.lock().unwrap().push(event.clone()) });
+    record.playOn(&mut engine, record.actions.len())?;
+    let events = events.lock().unwrap();
+    Ok(Narrator::new(data).narrate(&events))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::agent::{Agent, RandomAgent};
+    use crate::runner::playGame;
+
+    use super::*;
+
+    #[test]
+    fn testNarration() {
+        let data = GameData::embedded().unwrap();
+        let mut agents: Vec<Box<dyn Agent>> = vec![Box::new(RandomAgent::new(1)), Box::new(RandomAgent::new(2))];
+        let result = playGame(&data, &mut agents, 9).unwrap();
+        let lines = narrateRecord(&data, &result.record(&data)).unwrap();
+        assert_eq!(lines.len(), result.actions.len() + 1);
+        assert!(lines[0].starts_with("Player 1 moved from space 0 to space "), "{}", lines[0]);
+        assert!(lines.iter().any(|l| { l.contains(" hired a") }));
+        assert!(lines.last().unwrap().starts_with("The game is over, won by Player"));
+
+        // Records of other data or another version aren't narrated any more than they're replayed
+        let mut record = result.record(&data);
+        record.data_hash ^= 1;
+        assert!(narrateRecord(&data, &record).is_err());
+        record.data_hash ^= 1;
+        record.version += 1;
+        assert!(narrateRecord(&data, &record).is_err());
+
+        let mut narrator = Narrator::new(&data);
+        let events = [
+            GameEvent::ActionTaken { player: 1, action: Action::MoveCattleman(Some(7)) },
+            GameEvent::CattlemanMoved { player: 1, from: 3, to: 7 },
+            GameEvent::TollPaid { from: 1, to: Some(0), toll: Toll::Green, amount: 2 },
+            GameEvent::EmployeeHired { player: 1, employee: crate::player::Employee::Engineer, cost: 5 },
+            GameEvent::Undone { action: Action::Pass },
+        ];
+        assert_eq!(narrator.narrate(&events), vec![
+            "Player 2 moved from space 3 to space 7, paid a $2 green toll to Player 1, hired an Engineer for $5".to_string(),
+            "Undid PASS".to_string(),
+        ]);
+    }
+}
@@ -0,0 +1,408 @@
+use std::fs::{read_to_string, write};
+use std::path::Path;
+use std::sync::Arc;
+
+use rand::{Rng, SeedableRng};
+use rand::prelude::SliceRandom;
+use rand_pcg::Pcg64;
+use serde::{Deserialize, Serialize};
+
+use crate::{Engine, GameState};
+use crate::actions::Action;
+use crate::data::GameData;
+use crate::error::{DataError, GwtError};
+use crate::features::{ACTION_SPACE, FEATURE_SIZE, LAYOUT_VERSION, actionIndex, encodeObservation};
+use crate::mcts::{MctsAgent, MctsConfig};
+use crate::runner::MAX_ACTIONS;
+use crate::view::PlayerView;
+
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct Layer {
+    inputs: usize,
+    outputs: usize,
+    // outputs rows of inputs weights each
+    weights: Vec<f32>,
+    biases: Vec<f32>,
+}
+
+impl Layer {
+    fn new<R: Rng>(inputs: usize, outputs: usize, rng: &mut R) -> Layer {
+        let limit = (6.0 / inputs as f32).sqrt();
+        Layer {
+            inputs,
+            outputs,
+            weights: (0..inputs * outputs).map(|_| { rng.gen_range(-limit..limit) }).collect(),
+            biases: vec![0.0; outputs],
+        }
+    }
+
+    fn forward(&self, input: &[f32]) -> Vec<f32> {
+        (0..self.outputs)
+            .map(|o| {
+                let row = &self.weights[o * self.inputs..(o + 1) * self.inputs];
+                self.biases[o] + row.iter().zip(input).map(|(w, x)| { w * x }).sum::<f32>()
+            })
+            .collect()
+    }
+
+    // Adds this example's gradients to grads and returns the gradient with respect to the input
+    fn backward(&self, input: &[f32], output_grad: &[f32], grads: &mut Layer) -> Vec<f32> {
+        let mut input_grad = vec![0.0; self.inputs];
+        for (o, &g) in output_grad.iter().enumerate().take(self.outputs) {
+            if g == 0.0 {
+                continue;
+            }
+            grads.biases[o] += g;
+            let offset = o * self.inputs;
+            for i in 0..self.inputs {
+                grads.weights[offset + i] += g * input[i];
+                input_grad[i] += g * self.weights[offset + i];
+            }
+        }
+        input_grad
+    }
+
+    // Why the layer's weights don't fit its shape, if they don't
+    fn misshapen(&self) -> Option<String> {
+        if self.weights.len() != self.inputs * self.outputs {
+            Some(format!("{} weights for {} inputs and {} outputs", self.weights.len(), self.inputs, self.outputs))
+        } else if self.biases.len() != self.outputs {
+            Some(format!("{} biases for {} outputs", self.biases.len(), self.outputs))
+        } else {
+            None
+        }
+    }
+
+    fn zeroed(&self) -> Layer {
+        Layer { inputs: self.inputs, outputs: self.outputs, weights: vec![0.0; self.weights.len()], biases: vec![0.0; self.outputs] }
+    }
+
+    fn step(&mut self, grads: &Layer, rate: f32) {
+        for (w, g) in self.weights.iter_mut().zip(grads.weights.iter()) {
+            *w -= rate * g;
+        }
+        for (b, g) in self.biases.iter_mut().zip(grads.biases.iter()) {
+            *b -= rate * g;
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Evaluation {
+    // The chance of winning for the observing seat
+    pub value: f32,
+    // One logit for each index of the action space
+    pub policy: Vec<f32>,
+}
+
+// One decision from a game, seen by the seat making it
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct Example {
+    pub features: Vec<f32>,
+    // The action space indices of the legal actions, and the share of the search spent on each
+    pub legal: Vec<usize>,
+    pub policy: Vec<f32>,
+    // The seat's share of the win at the end of the game
+    pub value: f32,
+}
+
+#[derive(Copy, Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct TrainConfig {
+    pub epochs: u32,
+    pub batch_size: usize,
+    pub learning_rate: f32,
+}
+
+impl TrainConfig {
+    pub fn new() -> TrainConfig {
+        TrainConfig { epochs: 10, batch_size: 32, learning_rate: 0.01 }
+    }
+}
+
+impl Default for TrainConfig {
+    fn default() -> TrainConfig {
+        TrainConfig::new()
+    }
+}
+
+// A multilayer perceptron over the features encoding, with a shared ReLU trunk and value and policy heads.
+// Features are passed through ln(1 + x) first since most of them are unbounded counts
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct Network {
+    layout_version: u32,
+    trunk: Vec<Layer>,
+    value: Layer,
+    policy: Layer,
+}
+
+struct Activations {
+    // The input to each trunk layer, then the trunk's output
+    inputs: Vec<Vec<f32>>,
+    value: f32,
+    policy: Vec<f32>,
+}
+
+impl Network {
+    pub fn new(hidden: &[usize], seed: u64) -> Network {
+        let mut rng = Pcg64::seed_from_u64(seed);
+        let mut trunk = Vec::<Layer>::new();
+        let mut inputs = FEATURE_SIZE;
+        for size in hidden {
+            trunk.push(Layer::new(inputs, *size, &mut rng));
+            inputs = *size;
+        }
+        Network {
+            layout_version: LAYOUT_VERSION,
+            trunk,
+            value: Layer::new(inputs, 1, &mut rng),
+            policy: Layer::new(inputs, ACTION_SPACE, &mut rng),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Network, GwtError> {
+        let file = path.display().to_string();
+        let contents = read_to_string(path).map_err(|e| { DataError::Io { file: file.clone(), message: e.to_string() } })?;
+        let network: Network = serde_json::from_str(&contents)
+            .map_err(|e| { DataError::Schema { file: file.clone(), line: e.line(), column: e.column(), message: e.to_string() } })?;
+        if network.layout_version != LAYOUT_VERSION {
+            let message = format!("Weights are for features layout {}, not {}", network.layout_version, LAYOUT_VERSION);
+            return Err(DataError::Invalid { file, message }.into());
+        }
+        if let Some(message) = network.misshapen() {
+            return Err(DataError::Invalid { file, message }.into());
+        }
+        Ok(network)
+    }
+
+    // Why the layers can't be chained from the features to the heads, if they can't
+    fn misshapen(&self) -> Option<String> {
+        let mut inputs = FEATURE_SIZE;
+        for (i, layer) in self.trunk.iter().enumerate() {
+            if layer.inputs != inputs {
+                return Some(format!("Trunk layer {} takes {} inputs, not {}", i, layer.inputs, inputs));
+            }
+            if let Some(message) = layer.misshapen() {
+                return Some(format!("Trunk layer {}: {}", i, message));
+            }
+            inputs = layer.outputs;
+        }
+        for (name, head, outputs) in [("Value", &self.value, 1), ("Policy", &self.policy, ACTION_SPACE)] {
+            if head.inputs != inputs || head.outputs != outputs {
+                return Some(format!("{} head is {}x{}, not {}x{}", name, head.inputs, head.outputs, inputs, outputs));
+            }
+            if let Some(message) = head.misshapen() {
+                return Some(format!("{} head: {}", name, message));
+            }
+        }
+        None
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), GwtError> {
+        let contents = serde_json::to_string(self).map_err(|e| { GwtError::Output(e.to_string()) })?;
+        write(path, contents).map_err(|e| { GwtError::Output(e.to_string()) })
+    }
+
+    fn forward(&self, features: &[f32]) -> Activations {
+        let mut inputs = vec![features.iter().map(|x| { x.max(0.0).ln_1p() }).collect::<Vec<f32>>()];
+        for layer in self.trunk.iter() {
+            let output = layer.forward(inputs.last().unwrap()).into_iter().map(|x| { x.max(0.0) }).collect();
+            inputs.push(output);
+        }
+        let hidden = inputs.last().unwrap();
+        let value = 1.0 / (1.0 + (-self.value.forward(hidden)[0]).exp());
+        let policy = self.policy.forward(hidden);
+        Activations { inputs, value, policy }
+    }
+
+    pub fn evaluate(&self, features: &[f32]) -> Evaluation {
+        let activations = self.forward(features);
+        Evaluation { value: activations.value, policy: activations.policy }
+    }
+
+    // The value for every seat in the game, finished games give each seat its share of the win
+    pub fn values(&self, engine: &Engine) -> Vec<f64> {
+        if let Some(shares) = winShares(engine) {
+            return shares;
+        }
+        (0..engine.players().len())
+            .map(|seat| { self.evaluate(&encodeObservation(&PlayerView::new(engine, seat as u32).observe())).value as f64 })
+            .collect()
+    }
+
+    // The softmax of the policy over the legal actions for the seat to move
+    pub fn priors(&self, engine: &Engine, actions: &[Action]) -> Vec<f32> {
+        let observation = PlayerView::new(engine, engine.currentPlayer() as u32).observe();
+        let policy = self.evaluate(&encodeObservation(&observation)).policy;
+        let logits: Vec<f32> = actions.iter()
+            .map(|a| { actionIndex(&observation, a).map(|i| { policy[i] }).unwrap_or(f32::MIN) })
+            .collect();
+        softmax(&logits)
+    }
+
+    // Binary cross entropy on the value and cross entropy on the policy over the legal actions,
+    // returns the mean loss of each epoch
+    pub fn train<R: Rng>(&mut self, examples: &[Example], config: &TrainConfig, rng: &mut R) -> Vec<f32> {
+        let mut order: Vec<usize> = (0..examples.len()).collect();
+        let mut losses = vec![];
+        for _ in 0..config.epochs {
+            order.shuffle(rng);
+            let mut total = 0.0;
+            for batch in order.chunks(config.batch_size.max(1)) {
+                let mut trunk_grads: Vec<Layer> = self.trunk.iter().map(|l| { l.zeroed() }).collect();
+                let mut value_grads = self.value.zeroed();
+                let mut policy_grads = self.policy.zeroed();
+                for i in batch {
+                    total += self.backward(&examples[*i], &mut trunk_grads, &mut value_grads, &mut policy_grads);
+                }
+                let rate = config.learning_rate / batch.len() as f32;
+                for (layer, grads) in self.trunk.iter_mut().zip(trunk_grads.iter()) {
+                    layer.step(grads, rate);
+                }
+                self.value.step(&value_grads, rate);
+                self.policy.step(&policy_grads, rate);
+            }
+            losses.push(total / examples.len().max(1) as f32);
+        }
+        losses
+    }
+
+    fn backward(&self, example: &Example, trunk_grads: &mut [Layer], value_grads: &mut Layer, policy_grads: &mut Layer) -> f32 {
+        let activations = self.forward(&example.features);
+        let hidden = activations.inputs.last().unwrap();
+        let v = activations.value.clamp(1e-6, 1.0 - 1e-6);
+        let mut loss = -(example.value * v.ln() + (1.0 - example.value) * (1.0 - v).ln());
+        let mut hidden_grad = self.value.backward(hidden, &[activations.value - example.value], value_grads);
+
+        let logits: Vec<f32> = example.legal.iter().map(|i| { activations.policy[*i] }).collect();
+        let probabilities = softmax(&logits);
+        let mut policy_grad = vec![0.0; ACTION_SPACE];
+        for ((index, p), target) in example.legal.iter().zip(probabilities.iter()).zip(example.policy.iter()) {
+            policy_grad[*index] = p - target;
+            loss -= target * p.max(1e-6).ln();
+        }
+        for (h, g) in hidden_grad.iter_mut().zip(self.policy.backward(hidden, &policy_grad, policy_grads)) {
+            *h += g;
+        }
+
+        for l in (0..self.trunk.len()).rev() {
+            // Through the ReLU, the layer's output is the next layer's input
+            for (g, out) in hidden_grad.iter_mut().zip(activations.inputs[l + 1].iter()) {
+                if *out <= 0.0 {
+                    *g = 0.0;
+                }
+            }
+            hidden_grad = self.trunk[l].backward(&activations.inputs[l], &hidden_grad, &mut trunk_grads[l]);
+        }
+        loss
+    }
+}
+
+// Each seat's share of the win, None while the game is still going
+pub fn winShares(engine: &Engine) -> Option<Vec<f64>> {
+    match engine.state() {
+        GameState::GameOver { winners, .. } => Some((0..engine.players().len())
+            .map(|seat| { if winners.contains(&(seat as u32)) { 1.0 / winners.len() as f64 } else { 0.0 } })
+            .collect()),
+        _ => None,
+    }
+}
+
+pub fn softmax(logits: &[f32]) -> Vec<f32> {
+    let max = logits.iter().cloned().fold(f32::MIN, f32::max);
+    let exps: Vec<f32> = logits.iter().map(|l| { (l - max).exp() }).collect();
+    let sum: f32 = exps.iter().sum();
+    exps.iter().map(|e| { e / sum }).collect()
+}
+
+// Plays one game with an MCTS agent in every seat, every decision with more than one legal action becomes an
+// example with the search's visit shares as the policy
+pub fn selfPlay(data: &GameData, players: u32, config: MctsConfig, network: Option<Arc<Network>>, seed: u64)
+                -> Result<Vec<Example>, GwtError> {
+    let mut engine = Engine::newGame(data, players, seed)?;
+    let mut agents: Vec<MctsAgent> = (0..players as u64)
+        .map(|seat| {
+            let seed = seed.wrapping_mul(31).wrapping_add(seat);
+            match &network {
+                Some(network) => MctsAgent::withNetwork(config, network.clone(), seed),
+                None => MctsAgent::new(config, seed),
+            }
+        })
+        .collect();
+    let mut examples = Vec::<(usize, Example)>::new();
+    let mut actions = 0;
+    while !engine.isGameOver() {
+        if actions >= MAX_ACTIONS {
+            return Err(GwtError::TurnLimit(MAX_ACTIONS));
+        }
+        let seat = engine.currentPlayer();
+        let legal = engine.legalActions();
+        let action = if legal.len() == 1 {
+            legal[0]
+        } else {
+            let view = PlayerView::new(&engine, seat as u32);
+            let observation = view.observe();
+            let visits = agents[seat].search(&view, &legal);
+            let total = visits.iter().map(|(_, v)| { *v }).sum::<u32>().max(1) as f32;
+            let indexed: Vec<(usize, f32)> = visits.iter()
+                .filter_map(|(a, v)| { actionIndex(&observation, a).map(|i| { (i, *v as f32 / total) }) })
+                .collect();
+            examples.push((seat, Example {
+                features: encodeObservation(&observation),
+                legal: indexed.iter().map(|(i, _)| { *i }).collect(),
+                policy: indexed.iter().map(|(_, p)| { *p }).collect(),
+                value: 0.0,
+            }));
+            visits.iter().max_by_key(|(_, v)| { *v }).map(|(a, _)| { *a }).unwrap()
+        };
+        engine.applyAction(action)?;
+        actions += 1;
+    }
+    let values = winShares(&engine).unwrap();
+    Ok(examples.into_iter()
+        .map(|(seat, example)| { Example { value: values[seat] as f32, ..example } })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::agent::Agent;
+
+    use super::*;
+
+    #[test]
+    fn testTrainAndSearch() {
+        let data = GameData::embedded().unwrap();
+        let config = MctsConfig { rollout_depth: 10, ..MctsConfig::new(8) };
+        let mut examples = selfPlay(&data, 2, config, None, 1).unwrap();
+        examples.truncate(40);
+        assert!(examples.iter().all(|e| { e.features.len() == FEATURE_SIZE && e.legal.len() == e.policy.len() }));
+
+        let mut network = Network::new(&[16], 2);
+        let mut rng = Pcg64::seed_from_u64(3);
+        let losses = network.train(&examples, &TrainConfig { epochs: 20, ..TrainConfig::new() }, &mut rng);
+        assert!(losses.last().unwrap() < losses.first().unwrap());
+
+        let path = std::env::temp_dir().join(format!("gwt_network_test_{}.json", std::process::id()));
+        network.save(&path).unwrap();
+        assert_eq!(Network::load(&path).unwrap(), network);
+        // Weights that don't fit the layout are refused
+        let mut broken = network.clone();
+        broken.trunk[0].biases.pop();
+        broken.save(&path).unwrap();
+        assert!(matches!(Network::load(&path), Err(GwtError::Data(DataError::Invalid { .. }))));
+        let mut broken = network.clone();
+        broken.policy = Layer::new(16, ACTION_SPACE - 1, &mut rng);
+        broken.save(&path).unwrap();
+        assert!(matches!(Network::load(&path), Err(GwtError::Data(DataError::Invalid { .. }))));
+        let mut broken = network.clone();
+        broken.trunk.push(Layer::new(8, 16, &mut rng));
+        broken.save(&path).unwrap();
+        assert!(matches!(Network::load(&path), Err(GwtError::Data(DataError::Invalid { .. }))));
+        std::fs::remove_file(&path).unwrap();
+
+        let engine = Engine::newGame(&data, 2, 5).unwrap();
+        let legal = engine.legalActions();
+        let mut agent = MctsAgent::withNetwork(MctsConfig::new(20), Arc::new(network), 4);
+        assert!(legal.contains(&agent.choose(&PlayerView::new(&engine, 0), &legal)));
+    }
+}
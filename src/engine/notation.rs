@@ -0,0 +1,326 @@
+// A short text notation for actions. Optional parameters are left off when they're None:
+//
+//   PAY 3                   PayCoins              DRAW 2, DRAW        DrawCards
+//   BUY Highland4           BuyCow                DISCARD 2           DiscardCards
+//   BUY Swiss2+Holstein1    Buy2Cows              TRASH Angus, TRASH O3   TrashCard
+//   SELL Angus              SellCow               MOVE 14             MoveCattleman
+//   SELL Angus+Angus        SellCowPair           ENG+3, ENG-2        MoveEngine
+//   SELLHAND, FILL, PASS    SellHand, FillHand, Pass
+//   CERT+1, CERTMAX         MoveCertificate, MaxCertificate
+//   TEPEE Green             TakeTepee             HAZARD Flood2GK     TakeHazard
+//   OBJ O12, PLAY O12       TakeObjective, PlayObjective
+//   MASTER Cowboy           TakeStationmaster     HIRE Engineer       HireEmployee
+//   REMOVE 5                RemoveDisc            DELIVER 4           PlaceDisc
+//   B5a@14, B5a, BUILD@14   PlaceBuilding         AUX1 2, AUX2 2      Auxiliary1, Auxiliary2
+//   USE 1.0                 UseBuilding           DROP Guernsey       DiscardCard
+//   TILE 2.1                ChooseTile            STATION 3           UpgradeStation
+//
+// Cows are their color followed by their points unless they're worth none. Hazards are their area, points
+// and toll, G for green and K for black hands. Player buildings are numbered from 1 with their side, and
+// objectives from 1 in the starting objectives followed by the objectives deck, so both need the game data.
+// Printing one that isn't in the data fails rather than writing something that can't be parsed back.
+
+use crate::actions::Action;
+use crate::buildings::{Building, Hazard, HazardType, Tepee, Toll};
+use crate::data::GameData;
+use crate::deck::{Card, Cow, CowColor, Objective};
+use crate::error::GwtError;
+use crate::player::Employee;
+
+const COLORS: [CowColor; 9] = [CowColor::Jersey, CowColor::Dutch, CowColor::Angus, CowColor::Guernsey,
+    CowColor::Holstein, CowColor::Swiss, CowColor::Ayrshire, CowColor::Highland, CowColor::Longhorn];
+const AREAS: [HazardType; 3] = [HazardType::Flood, HazardType::Drought, HazardType::Rockfall];
+const EMPLOYEES: [Employee; 3] = [Employee::Cowboy, Employee::Craftsman, Employee::Engineer];
+const TEPEES: [Tepee; 2] = [Tepee::Green, Tepee::Blue];
+
+pub struct Notation<'a> {
+    data: &'a GameData,
+}
+
+fn named<T: Copy + std::fmt::Debug>(options: &[T], text: &str) -> Option<T> {
+    options.iter().find(|o| { format!("{:?}", o) == text }).copied()
+}
+
+fn invalid(text: &str) -> GwtError {
+    GwtError::InvalidNotation(text.to_string())
+}
+
+impl<'a> Notation<'a> {
+    pub fn new(data: &'a GameData) -> Notation<'a> {
+        Notation { data }
+    }
+
+    fn objectives(&self) -> impl Iterator<Item=&Objective> {
+        self.data.starting_objectives.iter().chain(self.data.objectives.iter())
+    }
+
+    pub fn cow(cow: &Cow) -> String {
+        if cow.points() == 0 { format!("{:?}", cow.color()) } else { format!("{:?}{}", cow.color(), cow.points()) }
+    }
+
+    fn objective(&self, objective: &Objective) -> Result<String, GwtError> {
+        match self.objectives().position(|o| { o == objective }) {
+            Some(i) => Ok(format!("O{}", i + 1)),
+            None => Err(GwtError::Unnamed(format!("{:?}", objective))),
+        }
+    }
+
+    pub fn card(&self, card: &Card) -> Result<String, GwtError> {
+        match card {
+            Card::CowCard(cow) => Ok(Notation::cow(cow)),
+            Card::ObjectiveCard(objective) => self.objective(objective),
+        }
+    }
+
+    pub fn hazard(hazard: &Hazard) -> String {
+        let toll = match hazard.toll() {
+            Toll::NoToll => "",
+            Toll::Green => "G",
+            Toll::Black => "K",
+            Toll::GreenBlack => "GK",
+        };
+        format!("{:?}{}{}", hazard.area(), hazard.points(), toll)
+    }
+
+    // A player building's number and side, like B5a
+    pub fn building(&self, building: &Building) -> Result<String, GwtError> {
+        let find = |buildings: &[Building]| {
+            buildings.iter().position(|b| { b.withOwner(building.owner()) == *building })
+        };
+        match (find(&self.data.player_buildings_a), find(&self.data.player_buildings_b)) {
+            (Some(i), _) => Ok(format!("B{}a", i + 1)),
+            (None, Some(i)) => Ok(format!("B{}b", i + 1)),
+            (None, None) => Err(GwtError::Unnamed(format!("{:?}", building))),
+        }
+    }
+
+    fn signed(n: i32) -> String {
+        if n < 0 { n.to_string() } else { format!("+{}", n) }
+    }
+
+    pub fn print(&self, action: &Action) -> Result<String, GwtError> {
+        let optional = |name: &str, value: Option<String>| {
+            match value {
+                Some(v) => format!("{} {}", name, v),
+                None => name.to_string(),
+            }
+        };
+        Ok(match action {
+            Action::PayCoins(n) => format!("PAY {}", n),
+            Action::BuyCow(cow) => format!("BUY {}", Notation::cow(cow)),
+            Action::Buy2Cows(a, b) => format!("BUY {}+{}", Notation::cow(a), Notation::cow(b)),
+            Action::SellCow(cow) => format!("SELL {}", Notation::cow(cow)),
+            Action::SellCowPair(a, b) => format!("SELL {}+{}", Notation::cow(a), Notation::cow(b)),
+            Action::SellHand => "SELLHAND".to_string(),
+            Action::FillHand => "FILL".to_string(),
+            Action::DrawCards(n) => optional("DRAW", n.map(|n| { n.to_string() })),
+            Action::DiscardCards(n) => optional("DISCARD", n.map(|n| { n.to_string() })),
+            Action::TrashCard(card) => optional("TRASH", card.map(|c| { self.card(&c) }).transpose()?),
+            Action::MoveCattleman(space) => optional("MOVE", space.map(|s| { s.to_string() })),
+            Action::MoveEngine(n) => format!("ENG{}", n.map(Notation::signed).unwrap_or_default()),
+            Action::MoveCertificate(n) => format!("CERT{}", n.map(Notation::signed).unwrap_or_default()),
+            Action::MaxCertificate => "CERTMAX".to_string(),
+            Action::TakeTepee(tepee) => optional("TEPEE", tepee.map(|t| { format!("{:?}", t) })),
+            Action::TakeHazard(hazard) => optional("HAZARD", hazard.map(|h| { Notation::hazard(&h) })),
+            Action::TakeObjective(objective) => optional("OBJ", objective.map(|o| { self.objective(&o) }).transpose()?),
+            Action::TakeStationmaster(emp) => optional("MASTER", emp.map(|e| { format!("{:?}", e) })),
+            Action::RemoveDisc(slot) => optional("REMOVE", slot.map(|s| { s.to_string() })),
+            Action::PlaceDisc(city) => optional("DELIVER", city.map(|c| { c.to_string() })),
+            Action::HireEmployee(emp) => optional("HIRE", emp.map(|e| { format!("{:?}", e) })),
+            Action::PlaceBuilding(space, building) => {
+                let building = building.map(|b| { self.building(&b) }).transpose()?.unwrap_or_else(|| { "BUILD".to_string() });
+                match space {
+                    Some(space) => format!("{}@{}", building, space),
+                    None => building,
+                }
+            }
+            Action::Auxiliary1(i) => format!("AUX1 {}", i),
+            Action::Auxiliary2(i) => format!("AUX2 {}", i),
+            Action::UseBuilding(or, xor) => format!("USE {}.{}", or, xor),
+            Action::DiscardCard(card) => format!("DROP {}", self.card(card)?),
+            Action::ChooseTile(col, row) => format!("TILE {}.{}", col, row),
+            Action::UpgradeStation(station) => format!("STATION {}", station),
+            Action::PlayObjective(objective) => format!("PLAY {}", self.objective(objective)?),
+            Action::Pass => "PASS".to_string(),
+        })
+    }
+
+    fn parseCow(text: &str) -> Option<Cow> {
+        let split = text.find(|c: char| { c.is_ascii_digit() }).unwrap_or(text.len());
+        let color = named(&COLORS, &text[..split])?;
+        let points = if split == text.len() { 0 } else { text[split..].parse().ok()? };
+        Some(Cow::new(color, points))
+    }
+
+    fn parseCowPair(text: &str) -> Option<(Cow, Cow)> {
+        let (a, b) = text.split_once('+')?;
+        Some((Notation::parseCow(a)?, Notation::parseCow(b)?))
+    }
+
+    fn parseObjective(&self, text: &str) -> Option<Objective> {
+        let index: usize = text.strip_prefix('O')?.parse().ok()?;
+        self.objectives().nth(index.checked_sub(1)?).copied()
+    }
+
+    fn parseCard(&self, text: &str) -> Option<Card> {
+        match self.parseObjective(text) {
+            Some(objective) => Some(Card::ObjectiveCard(objective)),
+            None => Notation::parseCow(text).map(Card::CowCard),
+        }
+    }
+
+    fn parseHazard(text: &str) -> Option<Hazard> {
+        let start = text.find(|c: char| { c.is_ascii_digit() })?;
+        let end = text[start..].find(|c: char| { !c.is_ascii_digit() }).map(|e| { start + e }).unwrap_or(text.len());
+        let toll = match &text[end..] {
+            "" => Toll::NoToll,
+            "G" => Toll::Green,
+            "K" => Toll::Black,
+            "GK" => Toll::GreenBlack,
+            _ => return None,
+        };
+        Some(Hazard::new(named(&AREAS, &text[..start])?, toll, text[start..end].parse().ok()?))
+    }
+
+    fn parseBuilding(&self, text: &str, player: u32) -> Option<Building> {
+        let number = text.strip_prefix('B')?;
+        let (number, buildings) = match (number.strip_suffix('a'), number.strip_suffix('b')) {
+            (Some(n), _) => (n, &self.data.player_buildings_a),
+            (_, Some(n)) => (n, &self.data.player_buildings_b),
+            _ => return None,
+        };
+        let index: usize = number.parse().ok()?;
+        buildings.get(index.checked_sub(1)?).map(|b| { b.withOwner(player) })
+    }
+
+    fn parsePair(text: &str) -> Option<(u32, u32)> {
+        let (a, b) = text.split_once('.')?;
+        Some((a.parse().ok()?, b.parse().ok()?))
+    }
+
+    fn parseSigned(text: &str) -> Option<Option<i32>> {
+        if text.is_empty() {
+            Some(None)
+        } else if text.starts_with('+') || text.starts_with('-') {
+            text.parse().ok().map(Some)
+        } else {
+            None
+        }
+    }
+
+    // player is the seat taking the action, which owns the building being placed
+    pub fn parse(&self, text: &str, player: u32) -> Result<Action, GwtError> {
+        let text = text.trim();
+        let (word, argument) = match text.split_once(' ') {
+            Some((word, argument)) => (word, Some(argument.trim())),
+            None => (text, None),
+        };
+        // Parses the argument when there is one, a missing argument is None
+        fn optional<T>(argument: Option<&str>, parse: impl Fn(&str) -> Option<T>) -> Option<Option<T>> {
+            match argument {
+                Some(a) => parse(a).map(Some),
+                None => Some(None),
+            }
+        }
+        let number = |a: &str| { a.parse::<u32>().ok() };
+        let required = argument.unwrap_or("");
+        let action = match word {
+            "PAY" => required.parse().ok().map(Action::PayCoins),
+            "BUY" if required.contains('+') => Notation::parseCowPair(required).map(|(a, b)| { Action::Buy2Cows(a, b) }),
+            "BUY" => Notation::parseCow(required).map(Action::BuyCow),
+            "SELL" if required.contains('+') => Notation::parseCowPair(required).map(|(a, b)| { Action::SellCowPair(a, b) }),
+            "SELL" => Notation::parseCow(required).map(Action::SellCow),
+            "SELLHAND" if argument.is_none() => Some(Action::SellHand),
+            "FILL" if argument.is_none() => Some(Action::FillHand),
+            "DRAW" => optional(argument, number).map(Action::DrawCards),
+            "DISCARD" => optional(argument, number).map(Action::DiscardCards),
+            "TRASH" => optional(argument, |a| { self.parseCard(a) }).map(Action::TrashCard),
+            "MOVE" => optional(argument, |a| { a.parse::<i32>().ok() }).map(Action::MoveCattleman),
+            "CERTMAX" if argument.is_none() => Some(Action::MaxCertificate),
+            "TEPEE" => optional(argument, |a| { named(&TEPEES, a) }).map(Action::TakeTepee),
+            "HAZARD" => optional(argument, Notation::parseHazard).map(Action::TakeHazard),
+            "OBJ" => optional(argument, |a| { self.parseObjective(a) }).map(Action::TakeObjective),
+            "MASTER" => optional(argument, |a| { named(&EMPLOYEES, a) }).map(Action::TakeStationmaster),
+            "REMOVE" => optional(argument, number).map(Action::RemoveDisc),
+            "DELIVER" => optional(argument, number).map(Action::PlaceDisc),
+            "HIRE" => optional(argument, |a| { named(&EMPLOYEES, a) }).map(Action::HireEmployee),
+            "AUX1" => number(required).map(Action::Auxiliary1),
+            "AUX2" => number(required).map(Action::Auxiliary2),
+            "USE" => Notation::parsePair(required).map(|(or, xor)| { Action::UseBuilding(or, xor) }),
+            "DROP" => self.parseCard(required).map(Action::DiscardCard),
+            "TILE" => Notation::parsePair(required).map(|(col, row)| { Action::ChooseTile(col, row) }),
+            "STATION" => number(required).map(Action::UpgradeStation),
+            "PLAY" => self.parseObjective(required).map(Action::PlayObjective),
+            "PASS" if argument.is_none() => Some(Action::Pass),
+            _ if argument.is_none() => self.parseWord(word, player),
+            _ => None,
+        };
+        action.ok_or_else(|| { invalid(text) })
+    }
+
+    // The notations written without a space: engine and certificate moves and building placements
+    fn parseWord(&self, word: &str, player: u32) -> Option<Action> {
+        if let Some(rest) = word.strip_prefix("ENG") {
+            return Notation::parseSigned(rest).map(Action::MoveEngine);
+        }
+        if let Some(rest) = word.strip_prefix("CERT") {
+            return Notation::parseSigned(rest).map(Action::MoveCertificate);
+        }
+        let (building, space) = match word.split_once('@') {
+            Some((building, space)) => (building, Some(space.parse::<u32>().ok()?)),
+            None => (word, None),
+        };
+        let building = if building == "BUILD" { None } else { Some(self.parseBuilding(building, player)?) };
+        Some(Action::PlaceBuilding(space, building))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Engine;
+    use crate::agent::{Agent, RandomAgent};
+    use crate::view::PlayerView;
+
+    use super::*;
+
+    #[test]
+    fn testExamples() {
+        let data = GameData::embedded().unwrap();
+        let notation = Notation::new(&data);
+        let building = data.player_buildings_a[4].withOwner(1);
+        assert_eq!(notation.print(&Action::PlaceBuilding(Some(14), Some(building))), Ok("B5a@14".to_string()));
+        assert_eq!(notation.parse("B5a@14", 1), Ok(Action::PlaceBuilding(Some(14), Some(building))));
+        let angus = Cow::new(CowColor::Angus, 0);
+        assert_eq!(notation.print(&Action::SellCowPair(angus, angus)), Ok("SELL Angus+Angus".to_string()));
+        assert_eq!(notation.parse("ENG+3", 0), Ok(Action::MoveEngine(Some(3))));
+        assert_eq!(notation.parse("ENG-2", 0), Ok(Action::MoveEngine(Some(-2))));
+        assert_eq!(notation.parse("DRAW", 0), Ok(Action::DrawCards(None)));
+        assert_eq!(notation.parse("HAZARD Flood2GK", 0), Ok(Action::TakeHazard(Some(Hazard::new(HazardType::Flood, Toll::GreenBlack, 2)))));
+        assert_eq!(notation.parse("BUY Purple3", 0), Err(GwtError::InvalidNotation("BUY Purple3".to_string())));
+        assert!(notation.parse("ENG3", 0).is_err());
+        assert!(notation.parse("B11a", 0).is_err());
+        // Objectives which aren't in the data have no number to print
+        let unknown = Objective::new(None, 9, 9, &[]).unwrap();
+        assert!(matches!(notation.print(&Action::PlayObjective(unknown)), Err(GwtError::Unnamed(_))));
+    }
+
+    #[test]
+    fn testRoundTrip() {
+        let data = GameData::embedded().unwrap();
+        let notation = Notation::new(&data);
+        let mut agent = RandomAgent::new(3);
+        for players in 2..=4 {
+            let mut engine = Engine::newGame(&data, players, 21).unwrap();
+            while !engine.isGameOver() {
+                let seat = engine.currentPlayer() as u32;
+                let legal = engine.legalActions();
+                for action in legal.iter() {
+                    let printed = notation.print(action).unwrap();
+                    assert_eq!(notation.parse(&printed, seat), Ok(*action), "{}", printed);
+                }
+                let action = agent.choose(&PlayerView::new(&engine, seat), &legal);
+                engine.applyAction(action).unwrap();
+            }
+        }
+    }
+}
@@ -2,19 +2,21 @@ use std::cmp::min;
 
 use serde::{Deserialize, Serialize};
 
-use crate::actions::ActionTag;
-use crate::buildings::{Building, Hazard};
-use crate::deck::{Card, Cow, Deck, Objective};
+use crate::actions::{Action, ActionTag, ActionValues};
+use crate::buildings::{Building, Hazard, Tepee};
+use crate::deck::{Card, Cow, Deck, DeckChange, Objective};
 use crate::deck::Card::CowCard;
+use crate::error::GwtError;
+use crate::tracks::StationMaster;
 
-#[derive(Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize, Debug)]
 pub enum Employee {
     Cowboy = 0,
     Craftsman = 1,
     Engineer = 2,
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize, Debug)]
 pub struct AuxiliaryAction {
     actions: [ActionTag; 2],
     unlocked: u32,
@@ -39,19 +41,118 @@ impl AuxiliaryAction {
         self.unlocked < 2
     }
 
-    pub fn unlock(&mut self) {
-        assert!(self.unlocked <= 1);
+    pub fn unlock(&mut self) -> Result<(), GwtError> {
+        if self.unlocked > 1 {
+            return Err(GwtError::AuxiliaryActionUnlocked);
+        }
         self.unlocked += 1;
+        Ok(())
     }
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize, Debug)]
 pub struct PlayerBoard {
-    aux_actions: [AuxiliaryAction; 5]
+    aux_actions: [AuxiliaryAction; 5],
+    // Discs removed from the hand size and movement rows
+    hand_upgrades: u32,
+    step_upgrades: u32,
+}
+
+impl PlayerBoard {
+    pub const NUM_AUX_ACTIONS: usize = 5;
+    // Disc slots after the auxiliary actions
+    pub const HAND_SLOT: u32 = 5;
+    pub const STEP_SLOT: u32 = 6;
+    const MAX_UPGRADES: u32 = 2;
+    // The dollars paid for the single and double version of each auxiliary action
+    const AUX_COSTS: [[u32; 2]; 5] = [[0, 0], [0, 0], [1, 2], [1, 2], [1, 2]];
+
+    pub fn new() -> PlayerBoard {
+        let exact = |v: i32| { ActionValues::Exact(v) };
+        PlayerBoard {
+            aux_actions: [
+                AuxiliaryAction::new(&ActionTag::TakeCoins(exact(1)), &ActionTag::TakeCoins(exact(2)), 1),
+                AuxiliaryAction::new(&ActionTag::DrawCards(exact(1)), &ActionTag::DrawCards(exact(2)), 1),
+                AuxiliaryAction::new(&ActionTag::MoveCertificate(exact(1)), &ActionTag::MoveCertificate(exact(2)), 0),
+                AuxiliaryAction::new(&ActionTag::MoveEngine(exact(1)), &ActionTag::MoveEngine(exact(2)), 0),
+                // The double version trashes twice
+                AuxiliaryAction::new(&ActionTag::TrashCard, &ActionTag::TrashCard, 0),
+            ],
+            hand_upgrades: 0,
+            step_upgrades: 0,
+        }
+    }
+
+    pub fn auxAction(&self, idx: usize) -> &AuxiliaryAction { &self.aux_actions[idx] }
+
+    pub fn auxCost(idx: usize, double: bool) -> u32 {
+        PlayerBoard::AUX_COSTS[idx][double as usize]
+    }
+
+    pub fn handSize(&self) -> usize { 4 + self.hand_upgrades as usize }
+
+    pub fn stepLimit(&self) -> u32 { 3 + self.step_upgrades }
+
+    // The slots a disc can be taken from, deliveries and stations needing an advanced disc
+    // can only take one from the lower rows of the board
+    pub fn discSlots(&self, advanced: bool) -> Vec<u32> {
+        let mut slots: Vec<u32> = self.aux_actions.iter()
+            .enumerate()
+            .filter(|(i, aux)| { aux.hasDisc() && (!advanced || *i >= 3) })
+            .map(|(i, _)| { i as u32 })
+            .collect();
+        if self.hand_upgrades < PlayerBoard::MAX_UPGRADES {
+            slots.push(PlayerBoard::HAND_SLOT);
+        }
+        if self.step_upgrades < PlayerBoard::MAX_UPGRADES {
+            slots.push(PlayerBoard::STEP_SLOT);
+        }
+        slots
+    }
+
+    pub fn removeDisc(&mut self, slot: u32) -> Result<(), GwtError> {
+        match slot {
+            PlayerBoard::HAND_SLOT if self.hand_upgrades < PlayerBoard::MAX_UPGRADES => self.hand_upgrades += 1,
+            PlayerBoard::STEP_SLOT if self.step_upgrades < PlayerBoard::MAX_UPGRADES => self.step_upgrades += 1,
+            s if (s as usize) < PlayerBoard::NUM_AUX_ACTIONS => self.aux_actions[s as usize].unlock()?,
+            _ => return Err(GwtError::NoDiscInSlot(slot)),
+        }
+        Ok(())
+    }
+}
+
+impl Default for PlayerBoard {
+    fn default() -> PlayerBoard {
+        PlayerBoard::new()
+    }
 }
 
 #[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
 pub struct Player {
+    id: u32,
+    dollars: u32,
+    cattleman_pos: usize,
+    hired: [u32; 3],
+    deck: Deck,
+    played_objectives: Vec<Objective>,
+    played_buildings: Vec<usize>,
+    // Contains the locations of played buildings on the track
+    buildings: Vec<Building>,
+    hazards: Vec<Hazard>,
+    green_tepees: u32,
+    blue_tepees: u32,
+    certificates: u32,
+    station_masters: Vec<StationMaster>,
+    job_market_token: bool,
+    board: PlayerBoard,
+    // The additions to the lists while the engine records an action
+    #[serde(skip)]
+    log: Option<Vec<PlayerChange>>,
+}
+
+// A player as saved before version 2, when cattleManPos, playedObjectives and playedBuildings became snake case
+#[derive(Deserialize)]
+pub(crate) struct PlayerV1 {
     id: u32,
     dollars: u32,
     cattleManPos: usize,
@@ -59,14 +160,65 @@ pub struct Player {
     deck: Deck,
     playedObjectives: Vec<Objective>,
     playedBuildings: Vec<usize>,
-    // Contains the locations of played buildings on the track
     buildings: Vec<Building>,
     hazards: Vec<Hazard>,
     green_tepees: u32,
     blue_tepees: u32,
+    certificates: u32,
+    station_masters: Vec<StationMaster>,
+    job_market_token: bool,
+    board: PlayerBoard,
+}
+
+impl From<PlayerV1> for Player {
+    fn from(player: PlayerV1) -> Player {
+        Player {
+            id: player.id,
+            dollars: player.dollars,
+            cattleman_pos: player.cattleManPos,
+            hired: player.hired,
+            deck: player.deck,
+            played_objectives: player.playedObjectives,
+            played_buildings: player.playedBuildings,
+            buildings: player.buildings,
+            hazards: player.hazards,
+            green_tepees: player.green_tepees,
+            blue_tepees: player.blue_tepees,
+            certificates: player.certificates,
+            station_masters: player.station_masters,
+            job_market_token: player.job_market_token,
+            board: player.board,
+            log: None,
+        }
+    }
+}
+
+// Everything about a player an action can change in place, saved whole for undo
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub(crate) struct Counters {
+    pub(crate) dollars: u32,
+    pub(crate) cattleman_pos: usize,
+    pub(crate) hired: [u32; 3],
+    pub(crate) green_tepees: u32,
+    pub(crate) blue_tepees: u32,
+    pub(crate) certificates: u32,
+    pub(crate) job_market_token: bool,
+    pub(crate) board: PlayerBoard,
+    pub(crate) hand_size: usize,
+}
+
+// A change to a player's lists or deck, with whatever the change lost
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub(crate) enum PlayerChange {
+    // The building was at this index of the unplaced ones
+    BuildingPlaced(usize, Building),
+    ObjectivePlayed,
+    HazardTaken,
+    StationMasterTaken,
+    Deck(DeckChange),
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize, Debug)]
 pub struct ObjectiveResources {
     pub buildings: u32,
     pub hazards: u32,
@@ -80,28 +232,263 @@ pub struct ObjectiveResources {
 }
 
 impl Player {
+    // Temporary certificates beyond this are lost
+    pub const MAX_CERTIFICATES: u32 = 4;
+    pub const MAX_EMPLOYEES: u32 = 6;
+
     pub fn new(turnPos: u32, startingDeck: Vec<Cow>, playerBuildings: Vec<Building>) -> Player {
         let startingDeck: Vec<Card> = startingDeck.iter()
             .map(|cow| { CowCard(*cow) })
             .collect();
-        return Player {
+        Player::withDeck(turnPos, Deck::new(4, startingDeck), playerBuildings)
+    }
+
+    pub fn newSeeded(turnPos: u32, startingDeck: Vec<Cow>, playerBuildings: Vec<Building>, seed: u64) -> Player {
+        let startingDeck: Vec<Card> = startingDeck.iter()
+            .map(|cow| { CowCard(*cow) })
+            .collect();
+        Player::withDeck(turnPos, Deck::new_seeded(4, startingDeck, seed), playerBuildings)
+    }
+
+    pub(crate) fn withDeck(turnPos: u32, deck: Deck, playerBuildings: Vec<Building>) -> Player {
+        let playerBuildings = playerBuildings.iter()
+            .map(|b| { b.withOwner(turnPos) })
+            .collect::<Vec<Building>>();
+        Player {
             id: turnPos,
             dollars: turnPos + 6,
-            cattleManPos: 0,
-            hired: [0; 3],
-            deck: Deck::new(4, startingDeck),
-            playedObjectives: Vec::<Objective>::with_capacity(4),
-            playedBuildings: Vec::with_capacity(playerBuildings.len()),
+            cattleman_pos: 0,
+            // Everyone starts with one of each employee
+            hired: [1; 3],
+            deck,
+            played_objectives: Vec::<Objective>::with_capacity(4),
+            played_buildings: Vec::with_capacity(playerBuildings.len()),
             buildings: playerBuildings,
             hazards: Vec::<Hazard>::new(),
             green_tepees: 0,
             blue_tepees: 0,
-        };
+            certificates: 0,
+            station_masters: Vec::<StationMaster>::new(),
+            job_market_token: false,
+            board: PlayerBoard::new(),
+            log: None,
+        }
     }
 
     pub fn id(&self) -> u32 { self.id }
 
+    pub fn dollars(&self) -> u32 { self.dollars }
+
+    pub fn deck(&self) -> &Deck { &self.deck }
+
+    pub fn deckMut(&mut self) -> &mut Deck { &mut self.deck }
+
+    pub fn board(&self) -> &PlayerBoard { &self.board }
+
+    pub fn cattleManPos(&self) -> usize { self.cattleman_pos }
+
+    // The buildings not yet placed on the track
+    pub fn buildings(&self) -> &Vec<Building> { &self.buildings }
+
+    pub fn playedBuildings(&self) -> &Vec<usize> { &self.played_buildings }
+
+    pub fn hazards(&self) -> &Vec<Hazard> { &self.hazards }
+
+    pub fn playedObjectives(&self) -> &Vec<Objective> { &self.played_objectives }
+
+    pub fn stationMasters(&self) -> &Vec<StationMaster> { &self.station_masters }
+
+    pub fn hasJobMarketToken(&self) -> bool { self.job_market_token }
+
+    pub fn hired(&self, emp: Employee) -> u32 {
+        self.hired[emp as usize]
+    }
+
+    pub fn totalHired(&self) -> u32 {
+        self.hired.iter().sum()
+    }
+
+    pub fn greenTepees(&self) -> u32 { self.green_tepees }
+
+    pub fn blueTepees(&self) -> u32 { self.blue_tepees }
+
     pub fn tepeePairs(&self) -> u32 {
         min(self.green_tepees, self.blue_tepees)
     }
+
+    // Temporary certificates plus the permanent ones from station masters
+    pub fn certificates(&self) -> u32 {
+        self.certificates + self.station_masters.iter()
+            .filter(|sm| { sm.permCertificate() })
+            .count() as u32
+    }
+
+    pub fn pay(&mut self, amount: u32) -> Result<(), GwtError> {
+        if amount > self.dollars {
+            return Err(GwtError::InsufficientFunds { player: self.id, required: amount, available: self.dollars });
+        }
+        self.dollars -= amount;
+        Ok(())
+    }
+
+    pub fn earn(&mut self, amount: u32) {
+        self.dollars += amount;
+    }
+
+    pub fn refillHand(&mut self) {
+        self.deck.refillHand();
+    }
+
+    pub fn moveCattleman(&mut self, pos: usize) {
+        self.cattleman_pos = pos;
+    }
+
+    pub fn hire(&mut self, emp: Employee) {
+        self.hired[emp as usize] += 1;
+    }
+
+    // Removes the building from the unplaced ones and records where it was placed
+    pub fn placeBuilding(&mut self, building: Building, location: usize) -> Result<(), GwtError> {
+        match self.buildings.iter().position(|b| { *b == building }) {
+            Some(idx) => {
+                self.buildings.remove(idx);
+                self.played_buildings.push(location);
+                self.note(PlayerChange::BuildingPlaced(idx, building));
+                Ok(())
+            }
+            None => Err(GwtError::illegal(Action::PlaceBuilding(Some(location as u32), Some(building)))),
+        }
+    }
+
+    pub fn gainCertificates(&mut self, amount: u32) {
+        self.certificates = min(self.certificates + amount, Player::MAX_CERTIFICATES);
+    }
+
+    // Temporary certificates are spent when the hand is sold
+    pub fn spendCertificates(&mut self) {
+        self.certificates = 0;
+    }
+
+    pub fn removeDisc(&mut self, slot: u32) -> Result<(), GwtError> {
+        self.board.removeDisc(slot)?;
+        self.deck.setHandSize(self.board.handSize());
+        Ok(())
+    }
+
+    pub fn playObjective(&mut self, objective: Objective) -> Result<(), GwtError> {
+        self.deck.trashCard(Card::ObjectiveCard(objective)).map_err(|e| { GwtError::deck(self.id, e) })?;
+        self.played_objectives.push(objective);
+        self.note(PlayerChange::ObjectivePlayed);
+        Ok(())
+    }
+
+    // Starting objectives are played from the start. The data gives them no points for failing, which validation checks
+    pub fn takeStartingObjective(&mut self, objective: Objective) {
+        self.played_objectives.push(objective);
+        self.note(PlayerChange::ObjectivePlayed);
+    }
+
+    pub fn takeHazard(&mut self, hazard: Hazard) {
+        self.hazards.push(hazard);
+        self.note(PlayerChange::HazardTaken);
+    }
+
+    pub fn takeTepee(&mut self, tepee: Tepee) {
+        match tepee {
+            Tepee::Green => self.green_tepees += 1,
+            Tepee::Blue => self.blue_tepees += 1,
+        }
+    }
+
+    pub fn takeStationMaster(&mut self, station_master: StationMaster) {
+        self.station_masters.push(station_master);
+        self.note(PlayerChange::StationMasterTaken);
+    }
+
+    pub fn takeJobMarketToken(&mut self) {
+        self.job_market_token = true;
+    }
+
+    pub(crate) fn counters(&self) -> Counters {
+        Counters {
+            dollars: self.dollars,
+            cattleman_pos: self.cattleman_pos,
+            hired: self.hired,
+            green_tepees: self.green_tepees,
+            blue_tepees: self.blue_tepees,
+            certificates: self.certificates,
+            job_market_token: self.job_market_token,
+            board: self.board,
+            hand_size: self.deck.handSize(),
+        }
+    }
+
+    pub(crate) fn setCounters(&mut self, counters: Counters) {
+        self.dollars = counters.dollars;
+        self.cattleman_pos = counters.cattleman_pos;
+        self.hired = counters.hired;
+        self.green_tepees = counters.green_tepees;
+        self.blue_tepees = counters.blue_tepees;
+        self.certificates = counters.certificates;
+        self.job_market_token = counters.job_market_token;
+        self.board = counters.board;
+        self.deck.setHandSize(counters.hand_size);
+    }
+
+    fn note(&mut self, change: PlayerChange) {
+        if let Some(log) = self.log.as_mut() {
+            log.push(change);
+        }
+    }
+
+    // Logs the changes to the lists and the deck from now on
+    pub(crate) fn startLog(&mut self) {
+        self.log.get_or_insert_with(Vec::new);
+        self.deck.startLog();
+    }
+
+    // Stops logging, returning the changes logged. The deck's moves come last but don't depend on the lists
+    pub(crate) fn takeLog(&mut self) -> Vec<PlayerChange> {
+        let mut log = self.log.take().unwrap_or_default();
+        log.extend(self.deck.takeLog().into_iter().map(PlayerChange::Deck));
+        log
+    }
+
+    // Takes back a logged change, the changes after it must have been taken back already
+    pub(crate) fn revert(&mut self, change: PlayerChange) {
+        match change {
+            PlayerChange::BuildingPlaced(idx, building) => {
+                self.played_buildings.pop();
+                self.buildings.insert(idx, building);
+            }
+            PlayerChange::ObjectivePlayed => {
+                self.played_objectives.pop();
+            }
+            PlayerChange::HazardTaken => {
+                self.hazards.pop();
+            }
+            PlayerChange::StationMasterTaken => {
+                self.station_masters.pop();
+            }
+            PlayerChange::Deck(change) => self.deck.revert(change),
+        }
+    }
+
+    pub fn objectiveResources(&self, buildings: u32, san_fran: u32, station_discs: u32) -> ObjectiveResources {
+        let cows = self.deck.cowCards();
+        let cows_of_value = |value: u32| -> u32 {
+            cows.iter().filter(|c| { c.value() == value }).count() as u32
+        };
+        ObjectiveResources {
+            buildings,
+            hazards: self.hazards.len() as u32,
+            san_fran,
+            green_tepees: self.green_tepees,
+            blue_tepees: self.blue_tepees,
+            station_discs,
+            ryb_cows: cows_of_value(3),
+            brown_cows: cows_of_value(4),
+            purple_cows: cows_of_value(5),
+        }
+    }
 }
@@ -0,0 +1,1226 @@
+// A compact binary encoding of the game position, and its Zobrist hash. The encoding leaves out only the orders
+// nobody at the table can see: hands, draw piles, discard piles, the cow deck, the objectives deck and the foresight
+// piles are counts of each card or tile. Buildings, objectives and station masters are their index in the game data
+// and the track's layout isn't encoded at all, so decoding needs the data the engine was set up from. The decoded
+// engine holds the counted piles in the order of the encoding and its decks have new rngs, so it plays the same from
+// here only once determinized.
+//
+// The encoding is a bit stream, lowest bit first: the number of players and the track's length, then every track
+// space, the station track, each player, the cow market, the foresight tiles, the job market, the objectives and last
+// the turn, which scoring a finished game needs the rest for. Small fields take a fixed number of bits, amounts and
+// counts a nibble varint (3 value bits and a continuation bit), and signed amounts are zigzagged into one.
+//
+// The Zobrist hash is the XOR of a key for every feature of the position: a player's dollars, how many of a card are
+// in one of their piles, what's on a track space and so on. A feature's key is splitmix64 of the part and the feature,
+// standing in for a table of random keys too big to precompute. Once kept, the engine holds the XOR of every part's
+// keys except those the current action has touched, so applying an action only rekeys the parts it changed. Some
+// features are keyed through their derived Hash, so a hash can only be compared with others from the same build.
+
+use std::hash::{Hash, Hasher};
+
+use crate::{Engine, GameState};
+use crate::actions::{ActionTag, ActionValues, DiscardCardOpts};
+use crate::buildings::{Building, Hazard, HazardType, Tepee, Toll};
+use crate::data::GameData;
+use crate::deck::{Card, Cow, CowColor, CowMarket, Deck, Objective};
+use crate::error::GwtError;
+use crate::history::Part;
+use crate::logical::XOr;
+use crate::player::{Counters, Employee, Player, PlayerBoard};
+use crate::rules::{KansasCityStep, Pending, Phase};
+use crate::scoring::ScoreSheet;
+use crate::tiles::{Foresight, JobMarket, Tile};
+use crate::tracks::{EngineSpace, Space, SpaceOccupant, StationMaster, StationTrack};
+
+// The hash the engine keeps, equal for every engine since it only caches what the rest of the engine already says
+#[derive(Clone, Default, Debug)]
+pub struct HashCache {
+    // The XOR of every part's keys except the dirty ones, None while not kept
+    hash: Option<u64>,
+    dirty: Vec<Part>,
+}
+
+impl PartialEq for HashCache {
+    fn eq(&self, _other: &HashCache) -> bool { true }
+}
+
+impl Eq for HashCache {}
+
+const EMPLOYEES: [Employee; 3] = [Employee::Cowboy, Employee::Craftsman, Employee::Engineer];
+const COLORS: [CowColor; 9] = [CowColor::Jersey, CowColor::Dutch, CowColor::Angus, CowColor::Guernsey, CowColor::Holstein,
+    CowColor::Swiss, CowColor::Ayrshire, CowColor::Highland, CowColor::Longhorn];
+const AREAS: [HazardType; 3] = [HazardType::Flood, HazardType::Drought, HazardType::Rockfall];
+const TOLLS: [Toll; 4] = [Toll::NoToll, Toll::Green, Toll::Black, Toll::GreenBlack];
+const TEPEES: [Tepee; 2] = [Tepee::Green, Tepee::Blue];
+const OPTIONS: [DiscardCardOpts; 3] = [DiscardCardOpts::AnyCow, DiscardCardOpts::PairCow, DiscardCardOpts::Objective];
+
+fn invalid(message: &str) -> GwtError {
+    GwtError::InvalidEncoding(message.to_string())
+}
+
+fn pick<T: Copy>(values: &[T], index: u64, what: &str) -> Result<T, GwtError> {
+    values.get(index as usize).copied().ok_or_else(|| { invalid(&format!("there's no {} {}", what, index)) })
+}
+
+struct BitWriter {
+    bytes: Vec<u8>,
+    bits: usize,
+}
+
+impl BitWriter {
+    fn fixed(&mut self, value: u64, bits: u32) {
+        for i in 0..bits {
+            if self.bits.is_multiple_of(8) {
+                self.bytes.push(0);
+            }
+            let last = self.bytes.len() - 1;
+            self.bytes[last] |= (((value >> i) & 1) as u8) << (self.bits % 8);
+            self.bits += 1;
+        }
+    }
+
+    fn flag(&mut self, value: bool) {
+        self.fixed(value as u64, 1);
+    }
+
+    fn amount(&mut self, mut value: u64) {
+        loop {
+            let more = value >= 8;
+            self.fixed((value & 7) | if more { 8 } else { 0 }, 4);
+            value >>= 3;
+            if !more {
+                break;
+            }
+        }
+    }
+
+    fn signed(&mut self, value: i32) {
+        self.amount(((value << 1) ^ (value >> 31)) as u32 as u64);
+    }
+}
+
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    bits: usize,
+}
+
+impl BitReader<'_> {
+    fn fixed(&mut self, bits: u32) -> Result<u64, GwtError> {
+        let mut value = 0;
+        for i in 0..bits {
+            let byte = self.bytes.get(self.bits / 8).ok_or_else(|| { invalid("it ends early") })?;
+            value |= (((byte >> (self.bits % 8)) & 1) as u64) << i;
+            self.bits += 1;
+        }
+        Ok(value)
+    }
+
+    fn flag(&mut self) -> Result<bool, GwtError> {
+        Ok(self.fixed(1)? == 1)
+    }
+
+    fn amount(&mut self) -> Result<u64, GwtError> {
+        let mut value = 0;
+        let mut shift = 0;
+        loop {
+            let nibble = self.fixed(4)?;
+            if shift > 60 {
+                return Err(invalid("an amount is too long"));
+            }
+            value |= (nibble & 7) << shift;
+            shift += 3;
+            if nibble & 8 == 0 {
+                return Ok(value);
+            }
+        }
+    }
+
+    // Counts of things which are each at least a bit long can't be more than the bits left
+    fn count(&mut self) -> Result<usize, GwtError> {
+        let count = self.amount()?;
+        if count > (self.bytes.len() * 8 - self.bits) as u64 {
+            return Err(invalid("a count is larger than what's left"));
+        }
+        Ok(count as usize)
+    }
+
+    fn signed(&mut self) -> Result<i32, GwtError> {
+        let zigzag = self.amount()?;
+        if zigzag > u32::MAX as u64 {
+            return Err(invalid("a signed amount is too long"));
+        }
+        Ok((zigzag >> 1) as i32 ^ -((zigzag & 1) as i32))
+    }
+}
+
+// Buildings are found in the data by their side, neutral, A or B, and their index there, then given their owner
+fn sides(data: &GameData) -> [&Vec<Building>; 3] {
+    [&data.neutral_buildings, &data.player_buildings_a, &data.player_buildings_b]
+}
+
+fn writeBuilding(w: &mut BitWriter, data: &GameData, building: &Building) -> Result<(), GwtError> {
+    for (side, buildings) in sides(data).iter().enumerate() {
+        if let Some(i) = buildings.iter().position(|b| { b.withOwner(building.owner()) == *building }) {
+            w.fixed(side as u64, 2);
+            w.amount(i as u64);
+            w.fixed(building.owner() as u64, 2);
+            return Ok(());
+        }
+    }
+    Err(GwtError::Unnamed(format!("{:?}", building)))
+}
+
+fn readBuilding(r: &mut BitReader, data: &GameData) -> Result<Building, GwtError> {
+    let buildings = pick(&sides(data), r.fixed(2)?, "building side")?;
+    let building = pick(buildings, r.amount()?, "building")?;
+    Ok(building.withOwner(r.fixed(2)? as u32))
+}
+
+// Starting objectives are numbered after the objectives deck
+fn objectives(data: &GameData) -> Vec<Objective> {
+    data.objectives.iter().chain(data.starting_objectives.iter()).copied().collect()
+}
+
+fn objectiveIndex(data: &GameData, objective: &Objective) -> Result<u64, GwtError> {
+    objectives(data).iter().position(|o| { o == objective })
+        .map(|i| { i as u64 })
+        .ok_or_else(|| { GwtError::Unnamed(format!("{:?}", objective)) })
+}
+
+fn readObjective(r: &mut BitReader, data: &GameData) -> Result<Objective, GwtError> {
+    pick(&objectives(data), r.amount()?, "objective")
+}
+
+fn writeStationMaster(w: &mut BitWriter, data: &GameData, station_master: &StationMaster) -> Result<(), GwtError> {
+    let i = data.station_masters.iter().position(|sm| { sm == station_master })
+        .ok_or_else(|| { GwtError::Unnamed(format!("{:?}", station_master)) })?;
+    w.amount(i as u64);
+    Ok(())
+}
+
+fn readStationMaster(r: &mut BitReader, data: &GameData) -> Result<StationMaster, GwtError> {
+    pick(&data.station_masters, r.amount()?, "station master")
+}
+
+fn writeHazard(w: &mut BitWriter, hazard: &Hazard) {
+    w.fixed(hazard.area() as u64, 2);
+    w.fixed(hazard.toll() as u64, 2);
+    w.amount(hazard.points() as u64);
+}
+
+fn readHazard(r: &mut BitReader) -> Result<Hazard, GwtError> {
+    let area = pick(&AREAS, r.fixed(2)?, "hazard area")?;
+    let toll = pick(&TOLLS, r.fixed(2)?, "toll")?;
+    Ok(Hazard::new(area, toll, r.amount()? as u32))
+}
+
+fn writeCow(w: &mut BitWriter, cow: &Cow) {
+    w.fixed(cow.color() as u64, 4);
+    w.amount(cow.points() as u64);
+}
+
+fn readCow(r: &mut BitReader) -> Result<Cow, GwtError> {
+    let color = pick(&COLORS, r.fixed(4)?, "cow color")?;
+    Ok(Cow::new(color, r.amount()? as u32))
+}
+
+fn writeCard(w: &mut BitWriter, data: &GameData, card: &Card) -> Result<(), GwtError> {
+    match card {
+        Card::CowCard(cow) => {
+            w.flag(false);
+            writeCow(w, cow);
+        }
+        Card::ObjectiveCard(objective) => {
+            w.flag(true);
+            w.amount(objectiveIndex(data, objective)?);
+        }
+    }
+    Ok(())
+}
+
+fn readCard(r: &mut BitReader, data: &GameData) -> Result<Card, GwtError> {
+    if r.flag()? { Ok(Card::ObjectiveCard(readObjective(r, data)?)) } else { Ok(Card::CowCard(readCow(r)?)) }
+}
+
+fn writeTile(w: &mut BitWriter, tile: &Tile) {
+    match tile {
+        Tile::EmployeeTile(emp) => {
+            w.fixed(0, 2);
+            w.fixed(*emp as u64, 2);
+        }
+        Tile::HazardTile(hazard) => {
+            w.fixed(1, 2);
+            writeHazard(w, hazard);
+        }
+        Tile::TepeeTile(tepee) => {
+            w.fixed(2, 2);
+            w.fixed(*tepee as u64, 1);
+        }
+        Tile::BlankTile => w.fixed(3, 2),
+    }
+}
+
+fn readTile(r: &mut BitReader) -> Result<Tile, GwtError> {
+    Ok(match r.fixed(2)? {
+        0 => Tile::EmployeeTile(pick(&EMPLOYEES, r.fixed(2)?, "employee")?),
+        1 => Tile::HazardTile(readHazard(r)?),
+        2 => Tile::TepeeTile(pick(&TEPEES, r.fixed(1)?, "tepee")?),
+        _ => Tile::BlankTile,
+    })
+}
+
+// Each different item once with how many there are, ordered by key so the items' own order doesn't show
+fn counts<T: Copy + Eq>(items: &[T], key: impl Fn(&T) -> u64) -> Vec<(T, u64)> {
+    let mut counts = Vec::<(T, u64)>::new();
+    for item in items.iter() {
+        match counts.iter_mut().find(|(i, _)| { i == item }) {
+            Some((_, n)) => *n += 1,
+            None => counts.push((*item, 1)),
+        }
+    }
+    counts.sort_by_key(|(item, _)| { key(item) });
+    counts
+}
+
+fn writeCounts<T: Copy + Eq>(w: &mut BitWriter, items: &[T], key: impl Fn(&T) -> u64,
+                             mut write: impl FnMut(&mut BitWriter, &T) -> Result<(), GwtError>) -> Result<(), GwtError> {
+    let counts = counts(items, key);
+    w.amount(counts.len() as u64);
+    for (item, n) in counts.iter() {
+        write(w, item)?;
+        w.amount(*n);
+    }
+    Ok(())
+}
+
+fn readCounts<T: Copy>(r: &mut BitReader, mut read: impl FnMut(&mut BitReader) -> Result<T, GwtError>) -> Result<Vec<T>, GwtError> {
+    let mut items = vec![];
+    for _ in 0..r.count()? {
+        let item = read(r)?;
+        let n = r.count()?;
+        items.extend(std::iter::repeat_n(item, n));
+    }
+    Ok(items)
+}
+
+fn cowKey(cow: &Cow) -> u64 {
+    (cow.color() as u64) << 32 | cow.points() as u64
+}
+
+fn writeCards(w: &mut BitWriter, data: &GameData, cards: &[Card]) -> Result<(), GwtError> {
+    let key = |card: &Card| {
+        match card {
+            Card::CowCard(cow) => cowKey(cow),
+            Card::ObjectiveCard(objective) => 1 << 63 | objectiveIndex(data, objective).unwrap_or(u32::MAX as u64),
+        }
+    };
+    writeCounts(w, cards, key, |w, card| { writeCard(w, data, card) })
+}
+
+fn readCards(r: &mut BitReader, data: &GameData) -> Result<Vec<Card>, GwtError> {
+    readCounts(r, |r| { readCard(r, data) })
+}
+
+fn objectiveCards(objectives: &[Objective]) -> Vec<Card> {
+    objectives.iter().map(|o| { Card::ObjectiveCard(*o) }).collect()
+}
+
+fn writeValues(w: &mut BitWriter, values: &ActionValues) {
+    let (code, n) = match *values {
+        ActionValues::Exact(n) => (0, n),
+        ActionValues::EmployeeMult(emp, n) => {
+            w.fixed(1, 4);
+            w.fixed(emp as u64, 2);
+            w.signed(n);
+            return;
+        }
+        ActionValues::AllEmployeesMult(n) => (2, n),
+        ActionValues::ForestMult(n) => (3, n),
+        ActionValues::TepeePairMult(n) => (4, n),
+        ActionValues::HazardPairMult(n) => (5, n),
+        ActionValues::CertificatePairMult(n) => (6, n),
+        ActionValues::ObjectivePairMult(n) => (7, n),
+        ActionValues::AtMost(n) => (8, n),
+        ActionValues::Max => return w.fixed(9, 4),
+    };
+    w.fixed(code, 4);
+    w.signed(n);
+}
+
+fn readValues(r: &mut BitReader) -> Result<ActionValues, GwtError> {
+    let code = r.fixed(4)?;
+    Ok(match code {
+        1 => {
+            let emp = pick(&EMPLOYEES, r.fixed(2)?, "employee")?;
+            ActionValues::EmployeeMult(emp, r.signed()?)
+        }
+        9 => ActionValues::Max,
+        _ => {
+            let n = r.signed()?;
+            match code {
+                0 => ActionValues::Exact(n),
+                2 => ActionValues::AllEmployeesMult(n),
+                3 => ActionValues::ForestMult(n),
+                4 => ActionValues::TepeePairMult(n),
+                5 => ActionValues::HazardPairMult(n),
+                6 => ActionValues::CertificatePairMult(n),
+                7 => ActionValues::ObjectivePairMult(n),
+                8 => ActionValues::AtMost(n),
+                _ => return Err(invalid(&format!("there are no action values {}", code))),
+            }
+        }
+    })
+}
+
+fn writeTag(w: &mut BitWriter, tag: &ActionTag) {
+    let (code, values) = match *tag {
+        ActionTag::TakeCoins(v) => (0, Some(v)),
+        ActionTag::BuyCows => (1, None),
+        ActionTag::DiscardCard(opts) => {
+            w.fixed(2, 5);
+            match opts {
+                DiscardCardOpts::Color(color) => {
+                    w.fixed(3, 2);
+                    w.fixed(color as u64, 4);
+                }
+                DiscardCardOpts::AnyCow => w.fixed(0, 2),
+                DiscardCardOpts::PairCow => w.fixed(1, 2),
+                DiscardCardOpts::Objective => w.fixed(2, 2),
+            }
+            return;
+        }
+        ActionTag::DrawCards(v) => (3, Some(v)),
+        ActionTag::TrashCard => (4, None),
+        ActionTag::MoveCattleman(v) => (5, Some(v)),
+        ActionTag::TeleportCattleman(v) => (6, Some(v)),
+        ActionTag::MoveEngine(v) => (7, Some(v)),
+        ActionTag::MoveCertificate(v) => (8, Some(v)),
+        ActionTag::TakeTepee => (9, None),
+        ActionTag::TakeHazard => (10, None),
+        ActionTag::TakeObjective => (11, None),
+        ActionTag::PlaceDisc(v) => (12, Some(v)),
+        ActionTag::HireEmployee(v) => (13, Some(v)),
+        ActionTag::PlaceBuilding(v) => (14, Some(v)),
+        ActionTag::CityDiscMoveTrain => (15, None),
+        ActionTag::StationDiscBehindTrain => (16, None),
+        ActionTag::AdjacentBuilding => (17, None),
+        ActionTag::Auxiliary => (18, None),
+        ActionTag::DoubleAuxiliary => (19, None),
+    };
+    w.fixed(code, 5);
+    if let Some(values) = values {
+        writeValues(w, &values);
+    }
+}
+
+fn readTag(r: &mut BitReader) -> Result<ActionTag, GwtError> {
+    let code = r.fixed(5)?;
+    Ok(match code {
+        1 => ActionTag::BuyCows,
+        2 => match r.fixed(2)? {
+            3 => ActionTag::DiscardCard(DiscardCardOpts::Color(pick(&COLORS, r.fixed(4)?, "cow color")?)),
+            opts => ActionTag::DiscardCard(OPTIONS[opts as usize]),
+        },
+        4 => ActionTag::TrashCard,
+        9 => ActionTag::TakeTepee,
+        10 => ActionTag::TakeHazard,
+        11 => ActionTag::TakeObjective,
+        15 => ActionTag::CityDiscMoveTrain,
+        16 => ActionTag::StationDiscBehindTrain,
+        17 => ActionTag::AdjacentBuilding,
+        18 => ActionTag::Auxiliary,
+        19 => ActionTag::DoubleAuxiliary,
+        _ => {
+            let values = readValues(r)?;
+            match code {
+                0 => ActionTag::TakeCoins(values),
+                3 => ActionTag::DrawCards(values),
+                5 => ActionTag::MoveCattleman(values),
+                6 => ActionTag::TeleportCattleman(values),
+                7 => ActionTag::MoveEngine(values),
+                8 => ActionTag::MoveCertificate(values),
+                12 => ActionTag::PlaceDisc(values),
+                13 => ActionTag::HireEmployee(values),
+                14 => ActionTag::PlaceBuilding(values),
+                _ => return Err(invalid(&format!("there's no action tag {}", code))),
+            }
+        }
+    })
+}
+
+fn writePending(w: &mut BitWriter, pending: &Pending) {
+    match pending {
+        Pending::Tag(tag) => {
+            w.fixed(0, 3);
+            writeTag(w, tag);
+        }
+        Pending::Either(choices) => {
+            w.fixed(1, 3);
+            for choice in choices.items.iter() {
+                w.flag(choice.is_some());
+                if let Some(tag) = choice {
+                    writeTag(w, tag);
+                }
+            }
+        }
+        Pending::BuyCows(cowboys) => {
+            w.fixed(2, 3);
+            w.amount(*cowboys as u64);
+        }
+        Pending::Discard(cards) => {
+            w.fixed(3, 3);
+            w.amount(*cards as u64);
+        }
+        Pending::RemoveDisc { advanced } => {
+            w.fixed(4, 3);
+            w.flag(*advanced);
+        }
+        Pending::UpgradeStation { from, to } => {
+            w.fixed(5, 3);
+            w.amount(*from as u64);
+            w.amount(*to as u64);
+        }
+    }
+}
+
+fn readPending(r: &mut BitReader) -> Result<Pending, GwtError> {
+    Ok(match r.fixed(3)? {
+        0 => Pending::Tag(readTag(r)?),
+        1 => {
+            let mut choices = XOr::empty();
+            for choice in choices.items.iter_mut() {
+                if r.flag()? {
+                    *choice = Some(readTag(r)?);
+                }
+            }
+            Pending::Either(choices)
+        }
+        2 => Pending::BuyCows(r.amount()? as u32),
+        3 => Pending::Discard(r.amount()? as u32),
+        4 => Pending::RemoveDisc { advanced: r.flag()? },
+        5 => Pending::UpgradeStation { from: r.amount()? as u32, to: r.amount()? as u32 },
+        code => return Err(invalid(&format!("there's no pending action {}", code))),
+    })
+}
+
+fn writeTurn(w: &mut BitWriter, engine: &Engine) {
+    w.fixed(engine.starting_player as u64, 2);
+    w.fixed(engine.current_player as u64, 2);
+    match &engine.state {
+        GameState::Playing => w.fixed(0, 2),
+        GameState::FinalRound { triggered_by } => {
+            w.fixed(1, 2);
+            w.fixed(*triggered_by as u64, 2);
+        }
+        GameState::GameOver { .. } => w.fixed(2, 2),
+    }
+    match engine.phase {
+        Phase::Move => w.fixed(0, 2),
+        Phase::Location { used, auxiliary } => {
+            w.fixed(1, 2);
+            for flag in used.iter().chain(std::iter::once(&auxiliary)) {
+                w.flag(*flag);
+            }
+        }
+        Phase::KansasCity(step) => {
+            w.fixed(2, 2);
+            match step {
+                KansasCityStep::Foresight(column) => {
+                    w.fixed(0, 2);
+                    w.fixed(column as u64, 2);
+                }
+                KansasCityStep::Income => w.fixed(1, 2),
+                KansasCityStep::Delivery { value } => {
+                    w.fixed(2, 2);
+                    w.amount(value as u64);
+                }
+                KansasCityStep::Done => w.fixed(3, 2),
+            }
+        }
+    }
+    w.amount(engine.pending.len() as u64);
+    for group in engine.pending.iter() {
+        w.amount(group.len() as u64);
+        for pending in group.iter() {
+            writePending(w, pending);
+        }
+    }
+}
+
+fn readTurn(r: &mut BitReader, engine: &mut Engine) -> Result<(), GwtError> {
+    let seat = |r: &mut BitReader, engine: &Engine| -> Result<usize, GwtError> {
+        let seat = r.fixed(2)? as usize;
+        if seat >= engine.players.len() { Err(invalid(&format!("there's no seat {}", seat))) } else { Ok(seat) }
+    };
+    engine.starting_player = seat(r, engine)?;
+    engine.current_player = seat(r, engine)?;
+    engine.state = match r.fixed(2)? {
+        0 => GameState::Playing,
+        1 => GameState::FinalRound { triggered_by: seat(r, engine)? },
+        2 => {
+            let scores = engine.scoreSheets();
+            let winners = ScoreSheet::winners(&scores);
+            GameState::GameOver { scores, winners }
+        }
+        _ => return Err(invalid("there's no game state 3")),
+    };
+    engine.phase = match r.fixed(2)? {
+        0 => Phase::Move,
+        1 => {
+            let used = [r.flag()?, r.flag()?, r.flag()?];
+            Phase::Location { used, auxiliary: r.flag()? }
+        }
+        2 => Phase::KansasCity(match r.fixed(2)? {
+            0 => KansasCityStep::Foresight(r.fixed(2)? as u32),
+            1 => KansasCityStep::Income,
+            2 => KansasCityStep::Delivery { value: r.amount()? as u32 },
+            _ => KansasCityStep::Done,
+        }),
+        _ => return Err(invalid("there's no phase 3")),
+    };
+    engine.pending = vec![];
+    for _ in 0..r.count()? {
+        let mut group = vec![];
+        for _ in 0..r.count()? {
+            group.push(readPending(r)?);
+        }
+        engine.pending.push(group);
+    }
+    Ok(())
+}
+
+// Only what's on the space, the rest of it is in the data
+fn writeSpace(w: &mut BitWriter, data: &GameData, space: &Space) -> Result<(), GwtError> {
+    match space.occupant() {
+        SpaceOccupant::Building { building, .. } => {
+            w.flag(building.is_some());
+            if let Some(building) = building {
+                writeBuilding(w, data, building)?;
+            }
+        }
+        SpaceOccupant::Hazard(_, hazard) => {
+            w.flag(hazard.is_some());
+            if let Some(hazard) = hazard {
+                writeHazard(w, hazard);
+            }
+        }
+        SpaceOccupant::Tepee(_, tepee) => {
+            w.flag(tepee.is_some());
+            if let Some(tepee) = tepee {
+                w.fixed(*tepee as u64, 1);
+            }
+        }
+        SpaceOccupant::KansasCity | SpaceOccupant::Start => {}
+    }
+    Ok(())
+}
+
+fn readSpace(r: &mut BitReader, data: &GameData, mut space: Space) -> Result<Space, GwtError> {
+    let placed = match space.occupant() {
+        SpaceOccupant::Building { .. } => !r.flag()? || space.placeBuilding(readBuilding(r, data)?),
+        SpaceOccupant::Hazard(..) => !r.flag()? || space.placeHazard(readHazard(r)?),
+        SpaceOccupant::Tepee(..) => !r.flag()? || space.placeTepee(pick(&TEPEES, r.fixed(1)?, "tepee")?),
+        SpaceOccupant::KansasCity | SpaceOccupant::Start => true,
+    };
+    if placed { Ok(space) } else { Err(invalid("a hazard is on a space of another area")) }
+}
+
+fn writeStations(w: &mut BitWriter, data: &GameData, engine: &Engine) -> Result<(), GwtError> {
+    let stations = &engine.station_track;
+    let players = engine.players.len() as u32;
+    for player in 0..players {
+        let (turnout, location) = match stations.engineSpace(player) {
+            EngineSpace::TurnoutTrack(location) => (true, location),
+            EngineSpace::MainTrack(location) => (false, location),
+        };
+        w.flag(turnout);
+        w.amount(location as u64);
+    }
+    for i in 0..stations.numStations() {
+        let station = stations.station(i);
+        for player in 0..players {
+            w.flag(station.hasDisc(player));
+        }
+        w.flag(station.stationMaster().is_some());
+        if let Some(station_master) = station.stationMaster() {
+            writeStationMaster(w, data, &station_master)?;
+        }
+    }
+    for city in stations.cities().cities().iter() {
+        for player in 0..players {
+            w.amount(city.discs(player) as u64);
+        }
+    }
+    Ok(())
+}
+
+fn readStations(r: &mut BitReader, data: &GameData, players: u32, stations: &mut StationTrack) -> Result<(), GwtError> {
+    for player in 0..players {
+        let turnout = r.flag()?;
+        let location = r.amount()? as usize;
+        stations.moveEngine(player, if turnout { EngineSpace::TurnoutTrack(location) } else { EngineSpace::MainTrack(location) });
+    }
+    for i in 0..stations.numStations() {
+        for player in 0..players {
+            if r.flag()? {
+                stations.placeStationDisc(player, i)?;
+            }
+        }
+        if r.flag()? {
+            let station_master = readStationMaster(r, data)?;
+            if i >= stations.stations().len() {
+                return Err(invalid("the final station has a station master"));
+            }
+            stations.placeStationMaster(i, station_master);
+        }
+    }
+    for city in 0..stations.cities().cities().len() {
+        for player in 0..players {
+            for _ in 0..r.count()? {
+                stations.makeDelivery(player, city as u32);
+            }
+        }
+    }
+    Ok(())
+}
+
+// The buildings the player placed are the ones on the track where they placed them
+fn writePlayer(w: &mut BitWriter, data: &GameData, player: &Player) -> Result<(), GwtError> {
+    let counters = player.counters();
+    w.amount(counters.dollars as u64);
+    w.amount(counters.cattleman_pos as u64);
+    for hired in counters.hired {
+        w.amount(hired as u64);
+    }
+    for count in [counters.certificates, counters.green_tepees, counters.blue_tepees] {
+        w.amount(count as u64);
+    }
+    w.flag(counters.job_market_token);
+    let board = player.board();
+    w.fixed(board.handSize() as u64 - 4, 2);
+    w.fixed(board.stepLimit() as u64 - 3, 2);
+    for i in 0..PlayerBoard::NUM_AUX_ACTIONS {
+        w.fixed(unlocked(board, i), 2);
+    }
+    w.amount(player.buildings().len() as u64);
+    for building in player.buildings().iter() {
+        writeBuilding(w, data, building)?;
+    }
+    w.amount(player.playedBuildings().len() as u64);
+    for location in player.playedBuildings().iter() {
+        w.amount(*location as u64);
+    }
+    w.amount(player.playedObjectives().len() as u64);
+    for objective in player.playedObjectives().iter() {
+        w.amount(objectiveIndex(data, objective)?);
+    }
+    w.amount(player.hazards().len() as u64);
+    for hazard in player.hazards().iter() {
+        writeHazard(w, hazard);
+    }
+    w.amount(player.stationMasters().len() as u64);
+    for station_master in player.stationMasters().iter() {
+        writeStationMaster(w, data, station_master)?;
+    }
+    let deck = player.deck();
+    writeCards(w, data, deck.hand())?;
+    writeCards(w, data, deck.drawPile())?;
+    writeCards(w, data, deck.discardPile())
+}
+
+fn unlocked(board: &PlayerBoard, aux: usize) -> u64 {
+    match board.auxAction(aux).availableActions() {
+        (None, _) => 0,
+        (Some(_), None) => 1,
+        (Some(_), Some(_)) => 2,
+    }
+}
+
+fn readPlayer(r: &mut BitReader, data: &GameData, id: u32, track: &[Space]) -> Result<Player, GwtError> {
+    let dollars = r.amount()? as u32;
+    let cattleman_pos = r.amount()? as usize;
+    let hired = [r.amount()? as u32, r.amount()? as u32, r.amount()? as u32];
+    let (certificates, green_tepees, blue_tepees) = (r.amount()? as u32, r.amount()? as u32, r.amount()? as u32);
+    let job_market_token = r.flag()?;
+    // The board is rebuilt by taking its discs off
+    let mut board = PlayerBoard::new();
+    for _ in 0..r.fixed(2)? {
+        board.removeDisc(PlayerBoard::HAND_SLOT)?;
+    }
+    for _ in 0..r.fixed(2)? {
+        board.removeDisc(PlayerBoard::STEP_SLOT)?;
+    }
+    for i in 0..PlayerBoard::NUM_AUX_ACTIONS {
+        for _ in unlocked(&board, i)..r.fixed(2)? {
+            board.removeDisc(i as u32)?;
+        }
+    }
+    let mut buildings = vec![];
+    for _ in 0..r.count()? {
+        buildings.push(readBuilding(r, data)?);
+    }
+    let mut placed = vec![];
+    for _ in 0..r.count()? {
+        let location = r.amount()? as usize;
+        let building = track.get(location).and_then(|s| { s.building() })
+            .ok_or_else(|| { invalid(&format!("there's no building at {}", location)) })?;
+        placed.push((building, location));
+    }
+    let mut played_objectives = vec![];
+    for _ in 0..r.count()? {
+        played_objectives.push(readObjective(r, data)?);
+    }
+    let mut hazards = vec![];
+    for _ in 0..r.count()? {
+        hazards.push(readHazard(r)?);
+    }
+    let mut station_masters = vec![];
+    for _ in 0..r.count()? {
+        station_masters.push(readStationMaster(r, data)?);
+    }
+    let (hand, draw, discard) = (readCards(r, data)?, readCards(r, data)?, readCards(r, data)?);
+
+    let deck = Deck::restored(hand, draw, discard, board.handSize(), id as u64);
+    buildings.extend(placed.iter().map(|(building, _)| { *building }));
+    let mut player = Player::withDeck(id, deck, buildings);
+    for (building, location) in placed {
+        player.placeBuilding(building, location)?;
+    }
+    for objective in played_objectives {
+        player.takeStartingObjective(objective);
+    }
+    for hazard in hazards {
+        player.takeHazard(hazard);
+    }
+    for station_master in station_masters {
+        player.takeStationMaster(station_master);
+    }
+    player.setCounters(Counters {
+        dollars, cattleman_pos, hired, green_tepees, blue_tepees, certificates, job_market_token, board,
+        hand_size: board.handSize(),
+    });
+    Ok(player)
+}
+
+fn tileKey(tile: &Tile) -> u64 {
+    match tile {
+        Tile::EmployeeTile(emp) => *emp as u64,
+        Tile::HazardTile(hazard) =>
+            1 << 32 | (hazard.area() as u64) << 24 | (hazard.toll() as u64) << 16 | hazard.points() as u64,
+        Tile::TepeeTile(tepee) => 2 << 32 | *tepee as u64,
+        Tile::BlankTile => 3 << 32,
+    }
+}
+
+fn writeMarkets(w: &mut BitWriter, data: &GameData, engine: &Engine) -> Result<(), GwtError> {
+    let cows = engine.cows.cows();
+    w.amount(cows.len() as u64);
+    for cow in cows.iter() {
+        writeCow(w, cow);
+    }
+    writeCounts(w, engine.cows.deck(), cowKey, |w, cow| {
+        writeCow(w, cow);
+        Ok(())
+    })?;
+
+    let foresight = &engine.foresight;
+    for tile in foresight.current().iter().chain(foresight.next().iter()) {
+        writeTile(w, tile);
+    }
+    for pile in foresight.piles().iter() {
+        writeCounts(w, pile, tileKey, |w, tile| {
+            writeTile(w, tile);
+            Ok(())
+        })?;
+    }
+
+    let employees = engine.job_market.employees();
+    w.amount(employees.len() as u64);
+    for emp in employees.iter() {
+        w.fixed(emp.map(|e| { e as u64 + 1 }).unwrap_or(0), 2);
+    }
+
+    w.amount(engine.objective_market.len() as u64);
+    for objective in engine.objective_market.iter() {
+        w.amount(objectiveIndex(data, objective)?);
+    }
+    writeCards(w, data, &objectiveCards(&engine.objectives))
+}
+
+fn readMarkets(r: &mut BitReader, data: &GameData, engine: &mut Engine) -> Result<(), GwtError> {
+    let mut market = vec![];
+    for _ in 0..r.count()? {
+        market.push(readCow(r)?);
+    }
+    engine.cows = CowMarket::restored(readCounts(r, readCow)?, &market);
+
+    // Foresight deals the current and then the next tile of each column off the top of its pile
+    let rows = [[readTile(r)?, readTile(r)?, readTile(r)?], [readTile(r)?, readTile(r)?, readTile(r)?]];
+    let mut piles: [Vec<Tile>; Foresight::FORESIGHT_SIZE] = Default::default();
+    for (i, pile) in piles.iter_mut().enumerate() {
+        *pile = readCounts(r, readTile)?;
+        pile.push(rows[1][i]);
+        pile.push(rows[0][i]);
+    }
+    engine.foresight = Foresight::new(piles);
+
+    let mut employees = vec![];
+    for _ in 0..r.count()? {
+        employees.push(match r.fixed(2)? {
+            0 => None,
+            emp => Some(pick(&EMPLOYEES, emp - 1, "employee")?),
+        });
+    }
+    engine.job_market = JobMarket::restored(engine.players.len(), employees);
+
+    engine.objective_market = vec![];
+    for _ in 0..r.count()? {
+        engine.objective_market.push(readObjective(r, data)?);
+    }
+    engine.objectives = readCards(r, data)?.into_iter()
+        .map(|card| { if let Card::ObjectiveCard(objective) = card { Ok(objective) } else { Err(invalid("a cow is in the objectives deck")) } })
+        .collect::<Result<Vec<Objective>, GwtError>>()?;
+    Ok(())
+}
+
+// FNV-1a, a fixed algorithm unlike the standard library's hasher. Derived Hash still feeds it discriminants and
+// lengths in whatever form the compiler chooses, so the result depends on the platform and compiler
+struct Fnv(u64);
+
+impl Hasher for Fnv {
+    fn finish(&self) -> u64 { self.0 }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ *byte as u64).wrapping_mul(0x100000001b3);
+        }
+    }
+}
+
+fn fingerprint<T: Hash>(value: &T) -> u64 {
+    let mut hasher = Fnv(0xcbf29ce484222325);
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn mix(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e3779b97f4a7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
+
+fn cardId(card: &Card) -> u64 {
+    match card {
+        Card::CowCard(cow) => cowKey(cow),
+        Card::ObjectiveCard(objective) => fingerprint(objective) | 1 << 63,
+    }
+}
+
+// The XOR of the keys of a part's features
+struct Keys {
+    part: u64,
+    hash: u64,
+}
+
+impl Keys {
+    fn add(&mut self, feature: &[u64]) {
+        self.hash ^= mix(feature.iter().fold(mix(self.part), |h, v| { mix(h ^ v) }));
+    }
+
+    // Lists which may hold the same thing twice are keyed by how many of each they hold, since equal keys would cancel
+    fn counts(&mut self, kind: u64, ids: impl Iterator<Item=u64>) {
+        let ids: Vec<u64> = ids.collect();
+        for (id, n) in counts(&ids, |id| { *id }) {
+            self.add(&[kind, id, n]);
+        }
+    }
+}
+
+fn playerFeatures(keys: &mut Keys, player: &Player) {
+    let counters = player.counters();
+    keys.add(&[0, counters.dollars as u64]);
+    keys.add(&[1, counters.cattleman_pos as u64]);
+    for (emp, hired) in counters.hired.iter().enumerate() {
+        keys.add(&[2, emp as u64, *hired as u64]);
+    }
+    keys.add(&[3, counters.certificates as u64]);
+    keys.add(&[4, counters.green_tepees as u64]);
+    keys.add(&[5, counters.blue_tepees as u64]);
+    keys.add(&[6, counters.job_market_token as u64]);
+    let board = player.board();
+    keys.add(&[7, board.handSize() as u64]);
+    keys.add(&[8, board.stepLimit() as u64]);
+    for i in 0..PlayerBoard::NUM_AUX_ACTIONS {
+        keys.add(&[9, i as u64, unlocked(board, i)]);
+    }
+    keys.counts(10, player.buildings().iter().map(fingerprint));
+    for location in player.playedBuildings().iter() {
+        keys.add(&[11, *location as u64]);
+    }
+    keys.counts(12, player.playedObjectives().iter().map(fingerprint));
+    keys.counts(13, player.hazards().iter().map(fingerprint));
+    keys.counts(14, player.stationMasters().iter().map(fingerprint));
+    let deck = player.deck();
+    keys.counts(15, deck.hand().iter().map(cardId));
+    keys.counts(16, deck.drawPile().iter().map(cardId));
+    keys.counts(17, deck.discardPile().iter().map(cardId));
+}
+
+fn spaceFeatures(keys: &mut Keys, space: &Space) {
+    let content = match space.occupant() {
+        SpaceOccupant::Building { building: Some(building), .. } => fingerprint(building),
+        SpaceOccupant::Hazard(_, Some(hazard)) => fingerprint(hazard),
+        SpaceOccupant::Tepee(_, Some(tepee)) => *tepee as u64 + 1,
+        _ => 0,
+    };
+    keys.add(&[20, content]);
+}
+
+fn stationFeatures(keys: &mut Keys, engine: &Engine) {
+    let stations = &engine.station_track;
+    for player in 0..engine.players.len() as u32 {
+        keys.add(&[30, player as u64, fingerprint(&stations.engineSpace(player))]);
+        for i in 0..stations.numStations() {
+            if stations.station(i).hasDisc(player) {
+                keys.add(&[31, i as u64, player as u64]);
+            }
+        }
+        for (i, city) in stations.cities().cities().iter().enumerate() {
+            keys.add(&[32, i as u64, player as u64, city.discs(player) as u64]);
+        }
+    }
+    for i in 0..stations.numStations() {
+        if let Some(station_master) = stations.station(i).stationMaster() {
+            keys.add(&[33, i as u64, fingerprint(&station_master)]);
+        }
+    }
+}
+
+fn partFeatures(keys: &mut Keys, engine: &Engine, part: Part) {
+    match part {
+        Part::Player(i) => playerFeatures(keys, &engine.players[i]),
+        Part::Space(i) => spaceFeatures(keys, &engine.track[i]),
+        Part::StationTrack => stationFeatures(keys, engine),
+        Part::Cows => {
+            keys.counts(40, engine.cows.cows().iter().map(cowKey));
+            keys.counts(41, engine.cows.deck().iter().map(cowKey));
+        }
+        Part::Foresight => {
+            let foresight = &engine.foresight;
+            for (row, tiles) in [foresight.current(), foresight.next()].iter().enumerate() {
+                for (column, tile) in tiles.iter().enumerate() {
+                    keys.add(&[50, row as u64, column as u64, tileKey(tile)]);
+                }
+            }
+            for (column, pile) in foresight.piles().iter().enumerate() {
+                keys.counts(51 + column as u64, pile.iter().map(tileKey));
+            }
+        }
+        Part::JobMarket => {
+            let employees = engine.job_market.employees();
+            keys.add(&[60, employees.len() as u64]);
+            for (slot, emp) in employees.iter().enumerate() {
+                keys.add(&[61, slot as u64, emp.map(|e| { e as u64 + 1 }).unwrap_or(0)]);
+            }
+        }
+        Part::Objectives => {
+            keys.counts(70, engine.objective_market.iter().map(fingerprint));
+            keys.counts(71, engine.objectives.iter().map(fingerprint));
+        }
+    }
+}
+
+fn parts(engine: &Engine) -> Vec<Part> {
+    let mut parts: Vec<Part> = (0..engine.players.len()).map(Part::Player).collect();
+    parts.extend((0..engine.track.len()).map(Part::Space));
+    parts.extend([Part::StationTrack, Part::Cows, Part::Foresight, Part::JobMarket, Part::Objectives]);
+    parts
+}
+
+fn partKey(engine: &Engine, part: Part) -> u64 {
+    let code = match part {
+        Part::Player(i) => i as u64,
+        Part::Space(i) => (1 << 32) | i as u64,
+        Part::StationTrack => 2 << 32,
+        Part::Cows => 3 << 32,
+        Part::Foresight => 4 << 32,
+        Part::JobMarket => 5 << 32,
+        Part::Objectives => 6 << 32,
+    };
+    let mut keys = Keys { part: code, hash: 0 };
+    partFeatures(&mut keys, engine, part);
+    keys.hash
+}
+
+fn turnKey(engine: &Engine) -> u64 {
+    let mut keys = Keys { part: 7 << 32, hash: 0 };
+    keys.add(&[80, engine.starting_player as u64]);
+    keys.add(&[81, engine.current_player as u64]);
+    let state = match &engine.state {
+        GameState::Playing => 0,
+        GameState::FinalRound { triggered_by } => 1 + *triggered_by as u64,
+        GameState::GameOver { .. } => 5,
+    };
+    keys.add(&[82, state]);
+    keys.add(&[83, fingerprint(&engine.phase)]);
+    keys.add(&[84, fingerprint(&engine.pending)]);
+    keys.hash
+}
+
+impl Engine {
+    // Fails for buildings, objectives and station masters which aren't in the data
+    pub fn encode(&self, data: &GameData) -> Result<Vec<u8>, GwtError> {
+        let mut w = BitWriter { bytes: vec![], bits: 0 };
+        w.fixed(self.players.len() as u64, 3);
+        w.amount(self.track.len() as u64);
+        for space in self.track.iter() {
+            writeSpace(&mut w, data, space)?;
+        }
+        writeStations(&mut w, data, self)?;
+        for player in self.players.iter() {
+            writePlayer(&mut w, data, player)?;
+        }
+        writeMarkets(&mut w, data, self)?;
+        writeTurn(&mut w, self);
+        Ok(w.bytes)
+    }
+
+    // The position encoded with the same data, its counted piles dealt in the order of the encoding
+    pub fn decode(data: &GameData, bytes: &[u8]) -> Result<Engine, GwtError> {
+        let mut r = BitReader { bytes, bits: 0 };
+        let players = r.fixed(3)? as u32;
+        if !(2..=4).contains(&players) {
+            return Err(GwtError::InvalidPlayerCount(players));
+        }
+        if r.amount()? != data.track.len() as u64 {
+            return Err(invalid("its track isn't the data's"));
+        }
+        let mut track = vec![];
+        for space in data.track.iter() {
+            track.push(readSpace(&mut r, data, *space)?);
+        }
+        let mut station_track = data.station_track.clone();
+        readStations(&mut r, data, players, &mut station_track)?;
+        let mut seats = vec![];
+        for id in 0..players {
+            seats.push(readPlayer(&mut r, data, id, &track)?);
+        }
+        let mut engine = Engine::new(seats, track, station_track);
+        readMarkets(&mut r, data, &mut engine)?;
+        readTurn(&mut r, &mut engine)?;
+        if r.bits + 8 <= bytes.len() * 8 {
+            return Err(invalid("there are bytes left over"));
+        }
+        Ok(engine)
+    }
+
+    // Starts or stops keeping the hash up to date as actions are applied, instead of hashing everything when asked
+    pub fn keepHash(&mut self, keep: bool) {
+        self.hash_cache.dirty.clear();
+        self.hash_cache.hash = if keep { Some(parts(self).into_iter().fold(0, |h, p| { h ^ partKey(self, p) })) } else { None };
+    }
+
+    pub fn zobrist(&self) -> u64 {
+        let parts = match self.hash_cache.hash {
+            Some(hash) => self.hash_cache.dirty.iter().fold(hash, |h, p| { h ^ partKey(self, *p) }),
+            None => parts(self).into_iter().fold(0, |h, p| { h ^ partKey(self, p) }),
+        };
+        parts ^ turnKey(self)
+    }
+
+    // Takes a part out of the kept hash before it changes
+    pub(crate) fn unhash(&mut self, part: Part) {
+        if let Some(hash) = self.hash_cache.hash {
+            if !self.hash_cache.dirty.contains(&part) {
+                self.hash_cache.hash = Some(hash ^ partKey(self, part));
+                self.hash_cache.dirty.push(part);
+            }
+        }
+    }
+
+    // Puts the changed parts back into the kept hash
+    pub(crate) fn settleHash(&mut self) {
+        let dirty = std::mem::take(&mut self.hash_cache.dirty);
+        if let Some(hash) = self.hash_cache.hash {
+            self.hash_cache.hash = Some(dirty.into_iter().fold(hash, |h, p| { h ^ partKey(self, p) }));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_pcg::Pcg64;
+
+    use crate::agent::testing::playRandom;
+    use crate::data::GameData;
+
+    use super::*;
+
+    #[test]
+    fn testIncrementalHash() {
+        let data = GameData::embedded().unwrap();
+        let mut engine = Engine::newGame(&data, 3, 6).unwrap();
+        engine.keepHash(true);
+        engine.keepHistory(true);
+        let mut hashes = vec![engine.zobrist()];
+        playRandom(&mut engine, 2, usize::MAX, |engine| {
+            let mut fresh = engine.clone();
+            fresh.keepHash(false);
+            assert_eq!(engine.zobrist(), fresh.zobrist());
+            hashes.push(engine.zobrist());
+        });
+        for i in (0..hashes.len() - 1).rev() {
+            engine.undo().unwrap();
+            assert_eq!(engine.zobrist(), hashes[i]);
+        }
+
+        // The encoding is much smaller than the JSON, and doesn't change when only a hidden order does
+        let encoded = engine.encode(&data).unwrap();
+        assert!(encoded.len() * 20 < serde_json::to_string(&engine).unwrap().len());
+        let mut shuffled = engine.clone();
+        for player in shuffled.players.iter_mut() {
+            player.deckMut().determinize(&mut Pcg64::seed_from_u64(1), false);
+        }
+        assert_eq!(shuffled.encode(&data).unwrap(), encoded);
+        assert_eq!(shuffled.zobrist(), engine.zobrist());
+        engine.applyAction(engine.legalActions()[0]).unwrap();
+        assert_ne!(engine.encode(&data).unwrap(), encoded);
+    }
+
+    fn sortedLegal(engine: &Engine) -> Vec<String> {
+        let mut legal: Vec<String> = engine.legalActions().iter().map(|a| { format!("{:?}", a) }).collect();
+        legal.sort();
+        legal
+    }
+
+    #[test]
+    fn testDecode() {
+        let data = GameData::embedded().unwrap();
+        for players in 2..=4 {
+            let check = |engine: &Engine| {
+                let encoded = engine.encode(&data).unwrap();
+                let decoded = Engine::decode(&data, &encoded).unwrap();
+                assert_eq!(decoded.encode(&data).unwrap(), encoded);
+                assert_eq!(decoded.zobrist(), engine.zobrist());
+                assert_eq!(sortedLegal(&decoded), sortedLegal(engine));
+                assert_eq!(decoded.isGameOver(), engine.isGameOver());
+                assert!(Engine::decode(&data, &encoded[..encoded.len() - 1]).is_err());
+                let mut longer = encoded.clone();
+                longer.push(0);
+                assert!(Engine::decode(&data, &longer).is_err());
+            };
+            let mut engine = Engine::newGame(&data, players, 8).unwrap();
+            check(&engine);
+            let mut moves = 0;
+            playRandom(&mut engine, players as u64, usize::MAX, |engine| {
+                moves += 1;
+                if moves % 25 == 0 || engine.isGameOver() {
+                    check(engine);
+                }
+            });
+        }
+        assert!(Engine::decode(&data, &[]).is_err());
+    }
+}
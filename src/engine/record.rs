@@ -0,0 +1,153 @@
+use std::fs::{read_to_string, write};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Engine, GameSetup, GameState};
+use crate::actions::Action;
+use crate::data::GameData;
+use crate::error::{DataError, GwtError, ReplayError};
+use crate::scoring::ScoreSheet;
+
+pub const RECORD_VERSION: u32 = 1;
+
+// Everything needed to play a game again: how it was set up and every action with the player who took it
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+pub struct GameRecord {
+    pub version: u32,
+    pub setup: GameSetup,
+    pub data_hash: u64,
+    pub actions: Vec<(u32, Action)>,
+    // Only set for finished games
+    pub scores: Option<Vec<ScoreSheet>>,
+}
+
+impl GameRecord {
+    pub fn new(data: &GameData, setup: GameSetup) -> GameRecord {
+        GameRecord { version: RECORD_VERSION, setup, data_hash: data.hash(), actions: vec![], scores: None }
+    }
+
+    // Adds the action the current player takes, before it's applied
+    pub fn push(&mut self, engine: &Engine, action: Action) {
+        self.actions.push((engine.currentPlayer() as u32, action));
+    }
+
+    // Records the final scores once the game is over
+    pub fn finish(&mut self, engine: &Engine) {
+        if let GameState::GameOver { scores, .. } = engine.state() {
+            self.scores = Some(scores.clone());
+        }
+    }
+
+    // Fails for records of another version or made with other game data
+    pub fn check(&self, data: &GameData) -> Result<(), GwtError> {
+        if self.version != RECORD_VERSION {
+            return Err(ReplayError::Version(self.version).into());
+        }
+        let found = data.hash();
+        if self.data_hash != found {
+            return Err(ReplayError::DataHash { expected: self.data_hash, found }.into());
+        }
+        Ok(())
+    }
+
+    // Applies the first actions of the record to the engine it was set up as, checking each is the current player's
+    pub fn playOn(&self, engine: &mut Engine, actions: usize) -> Result<(), GwtError> {
+        for (index, (player, action)) in self.actions.iter().take(actions).enumerate() {
+            if engine.isGameOver() {
+                return Err(ReplayError::ExtraActions(index).into());
+            }
+            let current = engine.currentPlayer() as u32;
+            if *player != current {
+                return Err(ReplayError::WrongPlayer { index, player: *player, current }.into());
+            }
+            engine.applyAction(*action)?;
+        }
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<GameRecord, GwtError> {
+        let file = path.display().to_string();
+        let contents = read_to_string(path).map_err(|e| { DataError::Io { file: file.clone(), message: e.to_string() } })?;
+        serde_json::from_str(&contents)
+            .map_err(|e| { DataError::Schema { file, line: e.line(), column: e.column(), message: e.to_string() }.into() })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), GwtError> {
+        let contents = serde_json::to_string_pretty(self).map_err(|e| { GwtError::Output(e.to_string()) })?;
+        write(path, contents).map_err(|e| { GwtError::Output(e.to_string()) })
+    }
+}
+
+impl Engine {
+    // Plays the whole record, checking every action is legal and the final scores match the recorded ones
+    pub fn replay(data: &GameData, record: &GameRecord) -> Result<Engine, GwtError> {
+        let engine = Engine::replayTo(data, record, record.actions.len())?;
+        if let Some(scores) = &record.scores {
+            match engine.state() {
+                GameState::GameOver { scores: replayed, .. } if replayed == scores => {}
+                _ => return Err(ReplayError::Scores.into()),
+            }
+        }
+        Ok(engine)
+    }
+
+    // The state after the first actions of the record have been played
+    pub fn replayTo(data: &GameData, record: &GameRecord, actions: usize) -> Result<Engine, GwtError> {
+        record.check(data)?;
+        let mut engine = Engine::setup(data, &record.setup)?;
+        record.playOn(&mut engine, actions)?;
+        Ok(engine)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::agent::{Agent, RandomAgent};
+    use crate::runner::playGame;
+    use crate::tiles::Tile;
+
+    use super::*;
+
+    #[test]
+    fn testReplay() {
+        let data = GameData::embedded().unwrap();
+        let mut agents: Vec<Box<dyn Agent>> = vec![Box::new(RandomAgent::new(1)), Box::new(RandomAgent::new(2))];
+        let result = playGame(&data, &mut agents, 9).unwrap();
+        let record = result.record(&data);
+        let engine = Engine::replay(&data, &record).unwrap();
+        assert_eq!(engine.state(), &GameState::GameOver { scores: result.scores.clone(), winners: result.winners.clone() });
+
+        let path = std::env::temp_dir().join(format!("gwt_record_test_{}.json", std::process::id()));
+        record.save(&path).unwrap();
+        let loaded = GameRecord::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.unwrap(), record);
+
+        let halfway = Engine::replayTo(&data, &record, 10).unwrap();
+        assert!(!halfway.isGameOver());
+
+        let mut wrong_player = record.clone();
+        wrong_player.actions[0].0 = 1;
+        assert_eq!(Engine::replay(&data, &wrong_player).err(), Some(ReplayError::WrongPlayer { index: 0, player: 1, current: 0 }.into()));
+        let mut wrong_scores = record.clone();
+        wrong_scores.scores.as_mut().unwrap()[0].dollars += 1;
+        assert_eq!(Engine::replay(&data, &wrong_scores).err(), Some(ReplayError::Scores.into()));
+        let sides = GameRecord::new(&data, GameSetup { side_b: vec![true; 10], ..record.setup.clone() });
+        assert_ne!(Engine::replay(&data, &sides).unwrap(), Engine::newGame(&data, 2, 9).unwrap());
+    }
+
+    #[test]
+    fn testOlderRecord() {
+        // Saved before the save games were versioned, and before the foresight piles had their 3 point hazards.
+        // It replays with the data it was played with, and only with that
+        let data = GameData::embedded().unwrap();
+        let mut played_with = data.clone();
+        played_with.foresight_piles[0].retain(|t| { !matches!(t, Tile::HazardTile(h) if h.points() == 3) });
+        let record = GameRecord::load(Path::new("./tests/fixtures/record_v1.json")).unwrap();
+        assert_eq!(record.data_hash, played_with.hash());
+        assert!(Engine::replay(&played_with, &record).unwrap().isGameOver());
+        let found = data.hash();
+        assert_eq!(Engine::replay(&data, &record).err(), Some(ReplayError::DataHash { expected: record.data_hash, found }.into()));
+    }
+}
@@ -0,0 +1,1146 @@
+use serde::{Deserialize, Serialize};
+
+use crate::Engine;
+use crate::actions::{Action, ActionTag, ActionValues, DiscardCardOpts};
+use crate::buildings::{Building, Tepee, Toll};
+use crate::deck::{Card, Cow};
+use crate::error::GwtError;
+use crate::events::GameEvent;
+use crate::history::{Change, Part};
+use crate::logical::XOr;
+use crate::player::{Employee, Player, PlayerBoard};
+use crate::tiles::{Foresight, Tile};
+use crate::tracks::{EngineSpace, SpaceOccupant};
+
+// Where the current player is in their turn
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize, Debug)]
+pub enum Phase {
+    // Choosing where to move the cattleman
+    Move,
+    // At a location, either some of the building's options or one auxiliary action can be used
+    Location { used: [bool; 3], auxiliary: bool },
+    KansasCity(KansasCityStep),
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize, Debug)]
+pub enum KansasCityStep {
+    // Choosing a tile from a foresight column
+    Foresight(u32),
+    Income,
+    // value is the hand value plus the certificates spent
+    Delivery { value: u32 },
+    // The hand has been shipped, only the hand refill is left
+    Done,
+}
+
+// Actions which have to be resolved before the turn continues
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize, Debug)]
+pub enum Pending {
+    // Optional, passing also skips the rest of its group
+    Tag(ActionTag),
+    // One of the tags, chosen by taking one of its actions. Optional like Tag
+    Either(XOr<ActionTag, 2>),
+    // Cows can be bought while there are cowboys left
+    BuyCows(u32),
+    // Cards drawn have to be discarded again
+    Discard(u32),
+    // A disc has to come off the player board after a delivery or station upgrade
+    RemoveDisc { advanced: bool },
+    // The engine passed the stations after from, up to and including to
+    UpgradeStation { from: u32, to: u32 },
+}
+
+impl Pending {
+    fn optional(&self) -> bool {
+        !matches!(self, Pending::Discard(_) | Pending::RemoveDisc { .. })
+    }
+}
+
+impl Engine {
+    pub const TRANSPORT_COST_PER_CROSSING: u32 = 2;
+    pub const OBJECTIVE_MARKET_SIZE: usize = 4;
+
+    pub fn phase(&self) -> Phase { self.phase }
+
+    // The groups of actions still to resolve, the last group is resolved first
+    pub fn pending(&self) -> &Vec<Vec<Pending>> { &self.pending }
+
+    pub fn legalActions(&self) -> Vec<Action> {
+        if self.isGameOver() {
+            return vec![];
+        }
+        if let Some(group) = self.pending.last() {
+            let mut actions = self.pendingActions(group[0]);
+            if group[0].optional() {
+                actions.push(Action::Pass);
+            }
+            return actions;
+        }
+        let player = self.player();
+        match self.phase {
+            Phase::Move => {
+                self.reachable(player.cattleManPos(), player.board().stepLimit()).iter()
+                    .map(|(space, _)| { Action::MoveCattleman(Some(*space as i32)) })
+                    .collect()
+            }
+            Phase::Location { used, auxiliary } => {
+                let mut actions = Vec::<Action>::new();
+                if !auxiliary {
+                    actions.append(&mut self.buildingActions(used));
+                    if used == [false; 3] {
+                        actions.append(&mut self.auxiliaryActions(false));
+                    }
+                }
+                actions.append(&mut self.objectiveActions());
+                actions.push(Action::FillHand);
+                actions
+            }
+            Phase::KansasCity(KansasCityStep::Foresight(col)) => vec![Action::ChooseTile(col, 0), Action::ChooseTile(col, 1)],
+            Phase::KansasCity(KansasCityStep::Income) => vec![Action::SellHand],
+            Phase::KansasCity(KansasCityStep::Delivery { value }) => {
+                (0..self.station_track.cities().cities().len())
+                    .filter(|city| { self.canDeliver(*city, value) })
+                    .map(|city| { Action::PlaceDisc(Some(city as u32)) })
+                    .collect()
+            }
+            Phase::KansasCity(KansasCityStep::Done) => {
+                let mut actions = self.objectiveActions();
+                actions.push(Action::FillHand);
+                actions
+            }
+        }
+    }
+
+    pub fn applyAction(&mut self, action: Action) -> Result<(), GwtError> {
+        self.apply(action, true)
+    }
+
+    // The observers are only told the action's events when tell is set
+    pub(crate) fn apply(&mut self, action: Action, tell: bool) -> Result<(), GwtError> {
+        if !self.legalActions().contains(&action) {
+            return Err(GwtError::illegal(action));
+        }
+        let result = self.attempt(action);
+        self.flushEvents(tell && result.is_ok());
+        result
+    }
+
+    // Resolves a legal action, taking back everything it had changed when it fails part way
+    fn attempt(&mut self, action: Action) -> Result<(), GwtError> {
+        self.beginDelta(action);
+        self.emit(GameEvent::ActionTaken { player: self.id(), action });
+        let result = self.resolveAction(action);
+        self.endDelta(result.is_ok());
+        self.settleHash();
+        result
+    }
+
+    fn resolveAction(&mut self, action: Action) -> Result<(), GwtError> {
+        let pending = self.pending.last().map(|group| { self.chosen(group[0], action) });
+        if pending.is_some() {
+            if action == Action::Pass {
+                self.pending.pop();
+            } else {
+                self.popPending();
+            }
+        }
+        self.resolve(action, pending)?;
+        self.settle()
+    }
+
+    // The tag an action was taken for when it resolves a choice between tags
+    fn chosen(&self, item: Pending, action: Action) -> Pending {
+        match item {
+            Pending::Either(choices) => choices.items.iter().flatten()
+                .find(|tag| { self.tagActions(**tag).contains(&action) })
+                .map(|tag| { Pending::Tag(*tag) })
+                .unwrap_or(item),
+            _ => item,
+        }
+    }
+
+    fn player(&self) -> &Player {
+        &self.players[self.current_player]
+    }
+
+    fn playerMut(&mut self) -> &mut Player {
+        self.touch(Part::Player(self.current_player));
+        &mut self.players[self.current_player]
+    }
+
+    fn id(&self) -> u32 {
+        self.current_player as u32
+    }
+
+    fn popPending(&mut self) {
+        if let Some(group) = self.pending.last_mut() {
+            group.remove(0);
+            if group.is_empty() {
+                self.pending.pop();
+            }
+        }
+    }
+
+    // Resolves everything that doesn't need a decision, and drops what can't be done
+    fn settle(&mut self) -> Result<(), GwtError> {
+        while let Some(group) = self.pending.last() {
+            let item = group[0];
+            match item {
+                Pending::Tag(tag) if Engine::automatic(tag) => {
+                    self.popPending();
+                    self.resolveTag(tag)?;
+                }
+                _ => {
+                    if !self.pendingActions(item).is_empty() {
+                        break;
+                    }
+                    if item.optional() {
+                        self.pending.pop();
+                    } else {
+                        self.popPending();
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn automatic(tag: ActionTag) -> bool {
+        matches!(tag, ActionTag::TakeCoins(_) | ActionTag::MoveCertificate(_) | ActionTag::DrawCards(_)
+            | ActionTag::CityDiscMoveTrain | ActionTag::StationDiscBehindTrain | ActionTag::AdjacentBuilding)
+    }
+
+    fn resolveTag(&mut self, tag: ActionTag) -> Result<(), GwtError> {
+        match tag {
+            ActionTag::TakeCoins(v) => {
+                let amount = self.value(v);
+                if amount >= 0 {
+                    self.playerMut().earn(amount as u32);
+                    self.emit(GameEvent::CoinsTaken { player: self.id(), amount });
+                } else {
+                    // Pay what you can
+                    let owed = std::cmp::min(amount.unsigned_abs(), self.player().dollars());
+                    self.playerMut().pay(owed)?;
+                    self.emit(GameEvent::CoinsTaken { player: self.id(), amount: -(owed as i32) });
+                }
+            }
+            ActionTag::MoveCertificate(v) => {
+                let amount = self.value(v).max(0) as u32;
+                self.playerMut().gainCertificates(amount);
+                self.emit(GameEvent::CertificatesGained { player: self.id(), amount });
+            }
+            ActionTag::DrawCards(v) => {
+                let mut drawn = 0;
+                for _ in 0..self.value(v).max(0) {
+                    if self.playerMut().deckMut().drawCard().is_err() {
+                        break;
+                    }
+                    drawn += 1;
+                }
+                if drawn > 0 {
+                    self.emit(GameEvent::CardsDrawn { player: self.id(), count: drawn });
+                    self.pending.push(vec![Pending::Discard(drawn)]);
+                }
+            }
+            ActionTag::CityDiscMoveTrain => {
+                let id = self.id();
+                let cities = self.station_track.cities().cities().iter()
+                    .filter(|c| { c.discs(id) > 0 })
+                    .count() as i32;
+                self.pending.push(vec![Pending::Tag(ActionTag::MoveEngine(ActionValues::Exact(cities)))]);
+            }
+            ActionTag::StationDiscBehindTrain => {
+                let to = self.station_track.engineLocation(self.id());
+                self.pending.push(vec![Pending::UpgradeStation { from: 0, to }]);
+            }
+            ActionTag::AdjacentBuilding => {
+                self.pending.push(vec![Pending::Tag(ActionTag::TeleportCattleman(ActionValues::Exact(1)))]);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn value(&self, v: ActionValues) -> i32 {
+        let player = self.player();
+        match v {
+            ActionValues::Exact(v) => v,
+            ActionValues::EmployeeMult(emp, v) => v * player.hired(emp) as i32,
+            ActionValues::AllEmployeesMult(v) => v * player.totalHired() as i32,
+            ActionValues::ForestMult(v) => v * self.forestBuildings() as i32,
+            ActionValues::TepeePairMult(v) => v * player.tepeePairs() as i32,
+            ActionValues::HazardPairMult(v) => v * (player.hazards().len() / 2) as i32,
+            ActionValues::CertificatePairMult(v) => v * (player.certificates() / 2) as i32,
+            ActionValues::ObjectivePairMult(v) => v * (player.playedObjectives().len() / 2) as i32,
+            ActionValues::AtMost(v) => v,
+            ActionValues::Max => Player::MAX_CERTIFICATES as i32,
+        }
+    }
+
+    fn forestBuildings(&self) -> u32 {
+        let id = self.id();
+        self.track.iter()
+            .filter(|space| {
+                match space.occupant() {
+                    SpaceOccupant::Building { building: Some(b), neutral: false, forest: true, .. } => b.owner() == id,
+                    _ => false,
+                }
+            })
+            .count() as u32
+    }
+
+    // The spaces the cattleman can stop on within steps, with the cheapest tolls to get there
+    pub fn reachable(&self, from: usize, steps: u32) -> Vec<(usize, u32)> {
+        let mut found = Vec::<(usize, u32)>::new();
+        self.searchPaths(from, steps, 0, &mut found);
+        found.sort_unstable();
+        found.dedup_by_key(|(space, _)| { *space });
+        found
+    }
+
+    fn searchPaths(&self, from: usize, steps: u32, paid: u32, found: &mut Vec<(usize, u32)>) {
+        if steps == 0 {
+            return;
+        }
+        for next in self.track[from].nextSpaces().iter().flatten() {
+            let space = &self.track[*next];
+            if space.isStop() {
+                let paid = paid + self.toll(*next);
+                found.push((*next, paid));
+                if *space.occupant() != SpaceOccupant::KansasCity {
+                    self.searchPaths(*next, steps - 1, paid, found);
+                }
+            } else {
+                self.searchPaths(*next, steps, paid, found);
+            }
+        }
+    }
+
+    // Tolls for passing through or stopping on a space, nothing is owed for your own buildings
+    fn toll(&self, space: usize) -> u32 {
+        let n = self.players.len();
+        match self.track[space].occupant() {
+            SpaceOccupant::Building { building: Some(b), neutral: false, .. } if b.owner() != self.id() => b.toll().cost(n),
+            SpaceOccupant::Hazard(_, Some(h)) => h.toll().cost(n),
+            _ => 0,
+        }
+    }
+
+    // Pays the tolls along the cheapest path to a space, building owners are paid for their hands
+    fn payTolls(&mut self, from: usize, to: usize, steps: u32) -> Result<(), GwtError> {
+        let path = match self.cheapestPath(from, to, steps) {
+            Some((_, path)) => path,
+            None => return Ok(()),
+        };
+        let n = self.players.len();
+        for space in path {
+            let (owner, toll) = match self.track[space].occupant() {
+                SpaceOccupant::Building { building: Some(b), neutral: false, .. } if b.owner() != self.id() =>
+                    (Some(b.owner() as usize), b.toll()),
+                SpaceOccupant::Hazard(_, Some(h)) => (None, h.toll()),
+                _ => (None, Toll::NoToll),
+            };
+            let cost = toll.cost(n);
+            let cost = std::cmp::min(cost, self.player().dollars());
+            self.playerMut().pay(cost)?;
+            if let Some(owner) = owner {
+                self.touch(Part::Player(owner));
+                self.players[owner].earn(cost);
+            }
+            if cost > 0 {
+                self.emit(GameEvent::TollPaid { from: self.id(), to: owner.map(|o| { o as u32 }), toll, amount: cost });
+            }
+        }
+        Ok(())
+    }
+
+    fn cheapestPath(&self, from: usize, to: usize, steps: u32) -> Option<(u32, Vec<usize>)> {
+        if from == to {
+            return Some((0, vec![]));
+        }
+        if steps == 0 {
+            return None;
+        }
+        self.track[from].nextSpaces().iter()
+            .flatten()
+            .filter_map(|next| {
+                let space = &self.track[*next];
+                if !space.isStop() {
+                    return self.cheapestPath(*next, to, steps);
+                }
+                if *next != to && *space.occupant() == SpaceOccupant::KansasCity {
+                    return None;
+                }
+                self.cheapestPath(*next, to, steps - 1).map(|(cost, mut path)| {
+                    path.insert(0, *next);
+                    (cost + self.toll(*next), path)
+                })
+            })
+            .min_by_key(|(cost, _)| { *cost })
+    }
+
+    fn moveCattlemanTo(&mut self, to: usize) {
+        let from = self.player().cattleManPos();
+        self.playerMut().moveCattleman(to);
+        self.emit(GameEvent::CattlemanMoved { player: self.id(), from, to });
+    }
+
+    // Moves to a location, where the turn continues with its building or in Kansas City
+    fn moveCattleman(&mut self, to: usize) {
+        self.moveCattlemanTo(to);
+        self.pending.clear();
+        self.phase = if *self.track[to].occupant() == SpaceOccupant::KansasCity {
+            Phase::KansasCity(KansasCityStep::Foresight(0))
+        } else {
+            Phase::Location { used: [false; 3], auxiliary: false }
+        };
+    }
+
+    fn playCard(&mut self, card: Card) -> Result<(), GwtError> {
+        let id = self.id();
+        self.playerMut().deckMut().playCard(card).map_err(|e| { GwtError::deck(id, e) })?;
+        self.emit(GameEvent::CardPlayed { player: id, card });
+        Ok(())
+    }
+
+    // The building at the cattleman's location if the player may use its actions
+    fn usableBuilding(&self) -> Option<Building> {
+        self.track[self.player().cattleManPos()].building()
+            .filter(|b| {
+                matches!(self.track[self.player().cattleManPos()].occupant(), SpaceOccupant::Building { neutral: true, .. })
+                    || b.owner() == self.id()
+            })
+    }
+
+    fn buildingActions(&self, used: [bool; 3]) -> Vec<Action> {
+        let building = match self.usableBuilding() {
+            Some(b) => b,
+            None => return vec![],
+        };
+        let mut actions = Vec::<Action>::new();
+        for (i, option) in building.actions().items.iter().enumerate() {
+            if used[i] {
+                continue;
+            }
+            if let Some(option) = option {
+                for (j, and) in option.items.iter().enumerate() {
+                    if let Some(and) = and {
+                        let first = and.items[0].map(|tag| { self.tagPossible(tag) }).unwrap_or(false);
+                        if first {
+                            actions.push(Action::UseBuilding(i as u32, j as u32));
+                        }
+                    }
+                }
+            }
+        }
+        actions
+    }
+
+    // Whether taking the first action of a building option would do anything
+    fn tagPossible(&self, tag: ActionTag) -> bool {
+        match tag {
+            ActionTag::TakeCoins(v) => self.value(v) >= 0 || self.value(v).unsigned_abs() <= self.player().dollars(),
+            ActionTag::MoveCertificate(_) | ActionTag::DrawCards(_) | ActionTag::CityDiscMoveTrain
+            | ActionTag::StationDiscBehindTrain | ActionTag::AdjacentBuilding => true,
+            tag => !self.tagActions(tag).is_empty(),
+        }
+    }
+
+    fn auxiliaryActions(&self, double: bool) -> Vec<Action> {
+        let player = self.player();
+        let mut actions = Vec::<Action>::new();
+        for i in 0..PlayerBoard::NUM_AUX_ACTIONS {
+            let (single, double_action) = player.board().auxAction(i).availableActions();
+            if double && double_action.is_some() {
+                if PlayerBoard::auxCost(i, true) <= player.dollars() {
+                    actions.push(Action::Auxiliary2(i as u32));
+                }
+            } else if single.is_some() && PlayerBoard::auxCost(i, false) <= player.dollars() {
+                actions.push(Action::Auxiliary1(i as u32));
+            }
+        }
+        actions
+    }
+
+    fn objectiveActions(&self) -> Vec<Action> {
+        let mut actions: Vec<Action> = self.player().deck().hand().iter()
+            .filter_map(|card| { if let Card::ObjectiveCard(obj) = card { Some(Action::PlayObjective(*obj)) } else { None } })
+            .collect();
+        Engine::dedup(&mut actions);
+        actions
+    }
+
+    // Keeps the first of each action, without changing the order
+    fn dedup(actions: &mut Vec<Action>) {
+        let mut seen = Vec::<Action>::with_capacity(actions.len());
+        actions.retain(|a| {
+            if seen.contains(a) {
+                false
+            } else {
+                seen.push(*a);
+                true
+            }
+        });
+    }
+
+    fn pendingActions(&self, item: Pending) -> Vec<Action> {
+        let player = self.player();
+        match item {
+            Pending::Tag(tag) => self.tagActions(tag),
+            Pending::Either(choices) => {
+                let mut actions: Vec<Action> = choices.items.iter().flatten().flat_map(|tag| { self.tagActions(*tag) }).collect();
+                Engine::dedup(&mut actions);
+                actions
+            }
+            Pending::BuyCows(cowboys) => {
+                let mut actions: Vec<Action> = self.cows.cows().iter()
+                    .filter(|cow| {
+                        let (price, needed) = Engine::cowPrice(cow);
+                        needed <= cowboys && price <= player.dollars()
+                    })
+                    .map(|cow| { Action::BuyCow(*cow) })
+                    .collect();
+                Engine::dedup(&mut actions);
+                actions
+            }
+            Pending::Discard(_) => {
+                let mut actions: Vec<Action> = player.deck().hand().iter()
+                    .map(|card| { Action::DiscardCard(*card) })
+                    .collect();
+                Engine::dedup(&mut actions);
+                actions
+            }
+            Pending::RemoveDisc { advanced } => {
+                player.board().discSlots(advanced).iter()
+                    .map(|slot| { Action::RemoveDisc(Some(*slot)) })
+                    .collect()
+            }
+            Pending::UpgradeStation { from, to } => {
+                (0..self.station_track.numStations())
+                    .filter(|i| {
+                        let station = self.station_track.station(*i);
+                        from < station.location() && station.location() <= to
+                            && !station.hasDisc(self.id())
+                            && station.price() <= player.dollars()
+                            && !player.board().discSlots(station.advancedDisc()).is_empty()
+                    })
+                    .map(|i| { Action::UpgradeStation(i as u32) })
+                    .collect()
+            }
+        }
+    }
+
+    fn handCows(&self) -> Vec<Cow> {
+        self.player().deck().hand().iter()
+            .filter_map(|card| { if let Card::CowCard(cow) = card { Some(*cow) } else { None } })
+            .collect()
+    }
+
+    fn tagActions(&self, tag: ActionTag) -> Vec<Action> {
+        let player = self.player();
+        let mut actions = match tag {
+            ActionTag::DiscardCard(DiscardCardOpts::AnyCow) => {
+                self.handCows().iter().map(|cow| { Action::SellCow(*cow) }).collect()
+            }
+            ActionTag::DiscardCard(DiscardCardOpts::Color(color)) => {
+                self.handCows().iter()
+                    .filter(|cow| { cow.color() == color })
+                    .map(|cow| { Action::SellCow(*cow) })
+                    .collect()
+            }
+            ActionTag::DiscardCard(DiscardCardOpts::PairCow) => {
+                let cows = self.handCows();
+                player.deck().pairInHand().iter()
+                    .map(|color| {
+                        let pair: Vec<&Cow> = cows.iter().filter(|cow| { cow.color() == *color }).take(2).collect();
+                        Action::SellCowPair(*pair[0], *pair[1])
+                    })
+                    .collect()
+            }
+            ActionTag::DiscardCard(DiscardCardOpts::Objective) => {
+                player.deck().hand().iter()
+                    .filter(|card| { matches!(card, Card::ObjectiveCard(_)) })
+                    .map(|card| { Action::DiscardCard(*card) })
+                    .collect()
+            }
+            ActionTag::TrashCard => {
+                player.deck().hand().iter().map(|card| { Action::TrashCard(Some(*card)) }).collect()
+            }
+            ActionTag::MoveCattleman(v) | ActionTag::TeleportCattleman(v) => {
+                self.reachable(player.cattleManPos(), self.value(v).max(0) as u32).iter()
+                    .map(|(space, _)| { Action::MoveCattleman(Some(*space as i32)) })
+                    .collect()
+            }
+            ActionTag::MoveEngine(v) => {
+                let pos = self.station_track.engineLocation(self.id()) as i32;
+                let end = self.station_track.end() as i32;
+                let distance = self.value(v);
+                let range = if distance >= 0 { 1..=distance.min(end - pos) } else { distance.max(-pos)..=-1 };
+                range.map(|k| { Action::MoveEngine(Some(k)) }).collect()
+            }
+            ActionTag::TakeTepee => {
+                [Tepee::Green, Tepee::Blue].iter()
+                    .filter(|tepee| {
+                        self.bestTepee(**tepee)
+                            .map(|(_, value)| { value >= 0 || value.unsigned_abs() <= player.dollars() })
+                            .unwrap_or(false)
+                    })
+                    .map(|tepee| { Action::TakeTepee(Some(*tepee)) })
+                    .collect()
+            }
+            ActionTag::TakeHazard => {
+                self.track.iter()
+                    .filter_map(|space| {
+                        if let SpaceOccupant::Hazard(_, Some(h)) = space.occupant() { Some(Action::TakeHazard(Some(*h))) } else { None }
+                    })
+                    .collect()
+            }
+            ActionTag::TakeObjective => {
+                self.objective_market.iter().map(|obj| { Action::TakeObjective(Some(*obj)) }).collect()
+            }
+            ActionTag::HireEmployee(v) => {
+                [Employee::Cowboy, Employee::Craftsman, Employee::Engineer].iter()
+                    .filter(|emp| {
+                        player.hired(**emp) < Player::MAX_EMPLOYEES
+                            && self.hireCost(**emp, v).map(|(_, cost)| { cost <= player.dollars() }).unwrap_or(false)
+                    })
+                    .map(|emp| { Action::HireEmployee(Some(*emp)) })
+                    .collect()
+            }
+            ActionTag::PlaceBuilding(v) => self.buildingPlacements(v),
+            ActionTag::BuyCows => self.pendingActions(Pending::BuyCows(player.hired(Employee::Cowboy))),
+            ActionTag::Auxiliary => self.auxiliaryActions(false),
+            ActionTag::DoubleAuxiliary => self.auxiliaryActions(true),
+            _ => vec![],
+        };
+        Engine::dedup(&mut actions);
+        actions
+    }
+
+    // Value 3 cows take one cowboy, the rarer ones more
+    fn cowPrice(cow: &Cow) -> (u32, u32) {
+        match cow.value() {
+            4 => (12, 2),
+            5 => (12, 3),
+            _ => (6, 1),
+        }
+    }
+
+    // The space holding the most valuable tepee of a color, with its value
+    fn bestTepee(&self, color: Tepee) -> Option<(usize, i32)> {
+        self.track.iter()
+            .enumerate()
+            .filter_map(|(i, space)| {
+                match space.occupant() {
+                    SpaceOccupant::Tepee(value, Some(t)) if *t == color => Some((i, *value)),
+                    _ => None,
+                }
+            })
+            .max_by_key(|(_, value)| { *value })
+    }
+
+    // The cheapest salary on the job market for an employee and what hiring them costs
+    fn hireCost(&self, emp: Employee, v: ActionValues) -> Option<(i32, u32)> {
+        self.job_market.findEmployees(emp).first()
+            .map(|(salary, _)| { (*salary, (*salary - self.value(v)).max(0) as u32) })
+    }
+
+    // ActionValues::EmployeeMult(Craftsman, -2) costs 2 dollars for each laborer the building needs
+    fn buildingCost(&self, building: &Building, v: ActionValues) -> u32 {
+        let per_laborer = match v {
+            ActionValues::EmployeeMult(_, mult) | ActionValues::Exact(mult) => mult.unsigned_abs(),
+            _ => 2,
+        };
+        per_laborer * building.laborers()
+    }
+
+    // Building on a risk space also costs its risk action, the cheapest matching cow in hand is discarded
+    fn riskCost(&self, space: usize) -> Option<(Option<Cow>, u32)> {
+        let risk = match self.track[space].occupant() {
+            SpaceOccupant::Building { risk_action, .. } => risk_action,
+            _ => return None,
+        };
+        let mut cow = None;
+        let mut dollars = 0;
+        for tag in risk.items.iter().flatten() {
+            match tag {
+                ActionTag::DiscardCard(DiscardCardOpts::Color(c)) => cow = Some(self.player().deck().cowInHand(*c)?),
+                ActionTag::DiscardCard(_) => cow = Some(*self.handCows().iter().min_by_key(|c| { c.value() })?),
+                ActionTag::TakeCoins(v) => dollars += self.value(*v).unsigned_abs(),
+                _ => {}
+            }
+        }
+        Some((cow, dollars))
+    }
+
+    fn buildingPlacements(&self, v: ActionValues) -> Vec<Action> {
+        let player = self.player();
+        let spaces: Vec<usize> = self.track.iter()
+            .enumerate()
+            .filter(|(_, space)| { matches!(space.occupant(), SpaceOccupant::Building { building: None, neutral: false, .. }) })
+            .map(|(i, _)| { i })
+            .collect();
+        let mut actions = Vec::<Action>::new();
+        for building in player.buildings() {
+            if building.laborers() > player.hired(Employee::Craftsman) {
+                continue;
+            }
+            let cost = self.buildingCost(building, v);
+            for space in spaces.iter() {
+                if let Some((_, risk)) = self.riskCost(*space) {
+                    if cost + risk <= player.dollars() {
+                        actions.push(Action::PlaceBuilding(Some(*space as u32), Some(*building)));
+                    }
+                }
+            }
+        }
+        actions
+    }
+
+    fn canDeliver(&self, city: usize, value: u32) -> bool {
+        let c = &self.station_track.cities().cities()[city];
+        if c.location() > value || (c.limited() && c.discs(self.id()) > 0) {
+            return false;
+        }
+        if c.location() == 0 {
+            return true;
+        }
+        self.transportCost(city) <= self.player().dollars()
+            && !self.player().board().discSlots(c.advancedDisc()).is_empty()
+    }
+
+    // Every crossing between the engine and the city costs the same
+    fn transportCost(&self, city: usize) -> u32 {
+        let engine = self.station_track.engineLocation(self.id());
+        let location = self.station_track.cities().cities()[city].location();
+        let crossings = self.station_track.crossings().iter()
+            .filter(|c| { engine < **c && **c <= location })
+            .count() as u32;
+        crossings * Engine::TRANSPORT_COST_PER_CROSSING
+    }
+
+    fn resolve(&mut self, action: Action, pending: Option<Pending>) -> Result<(), GwtError> {
+        let id = self.id();
+        let deck_err = |e| { GwtError::deck(id, e) };
+        match action {
+            Action::Pass => {}
+            Action::MoveCattleman(Some(space)) => {
+                let from = self.player().cattleManPos();
+                match pending {
+                    Some(Pending::Tag(ActionTag::TeleportCattleman(_))) => {}
+                    Some(Pending::Tag(ActionTag::MoveCattleman(v))) => {
+                        let steps = self.value(v).max(0) as u32;
+                        self.payTolls(from, space as usize, steps)?;
+                    }
+                    _ => {
+                        let steps = self.player().board().stepLimit();
+                        self.payTolls(from, space as usize, steps)?;
+                    }
+                }
+                self.moveCattleman(space as usize);
+            }
+            Action::UseBuilding(or, xor) => {
+                if let Phase::Location { used, .. } = &mut self.phase {
+                    used[or as usize] = true;
+                }
+                let building = self.usableBuilding().ok_or(GwtError::illegal(action))?;
+                self.emit(GameEvent::BuildingUsed { player: id, building, space: self.player().cattleManPos() });
+                let option = building.actions().items[or as usize]
+                    .and_then(|option| { option.items[xor as usize] })
+                    .ok_or(GwtError::illegal(action))?;
+                let group: Vec<Pending> = option.items.iter().flatten().map(|tag| { Pending::Tag(*tag) }).collect();
+                self.pending.push(group);
+            }
+            Action::Auxiliary1(idx) | Action::Auxiliary2(idx) => {
+                let double = matches!(action, Action::Auxiliary2(_));
+                if let Phase::Location { auxiliary, .. } = &mut self.phase {
+                    if pending.is_none() {
+                        *auxiliary = true;
+                    }
+                }
+                let cost = PlayerBoard::auxCost(idx as usize, double);
+                self.playerMut().pay(cost)?;
+                if cost > 0 {
+                    self.emit(GameEvent::AuxiliaryPaid { player: id, amount: cost });
+                }
+                let (single, double_action) = self.player().board().auxAction(idx as usize).availableActions();
+                let group = match (double, single, double_action) {
+                    // Actions without a bigger double version are done twice
+                    (true, Some(single), Some(double_action)) if single == double_action =>
+                        vec![Pending::Tag(single), Pending::Tag(single)],
+                    (true, _, Some(double_action)) => vec![Pending::Tag(double_action)],
+                    (_, Some(single), _) => vec![Pending::Tag(single)],
+                    _ => vec![],
+                };
+                if !group.is_empty() {
+                    self.pending.push(group);
+                }
+            }
+            Action::PlayObjective(objective) => {
+                self.playerMut().playObjective(objective)?;
+                self.emit(GameEvent::ObjectivePlayed { player: id, objective });
+                if let Some(tag) = objective.immediate() {
+                    self.pending.push(vec![Pending::Tag(tag)]);
+                }
+            }
+            Action::FillHand => {
+                self.playerMut().refillHand();
+                self.pending.clear();
+                self.phase = Phase::Move;
+                self.endTurn();
+            }
+            Action::SellCow(cow) => {
+                self.playCard(Card::CowCard(cow))?;
+            }
+            Action::SellCowPair(first, second) => {
+                self.playCard(Card::CowCard(first))?;
+                self.playCard(Card::CowCard(second))?;
+            }
+            Action::DiscardCard(card) => {
+                self.playCard(card)?;
+                if let Some(Pending::Discard(n)) = pending {
+                    if n > 1 {
+                        self.pending.push(vec![Pending::Discard(n - 1)]);
+                    }
+                }
+            }
+            Action::TrashCard(Some(card)) => {
+                self.playerMut().deckMut().trashCard(card).map_err(deck_err)?;
+                self.emit(GameEvent::CardTrashed { player: id, card });
+            }
+            Action::MoveEngine(Some(distance)) => {
+                let from = self.station_track.engineLocation(id);
+                let to = (from as i32 + distance) as u32;
+                let space = self.station_track.engineSpace(id);
+                self.touch(Part::StationTrack);
+                let end = self.station_track.moveEngine(id, EngineSpace::MainTrack(to as usize));
+                self.record(Change::EngineMoved(id, space));
+                if let Some((first, second)) = end {
+                    self.pending.push(vec![Pending::Tag(first), Pending::Tag(second)]);
+                }
+                self.emit(GameEvent::EngineMoved { player: id, from, to });
+                if distance > 0 {
+                    self.pending.push(vec![Pending::UpgradeStation { from, to }]);
+                }
+            }
+            Action::UpgradeStation(station) => {
+                // Nothing changes until the station and the price have been checked
+                if station as usize >= self.station_track.numStations() {
+                    return Err(GwtError::InvalidStation(station as usize));
+                }
+                let price = self.station_track.station(station as usize).price();
+                let advanced = self.station_track.station(station as usize).advancedDisc();
+                let available = self.player().dollars();
+                if price > available {
+                    return Err(GwtError::InsufficientFunds { player: id, required: price, available });
+                }
+                self.playerMut().pay(price)?;
+                self.touch(Part::StationTrack);
+                let station_master = self.station_track.upgradeStation(id, station as usize)?;
+                self.record(Change::StationUpgraded(id, station as usize, station_master));
+                self.emit(GameEvent::StationUpgraded { player: id, station: station as usize, price });
+                self.pending.push(vec![Pending::RemoveDisc { advanced }]);
+                if let Some(station_master) = station_master {
+                    self.playerMut().takeStationMaster(station_master);
+                    self.emit(GameEvent::StationMasterTaken { player: id, station_master });
+                    let bonus = *station_master.bonus();
+                    match bonus.items {
+                        [Some(tag), None] | [None, Some(tag)] => self.pending.push(vec![Pending::Tag(tag)]),
+                        [Some(_), Some(_)] => self.pending.push(vec![Pending::Either(bonus)]),
+                        [None, None] => {}
+                    }
+                }
+            }
+            Action::RemoveDisc(Some(slot)) => {
+                self.playerMut().removeDisc(slot)?;
+                self.emit(GameEvent::DiscRemoved { player: id, slot });
+            }
+            Action::TakeTepee(Some(tepee)) => {
+                let (space, value) = self.bestTepee(tepee).ok_or(GwtError::illegal(action))?;
+                self.touch(Part::Space(space));
+                self.track[space].takeTepee();
+                if value >= 0 {
+                    self.playerMut().earn(value as u32);
+                } else {
+                    self.playerMut().pay(value.unsigned_abs())?;
+                }
+                self.playerMut().takeTepee(tepee);
+                self.emit(GameEvent::TepeeTaken { player: id, tepee, dollars: value });
+            }
+            Action::TakeHazard(Some(hazard)) => {
+                let space = self.track.iter()
+                    .position(|s| { *s.occupant() == SpaceOccupant::Hazard(hazard.area(), Some(hazard)) })
+                    .ok_or(GwtError::illegal(action))?;
+                self.touch(Part::Space(space));
+                self.track[space].takeHazard();
+                self.playerMut().takeHazard(hazard);
+                self.emit(GameEvent::HazardTaken { player: id, hazard });
+            }
+            Action::TakeObjective(Some(objective)) => {
+                let idx = self.objective_market.iter()
+                    .position(|o| { *o == objective })
+                    .ok_or(GwtError::illegal(action))?;
+                self.touch(Part::Objectives);
+                self.objective_market.remove(idx);
+                self.record(Change::ObjectiveTaken(idx, objective));
+                self.playerMut().deckMut().addCard(Card::ObjectiveCard(objective));
+                self.emit(GameEvent::ObjectiveTaken { player: id, objective });
+                self.refillObjectives();
+            }
+            Action::HireEmployee(Some(emp)) => {
+                let v = match pending {
+                    Some(Pending::Tag(ActionTag::HireEmployee(v))) => v,
+                    _ => return Err(GwtError::illegal(action)),
+                };
+                let (salary, cost) = self.hireCost(emp, v).ok_or(GwtError::illegal(action))?;
+                self.playerMut().pay(cost)?;
+                self.touch(Part::JobMarket);
+                let slot = self.job_market.hire(emp, salary)?;
+                self.record(Change::EmployeeHired(slot, emp));
+                self.playerMut().hire(emp);
+                self.emit(GameEvent::EmployeeHired { player: id, employee: emp, cost });
+            }
+            Action::PlaceBuilding(Some(space), Some(building)) => {
+                let v = match pending {
+                    Some(Pending::Tag(ActionTag::PlaceBuilding(v))) => v,
+                    _ => return Err(GwtError::illegal(action)),
+                };
+                let (cow, risk) = self.riskCost(space as usize).ok_or(GwtError::illegal(action))?;
+                if let Some(cow) = cow {
+                    self.playCard(Card::CowCard(cow))?;
+                }
+                let cost = self.buildingCost(&building, v) + risk;
+                self.playerMut().pay(cost)?;
+                self.playerMut().placeBuilding(building, space as usize)?;
+                self.touch(Part::Space(space as usize));
+                self.track[space as usize].placeBuilding(building);
+                self.emit(GameEvent::BuildingPlaced { player: id, building, space: space as usize, cost });
+            }
+            Action::BuyCow(cow) => {
+                let cowboys = match pending {
+                    Some(Pending::BuyCows(cowboys)) => cowboys,
+                    Some(Pending::Tag(ActionTag::BuyCows)) => self.player().hired(Employee::Cowboy),
+                    _ => return Err(GwtError::illegal(action)),
+                };
+                let (price, needed) = Engine::cowPrice(&cow);
+                self.playerMut().pay(price)?;
+                self.touch(Part::Cows);
+                if let Some(idx) = self.cows.take(cow) {
+                    self.record(Change::CowBought(idx, cow));
+                }
+                self.playerMut().deckMut().addCard(Card::CowCard(cow));
+                self.emit(GameEvent::CowBought { player: id, cow, price });
+                if cowboys > needed {
+                    self.pending.push(vec![Pending::BuyCows(cowboys - needed)]);
+                }
+            }
+            Action::ChooseTile(col, row) => {
+                let refilled = self.foresight.pileSizes()[col as usize] > 0;
+                self.touch(Part::Foresight);
+                let tile = self.foresight.takeTile(col as usize, row as usize);
+                self.record(Change::TileTaken { column: col as usize, row: row as usize, tile, refilled });
+                self.emit(GameEvent::TileTaken { player: id, column: col as usize, row: row as usize, tile });
+                self.emit(GameEvent::TileDrawn { column: col as usize, tile: self.foresight.next()[col as usize] });
+                self.placeTile(tile);
+                self.phase = if (col as usize) + 1 < Foresight::FORESIGHT_SIZE {
+                    Phase::KansasCity(KansasCityStep::Foresight(col + 1))
+                } else {
+                    Phase::KansasCity(KansasCityStep::Income)
+                };
+            }
+            Action::SellHand => {
+                let value = self.player().deck().handValue() + self.player().certificates();
+                self.playerMut().earn(value);
+                self.playerMut().spendCertificates();
+                self.emit(GameEvent::HandSold { player: id, value });
+                self.phase = Phase::KansasCity(KansasCityStep::Delivery { value });
+            }
+            Action::PlaceDisc(Some(city)) => {
+                self.deliver(city as usize)?;
+                self.playerMut().deckMut().discardHand();
+                let start = self.track.iter().position(|s| { *s.occupant() == SpaceOccupant::Start }).unwrap_or(0);
+                self.moveCattlemanTo(start);
+                self.phase = Phase::KansasCity(KansasCityStep::Done);
+            }
+            _ => return Err(GwtError::illegal(action)),
+        }
+        Ok(())
+    }
+
+    // Hazards and tepees go on the first empty space that takes them, there's nowhere for them otherwise
+    fn placeTile(&mut self, tile: Tile) {
+        match tile {
+            Tile::EmployeeTile(emp) => {
+                self.addEmployee(emp);
+            }
+            Tile::HazardTile(hazard) => {
+                for i in 0..self.track.len() {
+                    let mut space = self.track[i];
+                    if space.placeHazard(hazard) {
+                        self.touch(Part::Space(i));
+                        self.track[i] = space;
+                        self.emit(GameEvent::HazardPlaced { hazard, space: i });
+                        break;
+                    }
+                }
+            }
+            Tile::TepeeTile(tepee) => {
+                for i in 0..self.track.len() {
+                    let mut space = self.track[i];
+                    if space.placeTepee(tepee) {
+                        self.touch(Part::Space(i));
+                        self.track[i] = space;
+                        self.emit(GameEvent::TepeePlaced { tepee, space: i });
+                        break;
+                    }
+                }
+            }
+            Tile::BlankTile => {}
+        }
+    }
+
+    // Kansas City only pays out, the other cities take a disc and the transport costs
+    fn deliver(&mut self, city: usize) -> Result<(), GwtError> {
+        let id = self.id();
+        let (location, coins, advanced) = {
+            let c = &self.station_track.cities().cities()[city];
+            (c.location(), c.coins(), c.advancedDisc())
+        };
+        if location == 0 {
+            self.playerMut().earn(coins);
+            self.emit(GameEvent::CoinsTaken { player: id, amount: coins as i32 });
+            return Ok(());
+        }
+        let cost = self.transportCost(city);
+        self.playerMut().pay(cost)?;
+        self.emit(GameEvent::DiscPlaced { player: id, city, cost });
+        let first_disc = self.station_track.cities().cities()[city].discs(id) == 0;
+        self.touch(Part::StationTrack);
+        self.station_track.makeDelivery(id, city as u32);
+        self.record(Change::Delivered(id, city as u32));
+        self.pending.push(vec![Pending::RemoveDisc { advanced }]);
+        if first_disc {
+            let cities = self.station_track.cities();
+            let pairs = cities.pairActions();
+            for pair in [city.wrapping_sub(1), city] {
+                if pair < pairs.len() && pair + 1 < cities.cities().len()
+                    && pairs[pair].takesObjective()
+                    && cities.cities()[pair].discs(id) > 0 && cities.cities()[pair + 1].discs(id) > 0 {
+                    self.pending.push(vec![Pending::Tag(ActionTag::TakeObjective)]);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn refillObjectives(&mut self) {
+        self.touch(Part::Objectives);
+        let mut dealt = 0;
+        while self.objective_market.len() < Engine::OBJECTIVE_MARKET_SIZE {
+            match self.objectives.pop() {
+                Some(objective) => self.objective_market.push(objective),
+                None => break,
+            }
+            dealt += 1;
+        }
+        if dealt > 0 {
+            self.record(Change::ObjectivesDealt(dealt));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::buildings::{Hazard, HazardType, Toll};
+    use crate::data::GameData;
+    use crate::deck::CowColor;
+
+    use super::*;
+
+    #[test]
+    fn testOpeningMoves() {
+        let data = GameData::embedded().unwrap();
+        let mut engine = Engine::newGame(&data, 2, 5).unwrap();
+        assert_eq!(engine.phase(), Phase::Move);
+        // Only the neutral buildings are stops at the start, and no tolls are owed for them
+        let moves = engine.reachable(0, 3);
+        assert!(moves.iter().all(|(space, toll)| { engine.track[*space].isStop() && *toll == 0 }));
+        assert!(!engine.legalActions().is_empty());
+        assert_eq!(engine.applyAction(Action::SellHand), Err(GwtError::illegal(Action::SellHand)));
+
+        let (space, _) = moves[0];
+        engine.applyAction(Action::MoveCattleman(Some(space as i32))).unwrap();
+        assert_eq!(engine.phase(), Phase::Location { used: [false; 3], auxiliary: false });
+        assert!(engine.legalActions().contains(&Action::Auxiliary1(0)));
+        engine.applyAction(Action::Auxiliary1(0)).unwrap();
+        assert_eq!(engine.players()[0].dollars(), 7);
+        assert_eq!(engine.legalActions(), vec![Action::FillHand]);
+        engine.applyAction(Action::FillHand).unwrap();
+        assert_eq!(engine.currentPlayer(), 1);
+        assert_eq!(engine.phase(), Phase::Move);
+    }
+
+    #[test]
+    fn testDrawAndDiscard() {
+        let data = GameData::embedded().unwrap();
+        let mut engine = Engine::newGame(&data, 2, 9).unwrap();
+        let (space, _) = engine.reachable(0, 3)[0];
+        engine.applyAction(Action::MoveCattleman(Some(space as i32))).unwrap();
+        engine.applyAction(Action::Auxiliary1(1)).unwrap();
+        // The card drawn has to be discarded again before anything else
+        assert_eq!(engine.pending(), &vec![vec![Pending::Discard(1)]]);
+        assert_eq!(engine.players()[0].deck().hand().len(), 5);
+        let discard = engine.legalActions()[0];
+        assert!(!engine.legalActions().contains(&Action::Pass));
+        engine.applyAction(discard).unwrap();
+        assert!(engine.pending().is_empty());
+        assert_eq!(engine.players()[0].deck().hand().len(), 4);
+    }
+    #[test]
+    fn testStationMasterBonus() {
+        let data = GameData::embedded().unwrap();
+        let mut engine = Engine::newGame(&data, 2, 3).unwrap();
+        // Station 0's master offers a tepee or a hazard
+        let station_master = data.station_masters[3];
+        engine.station_track.placeStationMaster(0, station_master);
+        engine.placeTile(Tile::HazardTile(Hazard::new(HazardType::Flood, Toll::Green, 2)));
+        engine.placeTile(Tile::TepeeTile(Tepee::Green));
+        engine.players[0].earn(20);
+        let to = engine.station_track.station(0).location();
+        engine.pending = vec![vec![Pending::UpgradeStation { from: 0, to }]];
+
+        // A failed action leaves the engine as it was, even without history
+        let before = engine.clone();
+        assert_eq!(engine.attempt(Action::UpgradeStation(99)), Err(GwtError::InvalidStation(99)));
+        assert_eq!(engine, before);
+
+        engine.applyAction(Action::UpgradeStation(0)).unwrap();
+        let tepees = engine.tagActions(ActionTag::TakeTepee);
+        let hazards = engine.tagActions(ActionTag::TakeHazard);
+        assert!(!tepees.is_empty() && !hazards.is_empty());
+        let mut expected = [tepees, hazards].concat();
+        expected.push(Action::Pass);
+        assert_eq!(engine.legalActions(), expected);
+
+        let hazard = *expected.iter().find(|a| { matches!(a, Action::TakeHazard(_)) }).unwrap();
+        engine.applyAction(hazard).unwrap();
+        assert_eq!(engine.players[0].hazards().len(), 1);
+        assert!(matches!(engine.pending()[..], [ref group] if group[0] == Pending::RemoveDisc { advanced: false }));
+    }
+
+    #[test]
+    fn testFailedActionTakenBack() {
+        let data = GameData::embedded().unwrap();
+        let mut engine = Engine::newGame(&data, 2, 3).unwrap();
+        let cow = engine.players[0].deck().hand().iter()
+            .find_map(|card| { if let Card::CowCard(cow) = card { Some(*cow) } else { None } })
+            .unwrap();
+        engine.keepHash(true);
+
+        // The first cow is played before the second turns out not to be in the hand
+        let before = engine.clone();
+        assert!(engine.attempt(Action::SellCowPair(cow, Cow::new(CowColor::Swiss, 3))).is_err());
+        assert_eq!(engine, before);
+        assert_eq!(engine.zobrist(), before.zobrist());
+    }
+}
@@ -0,0 +1,66 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{Engine, GameSetup, GameState};
+use crate::actions::Action;
+use crate::agent::Agent;
+use crate::data::GameData;
+use crate::error::GwtError;
+use crate::record::GameRecord;
+use crate::scoring::ScoreSheet;
+use crate::view::PlayerView;
+
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+pub struct GameResult {
+    pub seed: u64,
+    pub scores: Vec<ScoreSheet>,
+    pub winners: Vec<u32>,
+    // Every action taken, in order, and the seat which took it
+    pub actions: Vec<Action>,
+    pub seats: Vec<u32>,
+    pub turns: u32,
+}
+
+impl GameResult {
+    pub fn record(&self, data: &GameData) -> GameRecord {
+        let mut record = GameRecord::new(data, GameSetup::new(self.scores.len() as u32, self.seed));
+        record.actions = self.seats.iter().copied().zip(self.actions.iter().copied()).collect();
+        record.scores = Some(self.scores.clone());
+        record
+    }
+}
+
+// Games are stopped after this many actions, in case agents manage to stall forever
+pub const MAX_ACTIONS: u32 = 100_000;
+
+// Plays a game with one agent per seat, the same seed and agents always play the same game
+pub fn playGame(data: &GameData, agents: &mut [Box<dyn Agent>], seed: u64) -> Result<GameResult, GwtError> {
+    let mut engine = Engine::newGame(data, agents.len() as u32, seed)?;
+    let mut actions = Vec::<Action>::new();
+    let mut seats = Vec::<u32>::new();
+    let mut turns = 0;
+    while !engine.isGameOver() {
+        if actions.len() as u32 >= MAX_ACTIONS {
+            return Err(GwtError::TurnLimit(MAX_ACTIONS));
+        }
+        let seat = engine.currentPlayer();
+        let legal = engine.legalActions();
+        let action = agents[seat].choose(&PlayerView::new(&engine, seat as u32), &legal);
+        engine.applyAction(action)?;
+        if action == Action::FillHand {
+            turns += 1;
+        }
+        actions.push(action);
+        seats.push(seat as u32);
+    }
+    match engine.state() {
+        GameState::GameOver { scores, winners } => Ok(GameResult {
+            seed,
+            scores: scores.clone(),
+            winners: winners.clone(),
+            actions,
+            seats,
+            turns,
+        }),
+        _ => unreachable!(),
+    }
+}
@@ -0,0 +1,211 @@
+// Saved games are {"version": n, "engine": {...}}, saves from before versioning are the bare engine and count as
+// version 1. The envelope may also hold the game's SaveContext, what the engine doesn't know about the game it's in.
+// An older save is read into the structs of its own version, EngineV1 and so on, then upgraded one version at a time
+// until it's the current Engine. Changing how Engine or anything in it serializes means bumping SAVE_VERSION, keeping
+// the previous layout as structs of that version with the upgrade from them, and a fixture in tests/fixtures saved
+// at the previous version. Saves are never read through a serde_json Value, whose numbers can't hold the players'
+// u128 RNG states
+//
+//   2: Player's cattleManPos, playedObjectives and playedBuildings became snake case
+
+use std::fs::{read_to_string, write};
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::{Engine, GameState};
+use crate::data::GameData;
+use crate::deck::{CowMarket, Objective};
+use crate::error::{DataError, GwtError};
+use crate::player::PlayerV1;
+use crate::rules::{Pending, Phase};
+use crate::tiles::{Foresight, JobMarket};
+use crate::tracks::{Space, StationTrack};
+
+pub const SAVE_VERSION: u32 = 2;
+
+// The data the game was set up from and the seed its bots were made with, both left out by Engine::save and of saves
+// from before they were kept
+#[derive(Clone, Default, Debug)]
+pub struct SaveContext {
+    pub data: Option<GameData>,
+    pub seed: Option<u64>,
+}
+
+#[derive(Serialize)]
+struct SavedGame<'a> {
+    version: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    data: &'a Option<GameData>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<u64>,
+    engine: &'a Engine,
+}
+
+#[derive(Deserialize)]
+struct LoadedGame<E> {
+    #[serde(default)]
+    data: Option<GameData>,
+    #[serde(default)]
+    seed: Option<u64>,
+    engine: E,
+}
+
+impl<E> LoadedGame<E> {
+    fn upgrade<F>(self, upgrade: fn(E) -> F) -> LoadedGame<F> {
+        LoadedGame { data: self.data, seed: self.seed, engine: upgrade(self.engine) }
+    }
+}
+
+// The engine as saved at version 1
+#[derive(Deserialize)]
+struct EngineV1 {
+    starting_player: usize,
+    current_player: usize,
+    players: Vec<PlayerV1>,
+    cows: CowMarket,
+    objectives: Vec<Objective>,
+    track: Vec<Space>,
+    station_track: StationTrack,
+    foresight: Foresight,
+    job_market: JobMarket,
+    state: GameState,
+    objective_market: Vec<Objective>,
+    phase: Phase,
+    pending: Vec<Vec<Pending>>,
+}
+
+fn toVersion2(engine: EngineV1) -> Engine {
+    let players = engine.players.into_iter().map(|p| { p.into() }).collect();
+    Engine {
+        starting_player: engine.starting_player,
+        current_player: engine.current_player,
+        cows: engine.cows,
+        objectives: engine.objectives,
+        foresight: engine.foresight,
+        job_market: engine.job_market,
+        state: engine.state,
+        objective_market: engine.objective_market,
+        phase: engine.phase,
+        pending: engine.pending,
+        ..Engine::new(players, engine.track, engine.station_track)
+    }
+}
+
+// A bare engine is a version 1 save without the envelope
+fn read<E: DeserializeOwned>(contents: &str, bare: bool) -> serde_json::Result<LoadedGame<E>> {
+    if bare {
+        Ok(LoadedGame { data: None, seed: None, engine: serde_json::from_str(contents)? })
+    } else {
+        serde_json::from_str(contents)
+    }
+}
+
+// Reads the save into the structs of its version and upgrades it through every version after that
+fn upgrade(version: u32, contents: &str, bare: bool) -> serde_json::Result<LoadedGame<Engine>> {
+    match version {
+        1 => Ok(read::<EngineV1>(contents, bare)?.upgrade(toVersion2)),
+        _ => read::<Engine>(contents, bare),
+    }
+}
+
+// Only the version is read, the rest is skipped over
+#[derive(Deserialize)]
+struct Envelope {
+    version: Option<u32>,
+}
+
+impl Engine {
+    pub fn saveString(&self) -> Result<String, GwtError> {
+        self.saveStringWith(&SaveContext::default())
+    }
+
+    pub fn saveStringWith(&self, context: &SaveContext) -> Result<String, GwtError> {
+        let save = SavedGame { version: SAVE_VERSION, data: &context.data, seed: context.seed, engine: self };
+        serde_json::to_string_pretty(&save).map_err(|e| { GwtError::Output(e.to_string()) })
+    }
+
+    // name is the save's source for errors
+    pub fn loadString(contents: &str, name: &str) -> Result<Engine, GwtError> {
+        Ok(Engine::loadStringWith(contents, name)?.0)
+    }
+
+    pub fn loadStringWith(contents: &str, name: &str) -> Result<(Engine, SaveContext), GwtError> {
+        let schema = |e: serde_json::Error| {
+            DataError::Schema { file: name.to_string(), line: e.line(), column: e.column(), message: e.to_string() }
+        };
+        let envelope: Envelope = serde_json::from_str(contents).map_err(schema)?;
+        let version = envelope.version.unwrap_or(1);
+        if version == 0 || version > SAVE_VERSION {
+            let message = format!("Saved with version {}, only versions up to {} can be loaded", version, SAVE_VERSION);
+            return Err(DataError::Invalid { file: name.to_string(), message }.into());
+        }
+        let loaded = upgrade(version, contents, envelope.version.is_none()).map_err(schema)?;
+        Ok((loaded.engine, SaveContext { data: loaded.data, seed: loaded.seed }))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), GwtError> {
+        self.saveWith(path, &SaveContext::default())
+    }
+
+    pub fn saveWith(&self, path: &Path, context: &SaveContext) -> Result<(), GwtError> {
+        write(path, self.saveStringWith(context)?).map_err(|e| { GwtError::Output(e.to_string()) })
+    }
+
+    pub fn load(path: &Path) -> Result<Engine, GwtError> {
+        Ok(Engine::loadWith(path)?.0)
+    }
+
+    pub fn loadWith(path: &Path) -> Result<(Engine, SaveContext), GwtError> {
+        let file = path.display().to_string();
+        let contents = read_to_string(path).map_err(|e| { DataError::Io { file: file.clone(), message: e.to_string() } })?;
+        Engine::loadStringWith(&contents, &file)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::agent::testing::playRandom;
+    use crate::data::GameData;
+
+    use super::*;
+
+    // The game every fixture saved: two players on seed 11 after 40 random actions
+    fn fixtureGame() -> Engine {
+        let data = GameData::embedded().unwrap();
+        let mut engine = Engine::newGame(&data, 2, 11).unwrap();
+        playRandom(&mut engine, 11, 40, |_| {});
+        engine
+    }
+
+    #[test]
+    fn testFixtures() {
+        let engine = fixtureGame();
+        for version in 1..=SAVE_VERSION {
+            let path = format!("./tests/fixtures/save_v{}.json", version);
+            assert_eq!(Engine::load(Path::new(&path)).unwrap(), engine, "{}", path);
+        }
+        assert_eq!(Engine::loadString(&engine.saveString().unwrap(), "test").unwrap(), engine);
+
+        // The context comes back as it was saved, and a bare engine has none
+        let data = GameData::embedded().unwrap();
+        let context = SaveContext { data: Some(data.clone()), seed: Some(11) };
+        let (loaded, loaded_context) = Engine::loadStringWith(&engine.saveStringWith(&context).unwrap(), "test").unwrap();
+        assert_eq!(loaded, engine);
+        assert_eq!((loaded_context.data, loaded_context.seed), (Some(data), Some(11)));
+        let bare = Engine::loadWith(Path::new("./tests/fixtures/save_v1.json")).unwrap().1;
+        assert_eq!((bare.data, bare.seed), (None, None));
+
+        // Version 1 is a layout of its own, which the current engine can't read without upgrading it
+        let v1 = read_to_string("./tests/fixtures/save_v1.json").unwrap();
+        assert!(v1.contains("\"cattleManPos\"") && !v1.contains("\"cattleman_pos\""));
+        assert!(serde_json::from_str::<Engine>(&v1).is_err());
+        let enveloped = format!("{{\"version\": 1, \"seed\": 3, \"engine\": {}}}", v1);
+        let (upgraded, upgraded_context) = Engine::loadStringWith(&enveloped, "test").unwrap();
+        assert_eq!((upgraded, upgraded_context.seed), (engine.clone(), Some(3)));
+
+        let newer = engine.saveString().unwrap().replacen(&format!("\"version\": {}", SAVE_VERSION), "\"version\": 99", 1);
+        assert!(matches!(Engine::loadString(&newer, "test"), Err(GwtError::Data(DataError::Invalid { .. }))));
+    }
+}
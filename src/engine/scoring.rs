@@ -0,0 +1,230 @@
+use serde::{Deserialize, Serialize};
+
+use crate::actions::ActionValues;
+use crate::deck::Objective;
+use crate::player::{ObjectiveResources, Player};
+use crate::tracks::{CityTrack, Space, SpaceOccupant, StationMaster, StationTrack};
+
+#[derive(Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+pub struct ScoreSheet {
+    pub player: u32,
+    // 1 point for every 5 dollars
+    pub dollars: i32,
+    // Points printed on the player's buildings placed on the track
+    pub buildings: i32,
+    // Discs delivered to cities, including the bonus for pairs of adjacent cities
+    pub cities: i32,
+    pub stations: i32,
+    // Station masters scoring anything other than certificates
+    pub station_masters: i32,
+    pub cows: i32,
+    // Completed objectives minus the penalties for failed played objectives
+    pub objectives: i32,
+    pub hazards: i32,
+    // The bonus for the player who filled the last job market slot
+    pub job_market: i32,
+    // Station masters scoring certificate pairs
+    pub certificates: i32,
+    // Only compared when the totals are tied, the remaining dollars
+    pub tie_breaker: u32,
+}
+
+impl ScoreSheet {
+    pub const DOLLARS_PER_POINT: u32 = 5;
+    pub const JOB_MARKET_BONUS: i32 = 2;
+
+    pub fn new(player: &Player, track: &[Space], station_track: &StationTrack) -> ScoreSheet {
+        let id = player.id();
+        let placed_buildings: Vec<u32> = track.iter()
+            .filter_map(|space| {
+                match space.occupant() {
+                    SpaceOccupant::Building { building: Some(b), neutral: false, .. } if b.owner() == id => Some(b.points()),
+                    _ => None,
+                }
+            })
+            .collect();
+        let resources = player.objectiveResources(placed_buildings.len() as u32,
+                                                  station_track.cities().discsIn(id, CityTrack::SAN_FRANCISCO),
+                                                  station_track.stationDiscs(id));
+        let optional = player.deck().objectiveCards();
+        let (objectives, completed) = ScoreSheet::bestObjectives(player.playedObjectives(), &optional, resources);
+
+        let mut station_masters = 0;
+        let mut certificates = 0;
+        for sm in player.stationMasters() {
+            let pts = ScoreSheet::stationMasterPoints(sm, player, completed);
+            if let ActionValues::CertificatePairMult(_) = sm.points() {
+                certificates += pts;
+            } else {
+                station_masters += pts;
+            }
+        }
+
+        ScoreSheet {
+            player: id,
+            dollars: (player.dollars() / ScoreSheet::DOLLARS_PER_POINT) as i32,
+            buildings: placed_buildings.iter().sum::<u32>() as i32,
+            cities: station_track.cities().points(id),
+            stations: station_track.stationPoints(id),
+            station_masters,
+            cows: player.deck().cowPoints() as i32,
+            objectives,
+            hazards: player.hazards().iter().map(|h| { h.points() }).sum::<u32>() as i32,
+            job_market: if player.hasJobMarketToken() { ScoreSheet::JOB_MARKET_BONUS } else { 0 },
+            certificates,
+            tie_breaker: player.dollars(),
+        }
+    }
+
+    pub fn total(&self) -> i32 {
+        self.dollars + self.buildings + self.cities + self.stations + self.station_masters + self.cows
+            + self.objectives + self.hazards + self.job_market + self.certificates
+    }
+
+    // The ids of the players with the highest total, ties are broken by the remaining dollars
+    pub fn winners(sheets: &[ScoreSheet]) -> Vec<u32> {
+        let best = sheets.iter()
+            .map(|s| { (s.total(), s.tie_breaker) })
+            .max();
+        sheets.iter()
+            .filter(|s| { Some((s.total(), s.tie_breaker)) == best })
+            .map(|s| { s.player })
+            .collect()
+    }
+
+    fn stationMasterPoints(sm: &StationMaster, player: &Player, completed_objectives: u32) -> i32 {
+        match sm.points() {
+            ActionValues::Exact(v) => v,
+            ActionValues::EmployeeMult(emp, v) => v * player.hired(emp) as i32,
+            ActionValues::AllEmployeesMult(v) => v * player.totalHired() as i32,
+            ActionValues::TepeePairMult(v) => v * player.tepeePairs() as i32,
+            ActionValues::HazardPairMult(v) => v * (player.hazards().len() / 2) as i32,
+            ActionValues::CertificatePairMult(v) => v * (player.certificates() / 2) as i32,
+            ActionValues::ObjectivePairMult(v) => v * (completed_objectives / 2) as i32,
+            _ => 0,
+        }
+    }
+
+    // Played objectives must be attempted, the ones still in the deck only count when completed.
+    // Returns the best objective score and the number of objectives completed to reach it
+    fn bestObjectives(played: &[Objective], optional: &[Objective], resources: ObjectiveResources) -> (i32, u32) {
+        let objectives: Vec<(Objective, bool)> = played.iter()
+            .map(|obj| { (*obj, true) })
+            .chain(optional.iter().map(|obj| { (*obj, false) }))
+            .collect();
+        ScoreSheet::searchObjectives(&objectives, resources)
+    }
+
+    fn searchObjectives(objectives: &[(Objective, bool)], resources: ObjectiveResources) -> (i32, u32) {
+        match objectives.split_first() {
+            None => (0, 0),
+            Some(((obj, mandatory), rest)) => {
+                let (skip_pts, skip_done) = ScoreSheet::searchObjectives(rest, resources);
+                let skipped = if *mandatory {
+                    (skip_pts - obj.failPoints() as i32, skip_done)
+                } else {
+                    (skip_pts, skip_done)
+                };
+                match obj.meetsRequirements(resources) {
+                    Some(remaining) => {
+                        let (pts, done) = ScoreSheet::searchObjectives(rest, remaining);
+                        let completed = (pts + obj.successPoints() as i32, done + 1);
+                        if completed.0 >= skipped.0 { completed } else { skipped }
+                    }
+                    None => skipped,
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+
+    use crate::buildings::{Hazard, HazardType, Tepee, Toll};
+    use crate::deck::{Cow, ObjectiveRequirements};
+    use crate::error::GwtError;
+    use crate::player::Player;
+
+    use super::*;
+
+    fn startingCows() -> Vec<Cow> {
+        let path = Path::new("./data/player_starting_deck.json");
+        serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    fn stationTrack() -> StationTrack {
+        let path = Path::new("./data/default_station_track.json");
+        serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    fn stationMasters() -> Vec<StationMaster> {
+        let path = Path::new("./data/default_station_masters.json");
+        serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn testStartingScore() {
+        let player = Player::new(3, startingCows(), vec![]);
+        let sheet = ScoreSheet::new(&player, &[], &stationTrack());
+        assert_eq!(sheet.dollars, 1);
+        assert_eq!(sheet.tie_breaker, 9);
+        assert_eq!(sheet.total(), 1);
+    }
+
+    #[test]
+    fn testScoreCategories() {
+        let mut player = Player::new(1, startingCows(), vec![]);
+        let mut station_track = stationTrack();
+        // Topeka and Wichita are adjacent, the pair between them takes an objective for -3 points
+        station_track.makeDelivery(1, 1);
+        station_track.makeDelivery(1, 2);
+        station_track.makeDelivery(1, 9);
+        station_track.placeStationDisc(1, 0).unwrap();
+        station_track.placeStationDisc(1, station_track.numStations() - 1).unwrap();
+        assert_eq!(station_track.placeStationDisc(1, 100), Err(GwtError::InvalidStation(100)));
+        player.takeHazard(Hazard::new(HazardType::Flood, Toll::Green, 2));
+        player.takeHazard(Hazard::new(HazardType::Drought, Toll::Black, 3));
+        player.takeTepee(Tepee::Green);
+        player.takeTepee(Tepee::Blue);
+        for sm in stationMasters() {
+            player.takeStationMaster(sm);
+        }
+        player.takeJobMarketToken();
+
+        let sheet = ScoreSheet::new(&player, &[], &station_track);
+        assert_eq!(sheet.cities, 9 - 3);
+        assert_eq!(sheet.stations, 1 + 9);
+        assert_eq!(sheet.hazards, 5);
+        // 1 tepee pair, the 3 starting employees, 1 hazard pair and no objective pairs
+        assert_eq!(sheet.station_masters, 3 + 3 + 3);
+        // 3 permanent certificates is one pair
+        assert_eq!(sheet.certificates, 3);
+        assert_eq!(sheet.job_market, ScoreSheet::JOB_MARKET_BONUS);
+        assert_eq!(sheet.objectives, 0);
+    }
+
+    #[test]
+    fn testObjectiveSearch() {
+        let resources = ObjectiveResources {
+            buildings: 2,
+            hazards: 1,
+            san_fran: 0,
+            green_tepees: 0,
+            blue_tepees: 0,
+            station_discs: 0,
+            ryb_cows: 0,
+            brown_cows: 0,
+            purple_cows: 0,
+        };
+        let two_buildings = Objective::new(None, 3, 2, &[ObjectiveRequirements::Building, ObjectiveRequirements::Building]).unwrap();
+        let building_hazard = Objective::new(None, 5, 2, &[ObjectiveRequirements::Building, ObjectiveRequirements::Hazard]).unwrap();
+        let san_fran = Objective::new(None, 5, 3, &[ObjectiveRequirements::SanFran]).unwrap();
+        // The optional objective is worth more, so the played one is failed instead
+        assert_eq!(ScoreSheet::bestObjectives(&[two_buildings], &[building_hazard], resources), (5 - 2, 1));
+        assert_eq!(ScoreSheet::bestObjectives(&[two_buildings, san_fran], &[], resources), (3 - 3, 1));
+        assert_eq!(ScoreSheet::bestObjectives(&[], &[san_fran], resources), (0, 0));
+    }
+}
@@ -1,12 +1,15 @@
+use rand::Rng;
+use rand::prelude::SliceRandom;
 use serde::{Deserialize, Serialize};
 
-use crate::buildings::{Hazard, HazardType, Tepee, Toll};
+use crate::buildings::{Hazard, Tepee};
+use crate::error::JobMarketError;
 use crate::player::Employee;
 
 // https://github.com/rust-lang/rust/issues/83574
 // use std::iter::zip;
 
-#[derive(Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize, Debug)]
 pub enum Tile {
     EmployeeTile(Employee),
     HazardTile(Hazard),
@@ -22,10 +25,54 @@ pub struct Foresight {
 }
 
 impl Foresight {
-    const FORESIGHT_SIZE: usize = 3;
+    pub const FORESIGHT_SIZE: usize = 3;
 
-    pub fn new() -> Foresight {
-        Foresight { current: [Tile::BlankTile; 3], next: [Tile::BlankTile; 3], piles: Foresight::defaultTilePiles() }
+    // Deals the first two rows of tiles from the piles, which should already be shuffled
+    pub fn new(piles: [Vec<Tile>; Foresight::FORESIGHT_SIZE]) -> Foresight {
+        let mut foresight = Foresight { current: [Tile::BlankTile; 3], next: [Tile::BlankTile; 3], piles };
+        foresight.nextTiles();
+        foresight.nextTiles();
+        foresight
+    }
+
+    pub fn current(&self) -> &[Tile; Foresight::FORESIGHT_SIZE] { &self.current }
+
+    pub fn next(&self) -> &[Tile; Foresight::FORESIGHT_SIZE] { &self.next }
+
+    pub(crate) fn piles(&self) -> &[Vec<Tile>; Foresight::FORESIGHT_SIZE] { &self.piles }
+
+    pub fn pileSizes(&self) -> [usize; Foresight::FORESIGHT_SIZE] {
+        [self.piles[0].len(), self.piles[1].len(), self.piles[2].len()]
+    }
+
+    // Takes the tile from the current (row 0) or next (row 1) row of a column,
+    // the tiles behind it move up and the column is refilled from its pile
+    pub fn takeTile(&mut self, col: usize, row: usize) -> Tile {
+        let tile = if row == 0 { self.current[col] } else { self.next[col] };
+        if row == 0 {
+            self.current[col] = self.next[col];
+        }
+        self.next[col] = self.piles[col].pop().unwrap_or(Tile::BlankTile);
+        tile
+    }
+
+    // Takes back takeTile, refilled saying whether the column's pile had a tile to refill it with
+    pub(crate) fn putBack(&mut self, col: usize, row: usize, tile: Tile, refilled: bool) {
+        if refilled {
+            self.piles[col].push(self.next[col]);
+        }
+        if row == 0 {
+            self.next[col] = self.current[col];
+            self.current[col] = tile;
+        } else {
+            self.next[col] = tile;
+        }
+    }
+
+    pub fn shufflePiles<R: Rng>(&mut self, rng: &mut R) {
+        for pile in self.piles.iter_mut() {
+            pile.shuffle(rng);
+        }
     }
 
     pub fn nextTiles(&mut self) -> [Tile; 3] {
@@ -37,38 +84,10 @@ impl Foresight {
         }
         removed
     }
-
-    pub fn defaultTilePiles() -> [Vec<Tile>; 3] {
-        let mut tolls = vec![
-            vec![Tile::TepeeTile(Tepee::Blue); 8],
-            vec![Tile::TepeeTile(Tepee::Green); 9],
-        ].into_iter().flatten().collect::<Vec<Tile>>();
-        for haz in [HazardType::Flood, HazardType::Drought, HazardType::Rockfall] {
-            tolls.append(&mut vec![Tile::HazardTile(Hazard::new(haz, Toll::Green, 4)); 2]);
-            for toll in [Toll::Green, Toll::Black] {
-                for i in 2..3 {
-                    tolls.push(Tile::HazardTile(Hazard::new(haz, toll, i)));
-                }
-            }
-        }
-        let employees = vec![
-            vec![Tile::EmployeeTile(Employee::Engineer); 11],
-            vec![Tile::EmployeeTile(Employee::Cowboy); 11],
-            vec![Tile::EmployeeTile(Employee::Craftsman); 11],
-        ].into_iter().flatten().collect::<Vec<Tile>>();
-        let mix = vec![
-            vec![Tile::EmployeeTile(Employee::Engineer); 7],
-            vec![Tile::EmployeeTile(Employee::Cowboy); 7],
-            vec![Tile::EmployeeTile(Employee::Craftsman); 7],
-            vec![Tile::TepeeTile(Tepee::Green); 2],
-            vec![Tile::TepeeTile(Tepee::Blue); 3],
-        ].into_iter().flatten().collect::<Vec<Tile>>();
-        [tolls, employees, mix]
-    }
 }
 
 
-#[derive(Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize, Debug)]
 pub enum JobMarketEvent {
     NoEvent,
     RefillCowMarket,
@@ -91,29 +110,60 @@ impl JobMarket {
         JobMarket { employees: Vec::<Option<Employee>>::with_capacity(JobMarket::NUM_ROWS * num_players), cost: JobMarket::defaultRowSalary(), refresh_cow_market: vec![6, 9], game_end: 12, num_cols: num_players }
     }
 
+    // The slots filled so far, for a decoded position
+    pub(crate) fn restored(num_players: usize, employees: Vec<Option<Employee>>) -> JobMarket {
+        JobMarket { employees, ..JobMarket::new(num_players) }
+    }
+
     pub fn addEmployee(&mut self, emp: Employee) -> JobMarketEvent {
         self.employees.push(Some(emp));
         for row in self.refresh_cow_market.iter() {
-            if self.employees.len() / self.num_cols == *row && self.employees.len() % self.num_cols == 0 {
+            if self.employees.len() / self.num_cols == *row && self.employees.len().is_multiple_of(self.num_cols) {
                 return JobMarketEvent::RefillCowMarket;
             }
         }
-        if self.employees.len() / self.num_cols == self.game_end && self.employees.len() % self.num_cols == 0 {
+        if self.employees.len() / self.num_cols == self.game_end && self.employees.len().is_multiple_of(self.num_cols) {
             return JobMarketEvent::FinalRound;
         }
-        return JobMarketEvent::NoEvent;
+        JobMarketEvent::NoEvent
+    }
+
+    pub fn hireEmployee(&mut self, emp: Employee, salary: i32) -> Result<(), JobMarketError> {
+        self.hire(emp, salary).map(|_| {})
     }
 
-    pub fn hireEmployee(&mut self, emp: Employee, salary: i32) -> Result<(), String> {
+    // Hires like hireEmployee, returning the slot the employee was in
+    pub(crate) fn hire(&mut self, emp: Employee, salary: i32) -> Result<usize, JobMarketError> {
         for (i, e) in self.employees.iter().enumerate() {
             if *e == Some(emp) && self.salary(i) == salary {
                 self.employees[i] = None;
-                return Ok(());
+                return Ok(i);
             }
         }
-        return Err("No employee found".to_string());
+        Err(JobMarketError::EmployeeNotFound { employee: emp, salary })
     }
 
+    pub(crate) fn putBack(&mut self, slot: usize, emp: Employee) {
+        self.employees[slot] = Some(emp);
+    }
+
+    // Takes back the last addEmployee
+    pub(crate) fn removeLast(&mut self) {
+        self.employees.pop();
+    }
+
+    pub fn employees(&self) -> &Vec<Option<Employee>> { &self.employees }
+
+    // A row has a slot for each player
+    pub fn columns(&self) -> usize { self.num_cols }
+
+    pub fn rowSalaries(&self) -> &[i32; JobMarket::NUM_ROWS] { &self.cost }
+
+    // Rows count from 1, filling one of these refills the cow market and filling the last ends the game
+    pub fn cowMarketRows(&self) -> &Vec<usize> { &self.refresh_cow_market }
+
+    pub fn lastRow(&self) -> usize { self.game_end }
+
     fn salary(&self, emp_idx: usize) -> i32 {
         let idx = emp_idx / self.num_cols;
         if idx < self.cost.len() {
@@ -129,7 +179,7 @@ impl JobMarket {
             .filter(|check_emp| {
                 // #62358 <https://github.com/rust-lang/rust/issues/62358>
                 // *(*check_emp).1.contains(emp)
-                match *(*check_emp).1 {
+                match *check_emp.1 {
                     Some(check) => check == emp,
                     _ => false
                 }
@@ -183,6 +233,8 @@ mod tests {
         }
         assert_eq!(market.hireEmployee(Employee::Engineer, 5), Ok(()));
         assert_ne!(market.hireEmployee(Employee::Engineer, 5), Ok(()));
+        assert_eq!(market.hireEmployee(Employee::Craftsman, 6),
+                   Err(JobMarketError::EmployeeNotFound { employee: Employee::Craftsman, salary: 6 }));
     }
 
     #[test]
@@ -0,0 +1,246 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::agent::{Agent, AgentSpec};
+use crate::data::GameData;
+use crate::error::GwtError;
+use crate::runner::{GameResult, playGame};
+
+#[derive(Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+pub enum Format {
+    // Every combination of entrants plays at a table together
+    RoundRobin,
+    // Each round seats entrants with similar results together
+    Swiss { rounds: u32 },
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+pub struct TournamentConfig {
+    pub format: Format,
+    pub players: u32,
+    // Every table plays this many seeds, each once in every seat rotation
+    pub seeds: u32,
+    pub first_seed: u64,
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct Standing {
+    pub name: String,
+    pub games: u32,
+    // Shared wins count as a fraction
+    pub wins: f64,
+    pub rating: f64,
+}
+
+impl Standing {
+    pub fn winRate(&self) -> f64 {
+        if self.games == 0 { 0.0 } else { self.wins / self.games as f64 }
+    }
+
+    pub fn confidence(&self) -> (f64, f64) {
+        wilsonInterval(self.wins, self.games)
+    }
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct TournamentReport {
+    pub standings: Vec<Standing>,
+    pub seat_games: Vec<u32>,
+    pub seat_wins: Vec<f64>,
+}
+
+pub const INITIAL_RATING: f64 = 1500.0;
+const ELO_K: f64 = 16.0;
+// 95% confidence
+const Z: f64 = 1.96;
+
+// The Wilson score interval for a win rate, which stays sensible for small samples and rates near 0 or 1
+pub fn wilsonInterval(wins: f64, games: u32) -> (f64, f64) {
+    if games == 0 {
+        return (0.0, 1.0);
+    }
+    let n = games as f64;
+    let p = wins / n;
+    let denominator = 1.0 + Z * Z / n;
+    let centre = (p + Z * Z / (2.0 * n)) / denominator;
+    let margin = Z * (p * (1.0 - p) / n + Z * Z / (4.0 * n * n)).sqrt() / denominator;
+    ((centre - margin).max(0.0), (centre + margin).min(1.0))
+}
+
+pub struct Tournament<'a> {
+    data: &'a GameData,
+    entrants: Vec<AgentSpec>,
+    config: TournamentConfig,
+    standings: Vec<Standing>,
+    seat_games: Vec<u32>,
+    seat_wins: Vec<f64>,
+}
+
+impl<'a> Tournament<'a> {
+    pub fn new(data: &'a GameData, entrants: Vec<AgentSpec>, config: TournamentConfig) -> Result<Tournament<'a>, GwtError> {
+        if !(2..=4).contains(&config.players) {
+            return Err(GwtError::InvalidPlayerCount(config.players));
+        }
+        if entrants.len() < config.players as usize {
+            return Err(GwtError::InvalidPlayerCount(entrants.len() as u32));
+        }
+        let standings = entrants.iter()
+            .map(|e| { Standing { name: e.name(), games: 0, wins: 0.0, rating: INITIAL_RATING } })
+            .collect();
+        let seats = config.players as usize;
+        Ok(Tournament { data, entrants, config, standings, seat_games: vec![0; seats], seat_wins: vec![0.0; seats] })
+    }
+
+    pub fn run(mut self) -> Result<TournamentReport, GwtError> {
+        match self.config.format {
+            Format::RoundRobin => {
+                for table in Tournament::combinations(self.entrants.len(), self.config.players as usize) {
+                    self.playTable(&table)?;
+                }
+            }
+            Format::Swiss { rounds } => {
+                for _ in 0..rounds {
+                    for table in self.swissTables() {
+                        self.playTable(&table)?;
+                    }
+                }
+            }
+        }
+        Ok(TournamentReport { standings: self.standings, seat_games: self.seat_games, seat_wins: self.seat_wins })
+    }
+
+    fn combinations(n: usize, k: usize) -> Vec<Vec<usize>> {
+        if k == 0 {
+            return vec![vec![]];
+        }
+        (k - 1..n).flat_map(|last| {
+            Tournament::combinations(last, k - 1).into_iter().map(move |mut c| {
+                c.push(last);
+                c
+            })
+        }).collect()
+    }
+
+    // Sorted by results so far, the leftover entrants sit with those just above them
+    fn swissTables(&self) -> Vec<Vec<usize>> {
+        let mut order: Vec<usize> = (0..self.entrants.len()).collect();
+        order.sort_by(|a, b| {
+            let (a, b) = (&self.standings[*a], &self.standings[*b]);
+            b.winRate().partial_cmp(&a.winRate()).unwrap()
+                .then(b.rating.partial_cmp(&a.rating).unwrap())
+        });
+        let size = self.config.players as usize;
+        let mut tables: Vec<Vec<usize>> = order.chunks(size).map(|c| { c.to_vec() }).collect();
+        if let Some(last) = tables.last_mut() {
+            if last.len() < size {
+                *last = order[order.len() - size..].to_vec();
+            }
+        }
+        tables
+    }
+
+    // Every seed is played once per rotation, so each entrant sits in every seat equally often
+    fn playTable(&mut self, table: &[usize]) -> Result<(), GwtError> {
+        let seats = table.len();
+        for s in 0..self.config.seeds {
+            let seed = self.config.first_seed + s as u64;
+            for rotation in 0..seats {
+                let seating: Vec<usize> = (0..seats).map(|seat| { table[(seat + rotation) % seats] }).collect();
+                let mut agents: Vec<Box<dyn Agent>> = seating.iter()
+                    .enumerate()
+                    .map(|(seat, e)| { self.entrants[*e].build(seed.wrapping_mul(31).wrapping_add(seat as u64)) })
+                    .collect();
+                let result = playGame(self.data, &mut agents, seed)?;
+                self.record(&seating, &result);
+            }
+        }
+        Ok(())
+    }
+
+    fn record(&mut self, seating: &[usize], result: &GameResult) {
+        let share = 1.0 / result.winners.len() as f64;
+        for (seat, entrant) in seating.iter().enumerate() {
+            self.standings[*entrant].games += 1;
+            self.seat_games[seat] += 1;
+            if result.winners.contains(&(seat as u32)) {
+                self.standings[*entrant].wins += share;
+                self.seat_wins[seat] += share;
+            }
+        }
+        // Multiplayer games count as a game between every pair of players
+        let totals: Vec<(i32, u32)> = result.scores.iter().map(|s| { (s.total(), s.tie_breaker) }).collect();
+        let k = ELO_K / (seating.len() - 1) as f64;
+        let ratings: Vec<f64> = seating.iter().map(|e| { self.standings[*e].rating }).collect();
+        for i in 0..seating.len() {
+            for j in 0..seating.len() {
+                if i == j {
+                    continue;
+                }
+                let expected = 1.0 / (1.0 + 10f64.powf((ratings[j] - ratings[i]) / 400.0));
+                let actual = match totals[i].cmp(&totals[j]) {
+                    std::cmp::Ordering::Greater => 1.0,
+                    std::cmp::Ordering::Equal => 0.5,
+                    std::cmp::Ordering::Less => 0.0,
+                };
+                self.standings[seating[i]].rating += k * (actual - expected);
+            }
+        }
+    }
+}
+
+impl fmt::Display for TournamentReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut standings: Vec<&Standing> = self.standings.iter().collect();
+        standings.sort_by(|a, b| { b.rating.partial_cmp(&a.rating).unwrap() });
+        writeln!(f, "{:<16} {:>7} {:>8} {:>8} {:>17}", "agent", "rating", "games", "win %", "95% interval")?;
+        for s in standings {
+            let (lo, hi) = s.confidence();
+            writeln!(f, "{:<16} {:>7.0} {:>8} {:>8.1} {:>8.1} - {:>5.1}",
+                     s.name, s.rating, s.games, s.winRate() * 100.0, lo * 100.0, hi * 100.0)?;
+        }
+        writeln!(f)?;
+        writeln!(f, "{:<16} {:>7} {:>8} {:>8} {:>17}", "seat", "", "games", "win %", "95% interval")?;
+        for (seat, (games, wins)) in self.seat_games.iter().zip(self.seat_wins.iter()).enumerate() {
+            let (lo, hi) = wilsonInterval(*wins, *games);
+            let rate = if *games == 0 { 0.0 } else { wins / *games as f64 };
+            writeln!(f, "{:<16} {:>7} {:>8} {:>8.1} {:>8.1} - {:>5.1}",
+                     seat, "", games, rate * 100.0, lo * 100.0, hi * 100.0)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::heuristic::Strategy;
+
+    use super::*;
+
+    #[test]
+    fn testWilsonInterval() {
+        let (lo, hi) = wilsonInterval(50.0, 100);
+        assert!((lo - 0.404).abs() < 0.001 && (hi - 0.596).abs() < 0.001);
+        assert_eq!(wilsonInterval(0.0, 0), (0.0, 1.0));
+        let (lo, _) = wilsonInterval(0.0, 10);
+        assert_eq!(lo, 0.0);
+    }
+
+    #[test]
+    fn testRoundRobin() {
+        let data = GameData::embedded().unwrap();
+        let entrants = vec![AgentSpec::Random, AgentSpec::Heuristic(Strategy::CowBuyer), AgentSpec::Heuristic(Strategy::EngineRush)];
+        let config = TournamentConfig { format: Format::RoundRobin, players: 2, seeds: 2, first_seed: 0 };
+        let report = Tournament::new(&data, entrants.clone(), config).unwrap().run().unwrap();
+        // 3 tables of 2 seeds in 2 seatings
+        assert_eq!(report.seat_games, vec![12, 12]);
+        assert!(report.standings.iter().all(|s| { s.games == 8 }));
+        assert!(report.standings[0].rating < INITIAL_RATING);
+        assert_eq!(report, Tournament::new(&data, entrants.clone(), config).unwrap().run().unwrap());
+
+        let swiss = TournamentConfig { format: Format::Swiss { rounds: 2 }, ..config };
+        let report = Tournament::new(&data, entrants, swiss).unwrap().run().unwrap();
+        // The odd entrant out sits at a second table every round
+        assert_eq!(report.seat_games.iter().sum::<u32>(), 2 * 2 * 2 * 2 * 2);
+    }
+}
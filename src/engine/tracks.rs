@@ -1,10 +1,15 @@
+use std::cmp::Ordering;
+
 use serde::{Deserialize, Serialize};
 
 use crate::actions::{ActionTag, ActionValues};
 use crate::buildings::{Building, Hazard, HazardType, Tepee};
+use crate::error::GwtError;
 use crate::logical::And;
 
-#[derive(Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+// Spaces are copied around freely, so the building stays inline rather than boxed
+#[allow(clippy::large_enum_variant)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize, Debug)]
 pub enum SpaceOccupant {
     Building { risk_action: And<ActionTag, 3>, building: Option<Building>, neutral: bool, forest: bool },
     Hazard(HazardType, Option<Hazard>),
@@ -13,7 +18,7 @@ pub enum SpaceOccupant {
     Start,
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize, Debug)]
 pub struct Space {
     occupant: SpaceOccupant,
     nextSpace: [Option<usize>; 2],
@@ -21,7 +26,65 @@ pub struct Space {
 
 impl Space {
     pub fn new(occupant: SpaceOccupant, nextSpace: [Option<usize>; 2]) -> Space {
-        return Space { occupant, nextSpace };
+        Space { occupant, nextSpace }
+    }
+
+    pub fn occupant(&self) -> &SpaceOccupant { &self.occupant }
+
+    pub fn nextSpaces(&self) -> [Option<usize>; 2] { self.nextSpace }
+
+    // Hazards, tepees and Kansas City count as a step, empty spaces are passed over
+    pub fn isStop(&self) -> bool {
+        match self.occupant {
+            SpaceOccupant::Building { building, .. } => building.is_some(),
+            SpaceOccupant::Hazard(_, hazard) => hazard.is_some(),
+            SpaceOccupant::Tepee(_, tepee) => tepee.is_some(),
+            SpaceOccupant::KansasCity => true,
+            SpaceOccupant::Start => false,
+        }
+    }
+
+    pub fn building(&self) -> Option<Building> {
+        if let SpaceOccupant::Building { building, .. } = self.occupant { building } else { None }
+    }
+
+    // Hazard tiles only go on empty spaces of their own type, tepees on any empty tepee space
+    pub fn placeHazard(&mut self, new_hazard: Hazard) -> bool {
+        match &mut self.occupant {
+            SpaceOccupant::Hazard(area, hazard) if *area == new_hazard.area() && hazard.is_none() => {
+                *hazard = Some(new_hazard);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn placeTepee(&mut self, new_tepee: Tepee) -> bool {
+        match &mut self.occupant {
+            SpaceOccupant::Tepee(_, tepee) if tepee.is_none() => {
+                *tepee = Some(new_tepee);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn takeHazard(&mut self) -> Option<Hazard> {
+        if let SpaceOccupant::Hazard(_, hazard) = &mut self.occupant { hazard.take() } else { None }
+    }
+
+    pub fn takeTepee(&mut self) -> Option<Tepee> {
+        if let SpaceOccupant::Tepee(_, tepee) = &mut self.occupant { tepee.take() } else { None }
+    }
+
+    // Only spaces meant for buildings can take one, returns false otherwise
+    pub fn placeBuilding(&mut self, new_building: Building) -> bool {
+        if let SpaceOccupant::Building { building, .. } = &mut self.occupant {
+            *building = Some(new_building);
+            true
+        } else {
+            false
+        }
     }
 }
 
@@ -36,25 +99,95 @@ pub struct City {
     name: String,
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+impl City {
+    pub fn name(&self) -> &str { &self.name }
+
+    pub fn location(&self) -> u32 { self.location }
+
+    pub fn coins(&self) -> u32 { self.coins }
+
+    pub fn advancedDisc(&self) -> bool { self.advanced_disc }
+
+    // Limited cities only take one disc from each player
+    pub fn limited(&self) -> bool { self.limited }
+
+    pub fn discs(&self, player: u32) -> u32 {
+        self.placed_discs[player as usize]
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize, Debug)]
 pub struct PairAction {
     take_obj: bool,
     points: i32,
 }
 
+impl PairAction {
+    pub fn takesObjective(&self) -> bool { self.take_obj }
+}
+
 #[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
 pub struct CityTrack {
     cities: Vec<City>,
+    // pair_action[i] is awarded to players with discs in both cities[i] and cities[i + 1]
     pair_action: Vec<PairAction>,
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+impl CityTrack {
+    pub const SAN_FRANCISCO: &'static str = "San Francisco";
+
+    pub fn cities(&self) -> &Vec<City> { &self.cities }
+
+    pub fn pairActions(&self) -> &Vec<PairAction> { &self.pair_action }
+
+    pub fn placeDisc(&mut self, player: u32, city: usize) {
+        self.cities[city].placed_discs[player as usize] += 1;
+    }
+
+    pub(crate) fn removeDisc(&mut self, player: u32, city: usize) {
+        self.cities[city].placed_discs[player as usize] -= 1;
+    }
+
+    pub fn discsIn(&self, player: u32, name: &str) -> u32 {
+        self.cities.iter()
+            .filter(|c| { c.name == name })
+            .map(|c| { c.discs(player) })
+            .sum()
+    }
+
+    // Points for the discs delivered to cities, and for the pairs of adjacent cities with discs
+    pub fn points(&self, player: u32) -> i32 {
+        let city_pts: i32 = self.cities.iter()
+            .map(|c| { c.points * c.discs(player) as i32 })
+            .sum();
+        let pair_pts: i32 = self.pair_action.iter()
+            .enumerate()
+            .filter(|(i, _)| {
+                *i + 1 < self.cities.len()
+                    && self.cities[*i].discs(player) > 0
+                    && self.cities[*i + 1].discs(player) > 0
+            })
+            .map(|(_, pair)| { pair.points })
+            .sum();
+        city_pts + pair_pts
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize, Debug)]
 pub struct StationMaster {
     points: ActionValues,
     bonus: crate::logical::XOr<ActionTag, 2>,
     perm_cert: bool,
 }
 
+impl StationMaster {
+    pub fn points(&self) -> ActionValues { self.points }
+
+    pub fn bonus(&self) -> &crate::logical::XOr<ActionTag, 2> { &self.bonus }
+
+    pub fn permCertificate(&self) -> bool { self.perm_cert }
+}
+
 #[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
 pub struct Station {
     location: u32,
@@ -65,7 +198,24 @@ pub struct Station {
     station_master: Option<StationMaster>,
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+impl Station {
+    pub fn hasDisc(&self, player: u32) -> bool {
+        self.placed_discs[player as usize]
+    }
+
+    pub fn stationMaster(&self) -> Option<StationMaster> { self.station_master }
+
+    pub fn location(&self) -> u32 { self.location }
+
+    // The dollars paid to upgrade the station
+    pub fn price(&self) -> u32 { self.price.unsigned_abs() }
+
+    pub fn points(&self) -> u32 { self.points }
+
+    pub fn advancedDisc(&self) -> bool { self.advanced_disc }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize, Debug)]
 pub enum EngineSpace {
     TurnoutTrack(usize),
     MainTrack(usize),
@@ -91,7 +241,91 @@ impl StationTrack {
         }
     }
 
+    pub fn engineSpace(&self, player: u32) -> EngineSpace { self.engine_loc[player as usize] }
+
+    pub fn engineLocation(&self, player: u32) -> u32 {
+        match self.engine_loc[player as usize] {
+            EngineSpace::TurnoutTrack(loc) => loc as u32,
+            EngineSpace::MainTrack(loc) => loc as u32,
+        }
+    }
+
     pub fn makeDelivery(&mut self, player: u32, city: u32) {
+        self.cities.placeDisc(player, city as usize);
+    }
+
+    pub fn cities(&self) -> &CityTrack { &self.cities }
+
+    pub fn end(&self) -> u32 { self.end }
+
+    pub fn stations(&self) -> &Vec<Station> { &self.stations }
+
+    pub fn finalStation(&self) -> &Station { &self.final_station }
+
+    pub fn crossings(&self) -> &Vec<u32> { &self.crossings }
+
+    pub fn placeStationMaster(&mut self, station: usize, station_master: StationMaster) {
+        self.stations[station].station_master = Some(station_master);
+    }
+
+    // Stations are numbered in track order, the final station comes after all the others
+    pub fn placeStationDisc(&mut self, player: u32, station: usize) -> Result<(), GwtError> {
+        match station.cmp(&self.stations.len()) {
+            Ordering::Less => self.stations[station].placed_discs[player as usize] = true,
+            Ordering::Equal => self.final_station.placed_discs[player as usize] = true,
+            Ordering::Greater => return Err(GwtError::InvalidStation(station)),
+        }
+        Ok(())
+    }
+
+    // Stations are numbered the same way as for placeStationDisc
+    pub fn station(&self, station: usize) -> &Station {
+        if station < self.stations.len() { &self.stations[station] } else { &self.final_station }
+    }
+
+    pub fn numStations(&self) -> usize {
+        self.stations.len() + 1
+    }
+
+    // Places the player's disc, handing over the station master if there still is one
+    pub fn upgradeStation(&mut self, player: u32, station: usize) -> Result<Option<StationMaster>, GwtError> {
+        self.placeStationDisc(player, station)?;
+        if station < self.stations.len() {
+            Ok(self.stations[station].station_master.take())
+        } else {
+            Ok(None)
+        }
+    }
+
+    // Takes back upgradeStation, putting back the station master it handed over
+    pub(crate) fn downgradeStation(&mut self, player: u32, station: usize, station_master: Option<StationMaster>) {
+        if station < self.stations.len() {
+            self.stations[station].placed_discs[player as usize] = false;
+            self.stations[station].station_master = station_master;
+        } else {
+            self.final_station.placed_discs[player as usize] = false;
+        }
+    }
+
+    // Takes back makeDelivery
+    pub(crate) fn takeBackDelivery(&mut self, player: u32, city: u32) {
+        self.cities.removeDisc(player, city as usize);
+    }
+
+    fn allStations(&self) -> impl Iterator<Item=&Station> {
+        self.stations.iter().chain(std::iter::once(&self.final_station))
+    }
+
+    pub fn stationDiscs(&self, player: u32) -> u32 {
+        self.allStations()
+            .filter(|s| { s.hasDisc(player) })
+            .count() as u32
+    }
 
+    pub fn stationPoints(&self, player: u32) -> i32 {
+        self.allStations()
+            .filter(|s| { s.hasDisc(player) })
+            .map(|s| { s.points as i32 })
+            .sum()
     }
 }
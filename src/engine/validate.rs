@@ -0,0 +1,384 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::Engine;
+use crate::actions::{ActionTag, ActionValues};
+use crate::buildings::{Building, HazardType, Tepee, Toll};
+use crate::data::GameData;
+use crate::describe::Describe;
+use crate::logical::{And, XOr};
+use crate::tiles::Tile;
+use crate::tracks::{Space, SpaceOccupant};
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize, Debug)]
+pub enum Severity {
+    // Suspicious, but a game can still be played
+    Warning,
+    Error,
+}
+
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+pub struct ValidationIssue {
+    pub severity: Severity,
+    pub file: String,
+    pub message: String,
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}: {}: {}", self.severity, self.file, self.message)
+    }
+}
+
+// The number of cards, tiles and buildings expected in each file
+#[derive(Copy, Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+pub struct ExpectedCounts {
+    pub starting_deck: usize,
+    pub starting_objectives: usize,
+    pub objectives: usize,
+    pub cow_deck: usize,
+    pub station_masters: usize,
+    pub neutral_buildings: usize,
+    pub player_buildings: usize,
+    pub foresight_piles: [usize; 3],
+    // Across all the foresight piles, by colour (green, blue) and by area (flood, drought, rockfall)
+    pub foresight_tepees: [usize; 2],
+    pub foresight_hazards: [usize; 3],
+}
+
+impl ExpectedCounts {
+    pub fn standard() -> ExpectedCounts {
+        ExpectedCounts {
+            starting_deck: 14,
+            starting_objectives: 4,
+            objectives: 24,
+            cow_deck: 36,
+            station_masters: 5,
+            neutral_buildings: 7,
+            player_buildings: 10,
+            foresight_piles: [35, 33, 26],
+            foresight_tepees: [11, 11],
+            foresight_hazards: [6, 6, 6],
+        }
+    }
+}
+
+// Checks everything in the data set, returning every problem found rather than stopping at the first
+pub fn validate(data: &GameData, expected: &ExpectedCounts) -> Vec<ValidationIssue> {
+    let mut issues = Vec::<ValidationIssue>::new();
+    validateTrack(&data.track, &mut issues);
+    validateStations(data, &mut issues);
+    validateCounts(data, expected, &mut issues);
+    validateObjectives(data, &mut issues);
+    validateActions(data, &mut issues);
+    issues
+}
+
+fn report(issues: &mut Vec<ValidationIssue>, severity: Severity, file: &str, message: String) {
+    issues.push(ValidationIssue { severity, file: file.to_string(), message });
+}
+
+fn validateTrack(track: &[Space], issues: &mut Vec<ValidationIssue>) {
+    let file = GameData::TRACK;
+    let mut in_range = true;
+    for (i, space) in track.iter().enumerate() {
+        for next in space.nextSpaces().iter().flatten() {
+            if *next >= track.len() {
+                report(issues, Severity::Error, file, format!("Space {} leads to space {}, but there are only {} spaces", i, next, track.len()));
+                in_range = false;
+            }
+        }
+        let exits = space.nextSpaces().iter().flatten().count();
+        if *space.occupant() == SpaceOccupant::KansasCity && exits > 0 {
+            report(issues, Severity::Error, file, format!("Kansas City at space {} leads further along the track", i));
+        }
+    }
+    if !in_range {
+        return;
+    }
+    let start = match track.iter().position(|s| { *s.occupant() == SpaceOccupant::Start }) {
+        Some(start) => start,
+        None => {
+            report(issues, Severity::Error, file, "No start space".to_string());
+            return;
+        }
+    };
+
+    // Depth first search from the start, every path must end in Kansas City without revisiting a space
+    #[derive(Copy, Clone, Eq, PartialEq)]
+    enum Visit { Unvisited, InProgress, Done }
+    let mut visits = vec![Visit::Unvisited; track.len()];
+    let mut stack = vec![(start, 0)];
+    visits[start] = Visit::InProgress;
+    while let Some((space, exit)) = stack.pop() {
+        let exits: Vec<usize> = track[space].nextSpaces().iter().flatten().copied().collect();
+        if exit == 0 && exits.is_empty() && *track[space].occupant() != SpaceOccupant::KansasCity {
+            report(issues, Severity::Error, file, format!("Space {} is a dead end before Kansas City", space));
+        }
+        if exit < exits.len() {
+            stack.push((space, exit + 1));
+            let next = exits[exit];
+            match visits[next] {
+                Visit::Unvisited => {
+                    visits[next] = Visit::InProgress;
+                    stack.push((next, 0));
+                }
+                Visit::InProgress => {
+                    report(issues, Severity::Error, file, format!("Space {} leads back to space {}, forming a loop", space, next));
+                }
+                Visit::Done => {}
+            }
+        } else {
+            visits[space] = Visit::Done;
+        }
+    }
+    for (i, visit) in visits.iter().enumerate() {
+        if *visit == Visit::Unvisited {
+            report(issues, Severity::Warning, file, format!("Space {} can't be reached from the start", i));
+        }
+    }
+}
+
+fn validateStations(data: &GameData, issues: &mut Vec<ValidationIssue>) {
+    let file = GameData::STATION_TRACK;
+    let track = &data.station_track;
+    let mut prev: Option<u32> = None;
+    for (i, station) in track.stations().iter().enumerate() {
+        if station.location() >= track.end() {
+            report(issues, Severity::Error, file, format!("Station {} is at {}, past the end of the track at {}", i, station.location(), track.end()));
+        }
+        if prev.is_some_and(|p| { p >= station.location() }) {
+            report(issues, Severity::Error, file, format!("Station {} isn't after the station before it", i));
+        }
+        prev = Some(station.location());
+    }
+    if track.finalStation().location() != track.end() {
+        report(issues, Severity::Error, file, format!("The final station is at {}, not the end of the track at {}", track.finalStation().location(), track.end()));
+    }
+    for crossing in track.crossings() {
+        if *crossing > track.end() {
+            report(issues, Severity::Error, file, format!("Crossing {} is past the end of the track at {}", crossing, track.end()));
+        }
+    }
+    if data.station_masters.len() > track.stations().len() {
+        report(issues, Severity::Error, GameData::STATION_MASTERS, format!("{} station masters for {} stations", data.station_masters.len(), track.stations().len()));
+    }
+}
+
+fn validateCounts(data: &GameData, expected: &ExpectedCounts, issues: &mut Vec<ValidationIssue>) {
+    let neutral_spaces = data.neutralSpaces().len();
+    if data.neutral_buildings.len() != neutral_spaces {
+        report(issues, Severity::Error, GameData::NEUTRAL_BUILDINGS,
+               format!("{} neutral buildings for {} neutral spaces on the track", data.neutral_buildings.len(), neutral_spaces));
+    }
+    let mut counts = vec![
+        (GameData::STARTING_DECK, data.starting_deck.len(), expected.starting_deck),
+        (GameData::STARTING_OBJECTIVES, data.starting_objectives.len(), expected.starting_objectives),
+        (GameData::OBJECTIVES, data.objectives.len(), expected.objectives),
+        (GameData::COW_DECK, data.cow_deck.len(), expected.cow_deck),
+        (GameData::STATION_MASTERS, data.station_masters.len(), expected.station_masters),
+        (GameData::NEUTRAL_BUILDINGS, data.neutral_buildings.len(), expected.neutral_buildings),
+        (GameData::PLAYER_BUILDINGS_A, data.player_buildings_a.len(), expected.player_buildings),
+        (GameData::PLAYER_BUILDINGS_B, data.player_buildings_b.len(), expected.player_buildings),
+    ];
+    for (pile, count) in data.foresight_piles.iter().zip(expected.foresight_piles) {
+        counts.push((GameData::FORESIGHT_PILES, pile.len(), count));
+    }
+    let tiles: Vec<&Tile> = data.foresight_piles.iter().flatten().collect();
+    for (tepee, count) in [Tepee::Green, Tepee::Blue].iter().zip(expected.foresight_tepees) {
+        let found = tiles.iter().filter(|t| { ***t == Tile::TepeeTile(*tepee) }).count();
+        if found != count {
+            report(issues, Severity::Error, GameData::FORESIGHT_PILES, format!("Found {} {:?} tepees, expected {}", found, tepee, count));
+        }
+    }
+    // Every area has the same hazards, only the area differs
+    let areas = [HazardType::Flood, HazardType::Drought, HazardType::Rockfall];
+    let hazards = |area: HazardType| {
+        let mut hazards: Vec<(Toll, u32)> = tiles.iter()
+            .filter_map(|t| { if let Tile::HazardTile(h) = t { Some(*h) } else { None } })
+            .filter(|h| { h.area() == area })
+            .map(|h| { (h.toll(), h.points()) })
+            .collect();
+        hazards.sort_by_key(|(toll, points)| { (*toll as u32, *points) });
+        hazards
+    };
+    for (area, count) in areas.iter().zip(expected.foresight_hazards) {
+        let found = hazards(*area);
+        if found.len() != count {
+            report(issues, Severity::Error, GameData::FORESIGHT_PILES, format!("Found {} {:?} hazards, expected {}", found.len(), area, count));
+        } else if found != hazards(areas[0]) {
+            report(issues, Severity::Warning, GameData::FORESIGHT_PILES, format!("The {:?} hazards have other tolls or points than the {:?} ones", area, areas[0]));
+        }
+    }
+    for (file, found, count) in counts {
+        if found != count {
+            report(issues, Severity::Error, file, format!("Found {} entries, expected {}", found, count));
+        }
+    }
+}
+
+// Why a tag can never be carried out, if it can't
+// Starting objectives are played from the start, so any points for failing them would be lost by whoever didn't try
+fn validateObjectives(data: &GameData, issues: &mut Vec<ValidationIssue>) {
+    for (i, objective) in data.starting_objectives.iter().enumerate() {
+        if objective.failPoints() != 0 {
+            report(issues, Severity::Error, GameData::STARTING_OBJECTIVES,
+                   format!("Objective {} ({}) costs {} points when failed", i, objective.describe(), objective.failPoints()));
+        }
+    }
+}
+
+pub fn impossibleTag(tag: &ActionTag) -> Option<String> {
+    let value = match tag {
+        ActionTag::TakeCoins(v) | ActionTag::DrawCards(v) | ActionTag::MoveCattleman(v)
+        | ActionTag::TeleportCattleman(v) | ActionTag::MoveEngine(v) | ActionTag::MoveCertificate(v)
+        | ActionTag::PlaceDisc(v) | ActionTag::HireEmployee(v) | ActionTag::PlaceBuilding(v) => *v,
+        _ => return None,
+    };
+    match (tag, value) {
+        (_, ActionValues::AtMost(_)) =>
+            Some(format!("{:?} can only be used at the end of the station track", tag)),
+        (ActionTag::MoveCertificate(_), ActionValues::Max) => None,
+        (_, ActionValues::Max) => Some(format!("{:?} has no maximum", tag)),
+        (ActionTag::TakeCoins(_), ActionValues::Exact(0)) | (ActionTag::DrawCards(_), ActionValues::Exact(0))
+        | (ActionTag::MoveCattleman(_), ActionValues::Exact(0)) | (ActionTag::TeleportCattleman(_), ActionValues::Exact(0))
+        | (ActionTag::MoveEngine(_), ActionValues::Exact(0)) | (ActionTag::MoveCertificate(_), ActionValues::Exact(0)) =>
+            Some(format!("{:?} does nothing", tag)),
+        (ActionTag::DrawCards(_), ActionValues::Exact(v)) | (ActionTag::MoveCattleman(_), ActionValues::Exact(v))
+        | (ActionTag::TeleportCattleman(_), ActionValues::Exact(v)) | (ActionTag::MoveCertificate(_), ActionValues::Exact(v))
+        if v < 0 => Some(format!("{:?} can't go backwards", tag)),
+        _ => None,
+    }
+}
+
+fn validateTags<'a, I: Iterator<Item=&'a ActionTag>>(tags: I, file: &str, context: &str, issues: &mut Vec<ValidationIssue>) {
+    for tag in tags {
+        if let Some(problem) = impossibleTag(tag) {
+            report(issues, Severity::Error, file, format!("{}: {}", context, problem));
+        }
+    }
+}
+
+fn validateBuilding(building: &Building, file: &str, context: &str, issues: &mut Vec<ValidationIssue>) {
+    for xor in building.actions().items.iter().flatten() {
+        let xor: &XOr<And<ActionTag, 3>, 2> = xor;
+        if xor.items.iter().flatten().all(|and| { and.items.iter().all(|t| { t.is_none() }) }) {
+            report(issues, Severity::Error, file, format!("{}: an option has no actions", context));
+        }
+        for and in xor.items.iter().flatten() {
+            validateTags(and.items.iter().flatten(), file, context, issues);
+        }
+    }
+}
+
+fn validateActions(data: &GameData, issues: &mut Vec<ValidationIssue>) {
+    for (file, buildings) in [
+        (GameData::NEUTRAL_BUILDINGS, &data.neutral_buildings),
+        (GameData::PLAYER_BUILDINGS_A, &data.player_buildings_a),
+        (GameData::PLAYER_BUILDINGS_B, &data.player_buildings_b),
+    ] {
+        for (i, building) in buildings.iter().enumerate() {
+            validateBuilding(building, file, &format!("Building {} ({})", i, building.describe()), issues);
+        }
+    }
+    for (i, space) in data.track.iter().enumerate() {
+        if let SpaceOccupant::Building { risk_action, building, .. } = space.occupant() {
+            validateTags(risk_action.items.iter().flatten(), GameData::TRACK, &format!("Space {}", i), issues);
+            if let Some(b) = building {
+                validateBuilding(b, GameData::TRACK, &format!("Building on space {} ({})", i, b.describe()), issues);
+            }
+        }
+    }
+    for (file, objectives) in [(GameData::OBJECTIVES, &data.objectives), (GameData::STARTING_OBJECTIVES, &data.starting_objectives)] {
+        for (i, objective) in objectives.iter().enumerate() {
+            validateTags(objective.immediate().iter(), file, &format!("Objective {} ({})", i, objective.describe()), issues);
+        }
+    }
+    for (i, sm) in data.station_masters.iter().enumerate() {
+        let context = format!("Station master {} ({})", i, sm.describe());
+        validateTags(sm.bonus().items.iter().flatten(), GameData::STATION_MASTERS, &context, issues);
+        // A choice of bonus is made by taking one of its actions, which tags resolved without a decision don't have
+        if let [Some(first), Some(second)] = sm.bonus().items {
+            if Engine::automatic(first) || Engine::automatic(second) {
+                report(issues, Severity::Error, GameData::STATION_MASTERS,
+                       format!("{}: a choice of bonus can't include one that needs no decision", context));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::buildings::Hazard;
+
+    use super::*;
+
+    fn errors(issues: &[ValidationIssue]) -> Vec<&ValidationIssue> {
+        issues.iter().filter(|i| { i.severity == Severity::Error }).collect()
+    }
+
+    #[test]
+    fn testDefaultData() {
+        let data = GameData::embedded().unwrap();
+        let issues = validate(&data, &ExpectedCounts::standard());
+        assert_eq!(errors(&issues), Vec::<&ValidationIssue>::new());
+    }
+
+    #[test]
+    fn testBrokenTrack() {
+        let mut data = GameData::embedded().unwrap();
+        data.track = vec![
+            Space::new(SpaceOccupant::Start, [Some(1), Some(4)]),
+            Space::new(SpaceOccupant::Tepee(1, Some(Tepee::Blue)), [Some(2), None]),
+            Space::new(SpaceOccupant::Tepee(2, None), [Some(1), Some(3)]),
+            Space::new(SpaceOccupant::KansasCity, [None, None]),
+            Space::new(SpaceOccupant::Tepee(3, None), [None, None]),
+        ];
+        let issues = validate(&data, &ExpectedCounts::standard());
+        let track_errors: Vec<&ValidationIssue> = errors(&issues).into_iter()
+            .filter(|i| { i.file == GameData::TRACK })
+            .collect();
+        assert_eq!(track_errors.len(), 2);
+        assert!(track_errors.iter().any(|i| { i.message == "Space 4 is a dead end before Kansas City" }));
+        assert!(track_errors.iter().any(|i| { i.message == "Space 2 leads back to space 1, forming a loop" }));
+
+        data.track[4] = Space::new(SpaceOccupant::Tepee(3, None), [Some(5), None]);
+        let issues = validate(&data, &ExpectedCounts::standard());
+        assert!(errors(&issues).iter().any(|i| { i.message.contains("only 5 spaces") }));
+    }
+
+    #[test]
+    fn testForesightCounts() {
+        // The piles the data was once made with, without the 3 point hazards
+        let mut data = GameData::embedded().unwrap();
+        data.foresight_piles[0].retain(|t| { !matches!(t, Tile::HazardTile(h) if h.points() == 3) });
+        let issues = validate(&data, &ExpectedCounts::standard());
+        assert!(errors(&issues).iter().any(|i| { i.message == "Found 4 Flood hazards, expected 6" }));
+
+        let mut data = GameData::embedded().unwrap();
+        let last = data.foresight_piles[0].len() - 1;
+        data.foresight_piles[0][last] = Tile::HazardTile(Hazard::new(HazardType::Rockfall, Toll::Black, 5));
+        let issues = validate(&data, &ExpectedCounts::standard());
+        assert!(issues.iter().any(|i| { i.message == "The Rockfall hazards have other tolls or points than the Flood ones" }));
+    }
+
+    #[test]
+    fn testFailedStartingObjective() {
+        let mut data = GameData::embedded().unwrap();
+        let objective = *data.objectives.iter().find(|o| { o.failPoints() > 0 }).unwrap();
+        data.starting_objectives[0] = objective;
+        let issues = validate(&data, &ExpectedCounts::standard());
+        let expected = format!("Objective 0 ({}) costs {} points when failed", objective.describe(), objective.failPoints());
+        assert!(errors(&issues).iter().any(|i| { i.file == GameData::STARTING_OBJECTIVES && i.message == expected }));
+    }
+
+    #[test]
+    fn testImpossibleTags() {
+        assert_eq!(impossibleTag(&ActionTag::MoveEngine(ActionValues::Exact(-1))), None);
+        assert_eq!(impossibleTag(&ActionTag::MoveCertificate(ActionValues::Max)), None);
+        assert!(impossibleTag(&ActionTag::MoveCattleman(ActionValues::Exact(-1))).is_some());
+        assert!(impossibleTag(&ActionTag::TakeCoins(ActionValues::Max)).is_some());
+        assert!(impossibleTag(&ActionTag::MoveEngine(ActionValues::AtMost(-1))).is_some());
+    }
+}
@@ -0,0 +1,253 @@
+use rand::Rng;
+use rand::prelude::SliceRandom;
+use serde::{Deserialize, Serialize};
+
+use crate::{Engine, GameState};
+use crate::buildings::{Building, Hazard};
+use crate::deck::{Card, Cow, Deck, Objective};
+use crate::player::{Employee, Player, PlayerBoard};
+use crate::rules::{Pending, Phase};
+use crate::tiles::{Foresight, JobMarket, Tile};
+use crate::tracks::{Space, StationMaster, StationTrack};
+
+// A deck as one seat sees it, the unseen cards are only known as counts
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+pub struct DeckView {
+    // Only the deck's owner sees the hand
+    pub hand: Option<Vec<Card>>,
+    pub hand_len: usize,
+    pub hand_size: usize,
+    pub discard: Vec<Card>,
+    // The draw pile, and for opponents their hand as well
+    pub unseen: Vec<(Card, u32)>,
+}
+
+impl DeckView {
+    pub fn new(deck: &Deck, show_hand: bool) -> DeckView {
+        DeckView {
+            hand: if show_hand { Some(deck.hand().clone()) } else { None },
+            hand_len: deck.hand().len(),
+            hand_size: deck.handSize(),
+            discard: deck.discardPile().clone(),
+            unseen: deck.unseenCounts(!show_hand),
+        }
+    }
+
+    // 0 when the hand is hidden
+    pub fn handValue(&self) -> u32 {
+        self.hand.as_deref().map(Deck::cardsValue).unwrap_or(0)
+    }
+
+    // Every objective card in the deck that can be seen or counted, wherever it is
+    pub fn objectiveCards(&self) -> Vec<Objective> {
+        let unseen = self.unseen.iter().flat_map(|(card, n)| { std::iter::repeat_n(card, *n as usize) });
+        self.hand.iter().flatten().chain(self.discard.iter()).chain(unseen)
+            .filter_map(|card| { if let Card::ObjectiveCard(o) = card { Some(*o) } else { None } })
+            .collect()
+    }
+}
+
+// Everything on a player's board, which is public apart from their deck
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+pub struct SeatView {
+    pub id: u32,
+    pub dollars: u32,
+    pub cattleman_pos: usize,
+    pub hired: [u32; 3],
+    pub deck: DeckView,
+    pub played_objectives: Vec<Objective>,
+    pub played_buildings: Vec<usize>,
+    // The buildings not yet placed on the track
+    pub buildings: Vec<Building>,
+    pub hazards: Vec<Hazard>,
+    pub green_tepees: u32,
+    pub blue_tepees: u32,
+    pub certificates: u32,
+    pub station_masters: Vec<StationMaster>,
+    pub job_market_token: bool,
+    pub board: PlayerBoard,
+}
+
+impl SeatView {
+    pub fn new(player: &Player, observer: u32) -> SeatView {
+        SeatView {
+            id: player.id(),
+            dollars: player.dollars(),
+            cattleman_pos: player.cattleManPos(),
+            hired: [player.hired(Employee::Cowboy), player.hired(Employee::Craftsman), player.hired(Employee::Engineer)],
+            deck: DeckView::new(player.deck(), player.id() == observer),
+            played_objectives: player.playedObjectives().clone(),
+            played_buildings: player.playedBuildings().clone(),
+            buildings: player.buildings().clone(),
+            hazards: player.hazards().clone(),
+            green_tepees: player.greenTepees(),
+            blue_tepees: player.blueTepees(),
+            certificates: player.certificates(),
+            station_masters: player.stationMasters().clone(),
+            job_market_token: player.hasJobMarketToken(),
+            board: *player.board(),
+        }
+    }
+
+    pub fn hired(&self, emp: Employee) -> u32 { self.hired[emp as usize] }
+}
+
+// A redacted copy of the game for one seat, with the order of every deck and pile and the other players' hands
+// left out. This is what gets sent to anyone outside the engine
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Debug)]
+pub struct Observation {
+    pub seat: u32,
+    pub current_player: usize,
+    pub state: GameState,
+    pub phase: Phase,
+    pub pending: Vec<Vec<Pending>>,
+    pub players: Vec<SeatView>,
+    pub track: Vec<Space>,
+    pub station_track: StationTrack,
+    pub job_market: JobMarket,
+    pub cow_market: Vec<Cow>,
+    pub cow_deck_size: usize,
+    pub objective_market: Vec<Objective>,
+    pub objective_deck_size: usize,
+    pub foresight_current: [Tile; Foresight::FORESIGHT_SIZE],
+    pub foresight_next: [Tile; Foresight::FORESIGHT_SIZE],
+    pub foresight_pile_sizes: [usize; Foresight::FORESIGHT_SIZE],
+}
+
+// What one seat sees of the game when it's asked to choose an action. Opponents are only visible through
+// SeatView, and the hidden cards and tiles only through determinize
+#[derive(Copy, Clone, Debug)]
+pub struct PlayerView<'a> {
+    seat: u32,
+    engine: &'a Engine,
+}
+
+impl<'a> PlayerView<'a> {
+    pub fn new(engine: &'a Engine, seat: u32) -> PlayerView<'a> {
+        PlayerView { seat, engine }
+    }
+
+    pub fn seat(&self) -> u32 { self.seat }
+
+    // The seat's own player, with its hand but only counts of its draw pile
+    pub fn me(&self) -> SeatView { self.seatView(self.seat) }
+
+    pub fn playerCount(&self) -> usize { self.engine.players().len() }
+
+    pub fn seatView(&self, seat: u32) -> SeatView { SeatView::new(&self.engine.players()[seat as usize], self.seat) }
+
+    pub fn state(&self) -> &'a GameState { self.engine.state() }
+
+    pub fn currentPlayer(&self) -> usize { self.engine.currentPlayer() }
+
+    pub fn phase(&self) -> Phase { self.engine.phase() }
+
+    pub fn pending(&self) -> &'a Vec<Vec<Pending>> { self.engine.pending() }
+
+    pub fn track(&self) -> &'a Vec<Space> { self.engine.track() }
+
+    pub fn stationTrack(&self) -> &'a StationTrack { self.engine.stationTrack() }
+
+    pub fn jobMarket(&self) -> &'a JobMarket { self.engine.jobMarket() }
+
+    // The cows on display, the cow deck is hidden
+    pub fn cowMarket(&self) -> Vec<Cow> { self.engine.cowMarket().cows() }
+
+    pub fn objectiveMarket(&self) -> &'a Vec<Objective> { self.engine.objectiveMarket() }
+
+    // Only the face up tiles, the piles are hidden
+    pub fn foresightCurrent(&self) -> &'a [Tile; Foresight::FORESIGHT_SIZE] { self.engine.foresight().current() }
+
+    pub fn foresightNext(&self) -> &'a [Tile; Foresight::FORESIGHT_SIZE] { self.engine.foresight().next() }
+
+    pub fn observe(&self) -> Observation {
+        let engine = self.engine;
+        Observation {
+            seat: self.seat,
+            current_player: engine.currentPlayer(),
+            state: engine.state().clone(),
+            phase: engine.phase(),
+            pending: engine.pending().clone(),
+            players: (0..engine.players().len()).map(|seat| { self.seatView(seat as u32) }).collect(),
+            track: engine.track().clone(),
+            station_track: engine.stationTrack().clone(),
+            job_market: engine.jobMarket().clone(),
+            cow_market: engine.cowMarket().cows(),
+            cow_deck_size: engine.cowMarket().deckSize(),
+            objective_market: engine.objectiveMarket().clone(),
+            objective_deck_size: engine.objectives.len(),
+            foresight_current: *engine.foresight().current(),
+            foresight_next: *engine.foresight().next(),
+            foresight_pile_sizes: engine.foresight().pileSizes(),
+        }
+    }
+
+    // A full game state consistent with everything this seat has seen, with the hidden cards and tiles
+    // dealt at random: draw piles, the other players' hands, the foresight piles and the cow and objective decks
+    pub fn determinize<R: Rng>(&self, rng: &mut R) -> Engine {
+        let mut engine = self.engine.clone();
+        engine.keepHistory(false);
+        engine.keepHash(false);
+        self.redeal(&mut engine, rng);
+        engine
+    }
+
+    // Deals the hidden cards and tiles of a determinized engine again, once it's been undone back to this view
+    pub fn redeal<R: Rng>(&self, engine: &mut Engine, rng: &mut R) {
+        for player in engine.players.iter_mut() {
+            let hide_hand = player.id() != self.seat;
+            player.deckMut().determinize(rng, hide_hand);
+        }
+        engine.foresight.shufflePiles(rng);
+        engine.cows.shuffleDeck(rng);
+        engine.objectives.shuffle(rng);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_pcg::Pcg64;
+
+    use crate::data::GameData;
+
+    use super::*;
+
+    #[test]
+    fn testObservationHidesSecrets() {
+        let data = GameData::embedded().unwrap();
+        let engine = Engine::newGame(&data, 3, 7).unwrap();
+        let observation = PlayerView::new(&engine, 0).observe();
+        assert_eq!(observation.players[0].deck.hand.as_ref(), Some(engine.players()[0].deck().hand()));
+        assert_eq!(observation.players[1].deck.hand, None);
+        let unseen: u32 = observation.players[1].deck.unseen.iter().map(|(_, n)| { n }).sum();
+        assert_eq!(unseen as usize, engine.players()[1].deck().cowCards().len());
+
+        // Reshuffling everything seat 0 can't see doesn't change what it observes
+        let mut rng = Pcg64::seed_from_u64(1);
+        let mut shuffled = engine.clone();
+        for player in shuffled.players.iter_mut() {
+            let hide_hand = player.id() != 0;
+            player.deckMut().determinize(&mut rng, hide_hand);
+        }
+        shuffled.foresight.shufflePiles(&mut rng);
+        shuffled.cows.shuffleDeck(&mut rng);
+        shuffled.objectives.shuffle(&mut rng);
+        assert_ne!(engine, shuffled);
+        assert_eq!(observation, PlayerView::new(&shuffled, 0).observe());
+    }
+    #[test]
+    fn testOwnDrawOrderHidden() {
+        let data = GameData::embedded().unwrap();
+        let engine = Engine::newGame(&data, 2, 7).unwrap();
+        // Only seat 0's draw pile and deck rng change, which is all its own player would give away
+        let mut rng = Pcg64::seed_from_u64(2);
+        let mut shuffled = engine.clone();
+        shuffled.players[0].deckMut().determinize(&mut rng, false);
+        assert_ne!(engine.players()[0], shuffled.players()[0]);
+        let me = PlayerView::new(&engine, 0).me();
+        assert_eq!(me, PlayerView::new(&shuffled, 0).me());
+        assert_eq!(me.deck.hand.as_ref(), Some(engine.players()[0].deck().hand()));
+        assert_eq!(me.deck.handValue(), engine.players()[0].deck().handValue());
+    }
+}
@@ -1,13 +1,20 @@
+#![allow(non_snake_case)]
+
 #[cfg(test)]
 mod tests {
-    use serde::{Serialize, Deserialize};
-    use std::env;
-    use std::fs;
-
-    use engine::deck::{CowColor, Cow, Objective};
     use engine::buildings::Building;
-    use std::path::Path;
-    use engine::tiles::Foresight;
+
+    #[test]
+    pub fn invalidBuildings() {
+        use engine::error::GwtError;
+        use engine::logical::And;
+
+        assert!(Building::basicBuilding(6).is_ok());
+        assert_eq!(Building::basicBuilding(7), Err(GwtError::InvalidBuilding { num: 7, side_b: None }));
+        assert!(Building::playerBuilding(9, true).is_ok());
+        assert_eq!(Building::playerBuilding(10, true), Err(GwtError::InvalidBuilding { num: 10, side_b: Some(true) }));
+        assert_eq!(And::<u32, 2>::new(&[1, 2, 3]), Err(GwtError::TooManyItems { length: 3, capacity: 2 }));
+    }
 
     #[test]
     pub fn saveDefaultTrack() {
@@ -0,0 +1,84 @@
+#![allow(non_snake_case)]
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+
+    use engine::{Engine, GameState};
+    use engine::agent::{Agent, RandomAgent};
+    use engine::data::GameData;
+    use engine::error::GwtError;
+    use engine::deck::Cow;
+    use engine::player::{Employee, Player};
+    use engine::runner::playGame;
+    use engine::scoring::ScoreSheet;
+    use engine::tiles::JobMarketEvent;
+    use engine::tracks::StationTrack;
+
+    fn twoPlayerEngine() -> Engine {
+        let path = Path::new("./data/player_starting_deck.json");
+        let starting_cows: Vec<Cow> = serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+        let path = Path::new("./data/default_station_track.json");
+        let station_track: StationTrack = serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+        let players = (0..2).map(|i| { Player::new(i, starting_cows.clone(), vec![]) }).collect();
+        Engine::new(players, vec![], station_track)
+    }
+
+    #[test]
+    pub fn finalRound() {
+        let mut engine = twoPlayerEngine();
+        // Player 0 fills all but the last slot, then player 1 fills the last one
+        for _ in 0..23 {
+            assert_ne!(engine.addEmployee(Employee::Cowboy), JobMarketEvent::FinalRound);
+        }
+        engine.endTurn();
+        assert_eq!(engine.currentPlayer(), 1);
+        assert_eq!(engine.addEmployee(Employee::Cowboy), JobMarketEvent::FinalRound);
+        assert_eq!(*engine.state(), GameState::FinalRound { triggered_by: 1 });
+        assert!(engine.players()[1].hasJobMarketToken());
+        assert!(!engine.players()[0].hasJobMarketToken());
+
+        // Player 0 takes their final turn
+        engine.endTurn();
+        assert_eq!(engine.currentPlayer(), 0);
+        assert!(!engine.isGameOver());
+        engine.endTurn();
+        match engine.state() {
+            GameState::GameOver { scores, winners } => {
+                // 6 and 7 dollars are 1 point each, the job market bonus decides it
+                assert_eq!(scores[1].job_market, ScoreSheet::JOB_MARKET_BONUS);
+                assert_eq!(scores[1].total(), 1 + ScoreSheet::JOB_MARKET_BONUS);
+                assert_eq!(*winners, vec![1]);
+            }
+            state => panic!("Expected the game to be over, found {:?}\n{}", state, engine),
+        }
+    }
+
+    #[test]
+    pub fn newGame() {
+        let data = GameData::embedded().unwrap();
+        for players in 2..=4 {
+            let engine = Engine::newGame(&data, players, 17).unwrap();
+            assert_eq!(engine.players().len(), players as usize);
+            assert_eq!(engine, Engine::newGame(&data, players, 17).unwrap());
+            assert_ne!(engine, Engine::newGame(&data, players, 18).unwrap());
+        }
+        assert_eq!(Engine::newGame(&data, 5, 17), Err(GwtError::InvalidPlayerCount(5)));
+        assert!(Engine::startDefaultGame(3).is_ok());
+    }
+
+    #[test]
+    pub fn randomGames() {
+        let data = GameData::embedded().unwrap();
+        for players in 2..=4 {
+            let agents = || -> Vec<Box<dyn Agent>> {
+                (0..players).map(|i| { Box::new(RandomAgent::new(i)) as Box<dyn Agent> }).collect()
+            };
+            let result = playGame(&data, &mut agents(), 31).unwrap();
+            assert_eq!(result.scores.len(), players as usize);
+            assert!(!result.winners.is_empty());
+            assert_eq!(result, playGame(&data, &mut agents(), 31).unwrap());
+        }
+    }
+}
@@ -0,0 +1,47 @@
+#![allow(non_snake_case)]
+
+#[cfg(test)]
+mod tests {
+    use std::env::temp_dir;
+    use std::fs::remove_file;
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    // Runs the terminal game with the lines as its input and returns what it printed
+    fn play(args: &[&str], lines: &[&str]) -> String {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_gwt"))
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child.stdin.take().unwrap().write_all(format!("{}\n", lines.join("\n")).as_bytes()).unwrap();
+        let output = child.wait_with_output().unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        String::from_utf8(output.stdout).unwrap()
+    }
+
+    // The last view the player at the keyboard was shown
+    fn lastView(output: &str) -> String {
+        let start = output.rfind("== Player").unwrap();
+        let end = output[start..].find("> ").unwrap();
+        output[start..start + end].to_string()
+    }
+
+    #[test]
+    fn testScriptedGame() {
+        let path = temp_dir().join(format!("gwt_smoke_{}.json", std::process::id()));
+        let save = format!("save {}", path.display());
+        let output = play(&["--seed", "4", "--bot", "2:random"], &["1", "1", "1", "undo", "redo", &save, "quit"]);
+        assert!(output.contains("Player 1 moved from space 0 to space "), "{}", output);
+        assert!(output.contains("> Undid "), "{}", output);
+        assert!(output.contains("> Redid "), "{}", output);
+        assert!(output.contains(&format!("Saved to {}", path.display())), "{}", output);
+
+        // The save carries its data and seed, so the loaded game picks up where it was left
+        let loaded = play(&["--load", &path.display().to_string(), "--bot", "2:random"], &["quit"]);
+        remove_file(&path).unwrap();
+        assert_eq!(lastView(&loaded), lastView(&output));
+    }
+}